@@ -1,78 +1,226 @@
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{
-    Data,
-    DeriveInput,
-    Fields,
-    FieldsNamed,
-    // parse::{Parse, ParseStream},
+    Data, DeriveInput, Fields, FieldsNamed,
+    parse::{Parse, ParseStream},
     parse_macro_input,
+    punctuated::Punctuated,
 };
 
-// /// ```rust
-// /// #[derive(Debug, serde::Deserialize)]
-// /// #[skopje::extract(
-// ///     method = HTTP_GET,
-// ///     url = "https://api.binance.com/api/v1/ticker/allBookTickers",
-// /// )]
-// /// #[skopje::load(
-// ///     method = PG_INSERT,
-// ///     client = deadpool_postgres::Pool,
-// ///     obj = self.0
-// /// )]
-// /// pub struct Symbols(pub Vec<Symbol>);
-// /// ```
-// ///
-// /// Above is equivalent to:
-// ///
-// /// ```rust
-// /// #[derive(Debug, serde::Deserialize)]
-// /// pub struct Symbols(pub Vec<Symbol>);
-// ///
-// /// #[skopje::async_trait]
-// /// impl skopje::etl::Extract for Symbols {
-// ///     type Client = skopje::HttpClient;
-// ///     async fn extract(client: Self::Client) -> Result<Self> {
-// ///         let url = "https://api.binance.com/api/v1/ticker/allBookTickers";
-// ///         let data: Self = client.fetch(url).await?;
-// ///         Ok(data)
-// ///     }
-// /// }
-// ///
-// /// #[skopje::async_trait]
-// /// impl skopje::etl::Load for Symbols {
-// ///     type Client = skopje::PgPool;
-// ///     async fn load(&self, client: Self::Client) -> Result<()> {
-// ///         client
-// ///             .insert(super::common_sql::INSERT_SYMBOL, self.0.iter())
-// ///             .await?;
-// ///         Ok(())
-// ///     }
-// /// }
-// /// ```
-// #[proc_macro_attribute]
-// pub fn extract(item: TokenStream, attr: TokenStream) -> TokenStream {
-//     let args = parse_macro_input!(attr as ExtractArgs);
-//     quote! {}.into()
-// }
-
-// struct ExtractArgs<'a> {
-//     method: Option<&'a str>,
-//     url: Option<&'a str>,
-//     path: Option<&'a str>,
-// }
-
-// impl Parse for ExtractArgs<'_> {
-//     fn parse(input: ParseStream) -> syn::Result<Self> {
-//         let mut output = Self {
-//             method: None,
-//             url: None,
-//             path: None,
-//         };
-
-//         Ok(output)
-//     }
-// }
+/// Generate an [`Extract`](crate::etl::Extract) implementation for a newtype over a collection,
+/// fetching it from a URL with [`HttpExtractExt::fetch`](crate::extract::http::HttpExtractExt).
+/// Take the following:
+///
+/// ```rust
+/// #[derive(Debug, serde::Deserialize)]
+/// #[skopje::extract(urls = [
+///     "https://example.com/api/v1/symbols?page=1",
+///     "https://example.com/api/v1/symbols?page=2",
+/// ])]
+/// pub struct Symbols(pub Vec<Symbol>);
+/// ```
+///
+/// Above is equivalent to below:
+///
+/// ```rust
+/// #[derive(Debug, serde::Deserialize)]
+/// pub struct Symbols(pub Vec<Symbol>);
+///
+/// #[skopje::async_trait]
+/// impl skopje::etl::Extract for Symbols {
+///     type Client = skopje::HttpClient;
+///
+///     async fn extract(client: &Self::Client) -> skopje::etl::Result<Self> {
+///         let mut items = std::vec::Vec::new();
+///         let url = "https://example.com/api/v1/symbols?page=1";
+///         let page: Vec<Symbol> =
+///             skopje::extract::http::HttpExtractExt::fetch(client, url.as_ref()).await?;
+///         items.extend(page);
+///         let url = "https://example.com/api/v1/symbols?page=2";
+///         let page: Vec<Symbol> =
+///             skopje::extract::http::HttpExtractExt::fetch(client, url.as_ref()).await?;
+///         items.extend(page);
+///         Ok(Self(items.into_iter().collect()))
+///     }
+/// }
+/// ```
+///
+/// A single `url = "..."` is fetched in the same way. Each URL is fetched in turn, and the
+/// collections are concatenated, in order, into the struct's inner collection.
+///
+/// A URL can be any expression of a string (`&str`, `String`, ...), not only a literal; it's
+/// evaluated on each `extract()`, e.g. `url = format!("{}/symbols", base_url())`, for a host
+/// only known at run time.
+///
+/// The `method` defaults to `HTTP_GET`, as above; with `method = FS_READ`, the collection is
+/// read from a JSON file instead, with [`read_json`](crate::extract::fs::read_json), and the
+/// `Client` is `()`:
+///
+/// ```rust
+/// #[derive(Debug, serde::Deserialize)]
+/// #[skopje::extract(method = FS_READ, path = "data/symbols.json")]
+/// pub struct Symbols(pub Vec<Symbol>);
+/// ```
+///
+/// Like a URL, the `path` can be any expression of a string, evaluated on each `extract()`.
+#[proc_macro_attribute]
+pub fn extract(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as ExtractArgs);
+    let body = parse_macro_input!(item as DeriveInput);
+
+    // Extract the struct name, and the collection it wraps.
+    let struct_name = &body.ident;
+    let inner = match &body.data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => &unnamed.unnamed[0].ty,
+            _ => {
+                return syn::Error::new_spanned(
+                    &body.ident,
+                    "extract can only be used on a newtype over a collection, e.g. `struct Symbols(Vec<Symbol>)`",
+                )
+                .to_compile_error()
+                .into();
+            }
+        },
+        _ => {
+            return syn::Error::new_spanned(&body.ident, "extract can only be used on structs")
+                .to_compile_error()
+                .into();
+        }
+    };
+
+    // Read the collection from a file.
+    if args
+        .method
+        .as_ref()
+        .is_some_and(|method| method == "FS_READ")
+    {
+        let Some(path) = &args.path else {
+            return syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "extract with `method = FS_READ` needs a `path = \"...\"`",
+            )
+            .to_compile_error()
+            .into();
+        };
+        if let Some(url) = args.urls.first() {
+            return syn::Error::new_spanned(
+                url,
+                "extract with `method = FS_READ` takes a `path`, not URLs",
+            )
+            .to_compile_error()
+            .into();
+        }
+
+        return quote! {
+            #body
+
+            #[skopje::async_trait]
+            impl skopje::etl::Extract for #struct_name {
+                type Client = ();
+
+                async fn extract(_: &Self::Client) -> skopje::etl::Result<Self> {
+                    let path = #path;
+                    let items: #inner = skopje::extract::fs::read_json(
+                        std::convert::AsRef::<str>::as_ref(&path),
+                    )
+                    .await?;
+                    std::result::Result::Ok(Self(items))
+                }
+            }
+        }
+        .into();
+    }
+
+    if let Some(path) = &args.path {
+        return syn::Error::new_spanned(path, "a `path` needs `method = FS_READ`")
+            .to_compile_error()
+            .into();
+    }
+    if args.urls.is_empty() {
+        return syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "extract needs a `url = \"...\"`, or `urls = [...]`",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let urls = &args.urls;
+
+    // Return the struct, along with the implementation.
+    quote! {
+        #body
+
+        #[skopje::async_trait]
+        impl skopje::etl::Extract for #struct_name {
+            type Client = skopje::HttpClient;
+
+            async fn extract(client: &Self::Client) -> skopje::etl::Result<Self> {
+                let mut items = std::vec::Vec::new();
+                #(
+                    let url = #urls;
+                    let page: #inner = skopje::extract::http::HttpExtractExt::fetch(
+                        client,
+                        std::convert::AsRef::<str>::as_ref(&url),
+                    )
+                    .await?;
+                    items.extend(page);
+                )*
+                std::result::Result::Ok(Self(items.into_iter().collect()))
+            }
+        }
+    }
+    .into()
+}
+
+/// The arguments of the `extract` attribute.
+#[derive(Default)]
+struct ExtractArgs {
+    /// `method = HTTP_GET` (the default), or `method = FS_READ`.
+    method: Option<syn::Ident>,
+    /// `url = "..."`, or each of `urls = ["...", ...]`; any expression of a string.
+    urls: Vec<syn::Expr>,
+    /// `path = "..."`, of the file read by `FS_READ`; any expression of a string.
+    path: Option<syn::Expr>,
+}
+
+impl Parse for ExtractArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut output = Self::default();
+
+        while !input.is_empty() {
+            let key: syn::Ident = input.parse()?;
+            input.parse::<syn::Token![=]>()?;
+            if key == "method" {
+                let method: syn::Ident = input.parse()?;
+                if method != "HTTP_GET" && method != "FS_READ" {
+                    return Err(syn::Error::new(
+                        method.span(),
+                        "unsupported extract method, expected `HTTP_GET` or `FS_READ`",
+                    ));
+                }
+                output.method = Some(method);
+            } else if key == "path" {
+                output.path = Some(input.parse()?);
+            } else if key == "url" {
+                output.urls.push(input.parse()?);
+            } else if key == "urls" {
+                let content;
+                syn::bracketed!(content in input);
+                let urls = Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated(&content)?;
+                output.urls.extend(urls);
+            } else {
+                return Err(syn::Error::new(key.span(), "unsupported extract argument"));
+            }
+
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+
+        Ok(output)
+    }
+}
 
 /// Provide a like-for-like implementation of ['crate::load::pg::SqlMap`].
 /// Take the following:
@@ -93,16 +241,35 @@ use syn::{
 ///     field1: i64,
 /// }
 ///
+/// impl skopje::load::pg::SqlFields for MyStruct {
+///     fn sql_fields(&self) -> std::vec::Vec<&(dyn skopje::ToSql + std::marker::Sync)> {
+///         let mut fields: std::vec::Vec<&(dyn skopje::ToSql + std::marker::Sync)> =
+///             std::vec::Vec::new();
+///         fields.push(&self.field0);
+///         fields.push(&self.field1);
+///         fields
+///     }
+/// }
+///
 /// impl skopje::load::pg::SqlMap for &MyStruct {
 ///     fn sql_map(&self) -> std::vec::Vec<&(dyn skopje::ToSql + std::marker::Sync)> {
-///         std::vec::Vec::from([
-///             &self.field0,
-///             &self.field1,
-///         ])
+///         skopje::load::pg::SqlFields::sql_fields(*self)
 ///     }
 /// }
 /// ```
-#[proc_macro_derive(SqlMap)]
+///
+/// `Option<T>` fields are mapped like any other field; `None` is written as NULL by both
+/// `insert()` and `copy()`.
+///
+/// A field marked `#[sql(flatten)]`, whose type also derives `SqlMap`, has its own fields
+/// spliced in at its position, i.e. `fields.extend(self.address.sql_fields())`.
+///
+/// A field marked `#[sql(skip)]` is left out altogether, e.g. an `id` generated by the database.
+///
+/// A struct marked `#[sql(table = "...")]` also implements
+/// [`SqlTable`](crate::load::pg::SqlTable), naming its table, and the column of each field (in
+/// the same order, and renamed with `#[sql(rename = "...")]`).
+#[proc_macro_derive(SqlMap, attributes(sql))]
 pub fn derive_sql_map(item: TokenStream) -> TokenStream {
     let body = parse_macro_input!(item as DeriveInput);
 
@@ -118,19 +285,547 @@ pub fn derive_sql_map(item: TokenStream) -> TokenStream {
         _ => panic!("SqlMap can only be derived for structs"),
     };
 
-    // Create an array of references to each field.
-    let field_refs = fields.iter().map(|field| {
+    let table = match sql_table(&body.attrs) {
+        Ok(table) => table,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    // Push a reference to each field, or extend with a flattened field's own fields.
+    let mut field_refs = Vec::new();
+    let mut columns = Vec::new();
+    for field in fields {
         let field_name = &field.ident;
-        quote! { &self.#field_name }
+        let attrs = match SqlFieldAttrs::parse(field) {
+            Ok(attrs) => attrs,
+            Err(e) => return e.to_compile_error().into(),
+        };
+        if attrs.skip {
+            continue;
+        }
+        if attrs.flatten {
+            if table.is_some() {
+                return syn::Error::new_spanned(
+                    field,
+                    "#[sql(flatten)] fields can't be used with #[sql(table = \"...\")]",
+                )
+                .to_compile_error()
+                .into();
+            }
+            field_refs.push(quote! {
+                fields.extend(skopje::load::pg::SqlFields::sql_fields(&self.#field_name));
+            });
+            continue;
+        }
+        field_refs.push(quote! { fields.push(&self.#field_name); });
+        columns.push(match attrs.rename {
+            Some(rename) => rename.value(),
+            None => field_name.as_ref().expect("named field").to_string(),
+        });
+    }
+
+    // The table & column names, if the struct names its table.
+    let sql_table = table.map(|table| {
+        quote! {
+            impl skopje::load::pg::SqlTable for #struct_name {
+                const TABLE: &'static str = #table;
+                const COLUMNS: &'static [&'static str] = &[#(#columns),*];
+            }
+        }
     });
 
     // Return the implementation.
     quote! {
+        impl skopje::load::pg::SqlFields for #struct_name {
+            fn sql_fields(&self) -> std::vec::Vec<&(dyn skopje::ToSql + std::marker::Sync)> {
+                let mut fields: std::vec::Vec<&(dyn skopje::ToSql + std::marker::Sync)> =
+                    std::vec::Vec::new();
+                #(#field_refs)*
+                fields
+            }
+        }
+
         impl skopje::load::pg::SqlMap for &#struct_name {
             fn sql_map(&self) -> std::vec::Vec<&(dyn skopje::ToSql + std::marker::Sync)> {
-                vec![#(#field_refs),*]
-             }
+                skopje::load::pg::SqlFields::sql_fields(*self)
+            }
         }
+
+        #sql_table
     }
     .into()
 }
+
+/// The `#[sql(...)]` attributes of a field, shared by the `SqlMap`, `SqlTypes` & `FromRow` derives.
+#[derive(Default)]
+struct SqlFieldAttrs {
+    /// `#[sql(type = ...)]`: the Postgres type of the field.
+    sql_type: Option<syn::Ident>,
+    /// `#[sql(flatten)]`: splice in the nested struct's own fields.
+    flatten: bool,
+    /// `#[sql(type_name = "...")]`: the name of a Postgres type resolved at runtime, e.g. an enum.
+    type_name: Option<syn::LitStr>,
+    /// `#[sql(skip)]`: leave the field out, e.g. a database-generated identity column.
+    skip: bool,
+    /// `#[sql(rename = "...")]`: the name of the field's column, if not the field's own name.
+    rename: Option<syn::LitStr>,
+}
+
+impl SqlFieldAttrs {
+    fn parse(field: &syn::Field) -> syn::Result<Self> {
+        let mut attrs = Self::default();
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("sql"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("type") {
+                    attrs.sql_type = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("flatten") {
+                    attrs.flatten = true;
+                    Ok(())
+                } else if meta.path.is_ident("type_name") {
+                    attrs.type_name = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("skip") {
+                    attrs.skip = true;
+                    Ok(())
+                } else if meta.path.is_ident("rename") {
+                    attrs.rename = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported sql attribute"))
+                }
+            })?;
+        }
+        Ok(attrs)
+    }
+}
+
+/// The struct-level `#[sql(table = "...")]` attribute, if there is one.
+fn sql_table(attrs: &[syn::Attribute]) -> syn::Result<Option<syn::LitStr>> {
+    let mut table = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("sql")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                table = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported sql attribute"))
+            }
+        })?;
+    }
+    Ok(table)
+}
+
+/// Provide an implementation of [`crate::transform::Remap`], building the struct from a
+/// loosely-typed `serde_json::Value`, with each field read from its key.
+/// Take the following:
+///
+/// ```rust
+/// #[derive(Remap)]
+/// struct MyStruct {
+///     #[remap(from = "t")]
+///     field0: String,
+///     #[serde(rename = "f1")]
+///     field1: f64,
+///     #[remap(from = "/nested/f2")]
+///     field2: Option<i64>,
+///     field3: bool,
+/// }
+/// ```
+///
+/// Above is equivalent to below:
+///
+/// ```rust
+/// impl skopje::transform::Remap for MyStruct {
+///     fn remap(value: &skopje::serde_json::Value) -> skopje::Result<Self> {
+///         Ok(Self {
+///             field0: skopje::transform::remap_field(value, "t")?,
+///             field1: skopje::transform::remap_field(value, "f1")?,
+///             field2: skopje::transform::remap_field(value, "/nested/f2")?,
+///             field3: skopje::transform::remap_field(value, "field3")?,
+///         })
+///     }
+/// }
+/// ```
+///
+/// A field's key is given by `#[remap(from = "...")]` (a key starting with `/` is a JSON
+/// Pointer, into nested objects), else any `#[serde(rename = "...")]`, else its own name.
+#[proc_macro_derive(Remap, attributes(remap, serde))]
+pub fn derive_remap(item: TokenStream) -> TokenStream {
+    let body = parse_macro_input!(item as DeriveInput);
+
+    // Extract the struct name.
+    let struct_name = &body.ident;
+
+    // Extract fields.
+    let fields = match &body.data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(FieldsNamed { named, .. }) => named,
+            _ => panic!("Remap can only be derived for structs with named fields"),
+        },
+        _ => panic!("Remap can only be derived for structs"),
+    };
+
+    // Read each field from its key.
+    let mut field_reads = Vec::new();
+    for field in fields {
+        let field_name = field.ident.as_ref().expect("named field");
+        let from = match remap_from(field) {
+            Ok(Some(from)) => from.value(),
+            Ok(None) => field_name.to_string(),
+            Err(e) => return e.to_compile_error().into(),
+        };
+        field_reads.push(quote! {
+            #field_name: skopje::transform::remap_field(value, #from)?
+        });
+    }
+
+    // Return the implementation.
+    quote! {
+        impl skopje::transform::Remap for #struct_name {
+            fn remap(value: &skopje::serde_json::Value) -> skopje::Result<Self> {
+                std::result::Result::Ok(Self { #(#field_reads),* })
+            }
+        }
+    }
+    .into()
+}
+
+/// The key a `Remap` field is read from: its `#[remap(from = "...")]`, else its
+/// `#[serde(rename = "...")]` (or `#[serde(rename(deserialize = "..."))]`), if any.
+fn remap_from(field: &syn::Field) -> syn::Result<Option<syn::LitStr>> {
+    let mut from = None;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("remap"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("from") {
+                from = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported remap attribute"))
+            }
+        })?;
+    }
+    if from.is_some() {
+        return Ok(from);
+    }
+
+    let mut rename = None;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") && meta.input.peek(syn::Token![=]) {
+                rename = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("rename") {
+                meta.parse_nested_meta(|nested| {
+                    if nested.path.is_ident("deserialize") {
+                        rename = Some(nested.value()?.parse()?);
+                    } else {
+                        skip_meta(&nested)?;
+                    }
+                    Ok(())
+                })?;
+            } else {
+                // Any other serde attribute is serde's own business.
+                skip_meta(&meta)?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(rename)
+}
+
+/// Consume the value (`= ...`) or list (`(...)`) of an attribute that isn't ours.
+fn skip_meta(meta: &syn::meta::ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(syn::Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(|nested| skip_meta(&nested))?;
+    }
+    Ok(())
+}
+
+/// Provide an implementation of [`crate::extract::pg::FromRow`].
+/// Take the following:
+///
+/// ```rust
+/// #[derive(FromRow)]
+/// struct MyStruct {
+///     field0: String,
+///     field1: i64,
+/// }
+/// ```
+///
+/// Above is equivalent to below:
+///
+/// ```rust
+/// struct MyStruct {
+///     field0: String,
+///     field1: i64,
+/// }
+///
+/// impl skopje::extract::pg::FromRow for MyStruct {
+///     fn from_row(row: &skopje::extract::pg::Row) -> skopje::Result<Self> {
+///         Ok(Self {
+///             field0: row.try_get("field0")?,
+///             field1: row.try_get("field1")?,
+///         })
+///     }
+/// }
+/// ```
+///
+/// Tuple structs are read by column position instead, i.e. `row.try_get(0)?`, and so on.
+///
+/// The `#[sql(...)]` field attributes of the `SqlMap` derive apply here too, so one struct can be
+/// loaded and read back: `#[sql(rename = "...")]` reads the field from that column, `#[sql(skip)]`
+/// leaves it as its `Default`, without reading a column, and `#[sql(flatten)]` reads the nested
+/// struct from the same row.
+#[proc_macro_derive(FromRow, attributes(sql))]
+pub fn derive_from_row(item: TokenStream) -> TokenStream {
+    let body = parse_macro_input!(item as DeriveInput);
+
+    // Extract the struct name.
+    let struct_name = &body.ident;
+
+    // Read each field by column name, or by position for tuple structs.
+    let construct = match &body.data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(FieldsNamed { named, .. }) => {
+                let mut field_reads = Vec::new();
+                for field in named {
+                    let field_name = field.ident.as_ref().expect("named field");
+                    let attrs = match SqlFieldAttrs::parse(field) {
+                        Ok(attrs) => attrs,
+                        Err(e) => return e.to_compile_error().into(),
+                    };
+                    let ty = &field.ty;
+                    let read = if attrs.skip {
+                        quote! { std::default::Default::default() }
+                    } else if attrs.flatten {
+                        quote! { <#ty as skopje::extract::pg::FromRow>::from_row(row)? }
+                    } else {
+                        let column = match attrs.rename {
+                            Some(rename) => rename.value(),
+                            None => field_name.to_string(),
+                        };
+                        quote! { row.try_get(#column)? }
+                    };
+                    field_reads.push(quote! { #field_name: #read });
+                }
+                quote! { Self { #(#field_reads),* } }
+            }
+            Fields::Unnamed(unnamed) => {
+                // skipped fields take no column, so the rest are read from consecutive positions
+                let mut field_reads = Vec::new();
+                let mut column = 0_usize;
+                for field in &unnamed.unnamed {
+                    let attrs = match SqlFieldAttrs::parse(field) {
+                        Ok(attrs) => attrs,
+                        Err(e) => return e.to_compile_error().into(),
+                    };
+                    if attrs.skip {
+                        field_reads.push(quote! { std::default::Default::default() });
+                    } else {
+                        field_reads.push(quote! { row.try_get(#column)? });
+                        column += 1;
+                    }
+                }
+                quote! { Self(#(#field_reads),*) }
+            }
+            Fields::Unit => panic!("FromRow cannot be derived for unit structs"),
+        },
+        _ => panic!("FromRow can only be derived for structs"),
+    };
+
+    // Return the implementation.
+    quote! {
+        impl skopje::extract::pg::FromRow for #struct_name {
+            fn from_row(row: &skopje::extract::pg::Row) -> skopje::Result<Self> {
+                std::result::Result::Ok(#construct)
+            }
+        }
+    }
+    .into()
+}
+
+/// Provide an implementation of [`crate::load::pg::SqlTypes`], mapping each field's Rust type
+/// to its Postgres [`Type`](crate::Type), in field order.
+/// Take the following:
+///
+/// ```rust
+/// #[derive(SqlTypes)]
+/// struct MyStruct {
+///     field0: String,
+///     field1: Option<i64>,
+///     field2: Vec<i32>,
+///     #[sql(type = VARCHAR)]
+///     field3: String,
+/// }
+/// ```
+///
+/// Above is equivalent to below:
+///
+/// ```rust
+/// impl skopje::load::pg::SqlTypes for MyStruct {
+///     fn sql_types() -> &'static [skopje::Type] {
+///         &[
+///             skopje::Type::TEXT,
+///             skopje::Type::INT8,
+///             skopje::Type::INT4_ARRAY,
+///             skopje::Type::VARCHAR,
+///         ]
+///     }
+/// }
+/// ```
+///
+/// `Option<T>` maps to the type of `T`, and `Vec<T>` (other than `Vec<u8>`, which is `BYTEA`)
+/// to the array type of `T`. Any other type needs its [`Type`](crate::Type) given explicitly,
+/// with `#[sql(type = ...)]`; or, for types without a constant, e.g. a Postgres `ENUM`, its name,
+/// with `#[sql(type_name = "...")]`, left as an
+/// [`unresolved_type`](crate::load::pg::unresolved_type) until the rows are loaded, and it's
+/// resolved against the pool.
+///
+/// Fields marked `#[sql(skip)]` have no type, matching the `SqlMap` derive.
+#[proc_macro_derive(SqlTypes, attributes(sql))]
+pub fn derive_sql_types(item: TokenStream) -> TokenStream {
+    let body = parse_macro_input!(item as DeriveInput);
+
+    // Extract the struct name.
+    let struct_name = &body.ident;
+
+    // Extract fields.
+    let fields = match &body.data {
+        Data::Struct(data_struct) => match &data_struct.fields {
+            Fields::Named(FieldsNamed { named, .. }) => named,
+            _ => panic!("SqlTypes can only be derived for structs with named fields"),
+        },
+        _ => panic!("SqlTypes can only be derived for structs"),
+    };
+
+    // Find the Postgres type of each field; a flattened field contributes all of its types.
+    let mut types = Vec::new();
+    let mut slices = Vec::new();
+    let mut at_runtime = false;
+    for field in fields {
+        let attrs = match SqlFieldAttrs::parse(field) {
+            Ok(attrs) => attrs,
+            Err(e) => return e.to_compile_error().into(),
+        };
+        if attrs.skip {
+            continue;
+        }
+        let ty = &field.ty;
+        if attrs.flatten {
+            at_runtime = true;
+            slices.push(quote! { <#ty as skopje::load::pg::SqlTypes>::sql_types() });
+            continue;
+        }
+        if let Some(type_name) = attrs.type_name {
+            at_runtime = true;
+            slices.push(quote! {
+                &[skopje::load::pg::unresolved_type(#type_name)]
+            });
+            continue;
+        }
+        let sql_type = match attrs.sql_type {
+            Some(sql_type) => sql_type,
+            None => match pg_type_name(ty) {
+                Some(name) => syn::Ident::new(&name, proc_macro2::Span::call_site()),
+                None => {
+                    return syn::Error::new_spanned(
+                        ty,
+                        "no default Postgres type for this field; set one with #[sql(type = ...)]",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+            },
+        };
+        types.push(quote! { skopje::Type::#sql_type });
+        slices.push(quote! { &[skopje::Type::#sql_type] });
+    }
+
+    // A flattened struct's types, and types resolved by name, can't be spliced into a constant
+    // slice, so they are collected once, on first use.
+    let sql_types = if at_runtime {
+        quote! {
+            static TYPES: std::sync::OnceLock<std::vec::Vec<skopje::Type>> =
+                std::sync::OnceLock::new();
+            TYPES.get_or_init(|| [#(#slices as &[skopje::Type]),*].concat())
+        }
+    } else {
+        quote! { &[#(#types),*] }
+    };
+
+    // Return the implementation.
+    quote! {
+        impl skopje::load::pg::SqlTypes for #struct_name {
+            fn sql_types() -> &'static [skopje::Type] {
+                #sql_types
+            }
+        }
+    }
+    .into()
+}
+
+/// The name of the `postgres_types::Type` constant for a Rust type, if it has a default.
+fn pg_type_name(ty: &syn::Type) -> Option<String> {
+    let segment = match ty {
+        syn::Type::Path(path) => path.path.segments.last()?,
+        syn::Type::Reference(reference) => return pg_type_name(&reference.elem),
+        _ => return None,
+    };
+
+    // The single generic argument of `Option<T>` or `Vec<T>`.
+    let inner = || match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first()? {
+            syn::GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    let name = match segment.ident.to_string().as_str() {
+        "Option" => return pg_type_name(inner()?),
+        "Vec" => {
+            let inner = inner()?;
+            if is_ident(inner, "u8") {
+                return Some("BYTEA".to_string());
+            }
+            // Postgres arrays are flat, so nested `Vec`s have no single element type.
+            let element = pg_type_name(inner)?;
+            return (!element.ends_with("_ARRAY")).then(|| format!("{element}_ARRAY"));
+        }
+        "bool" => "BOOL",
+        "i8" => "CHAR",
+        "i16" => "INT2",
+        "i32" => "INT4",
+        "i64" => "INT8",
+        "u32" => "OID",
+        "f32" => "FLOAT4",
+        "f64" => "FLOAT8",
+        "String" | "str" => "TEXT",
+        "Decimal" => "NUMERIC",
+        "NaiveDate" => "DATE",
+        "NaiveTime" => "TIME",
+        "NaiveDateTime" => "TIMESTAMP",
+        "DateTime" => "TIMESTAMPTZ",
+        "Uuid" => "UUID",
+        "Value" => "JSONB",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+fn is_ident(ty: &syn::Type, ident: &str) -> bool {
+    matches!(ty, syn::Type::Path(path) if path.path.is_ident(ident))
+}
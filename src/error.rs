@@ -0,0 +1,162 @@
+/// The error type returned by skopje's extract, load & keymap APIs.
+///
+/// Each variant is a class of failure that callers may want to handle differently, e.g. retrying
+/// on `Http`, but not on `Deserialize`. Anything else is carried in `Other`; and, as a standard
+/// error, a `skopje::Error` converts into an [`anyhow::Error`] with `?`.
+///
+/// ```rust
+/// use skopje::Error;
+///
+/// let deserialize_err: Error = serde_json::from_str::<u32>("\"not a number\"")
+///     .unwrap_err()
+///     .into();
+/// assert!(matches!(deserialize_err, Error::Deserialize(_)));
+///
+/// let http_err: Error = skopje::HttpClient::new()
+///     .get("not a url")
+///     .build()
+///     .unwrap_err()
+///     .into();
+/// assert!(matches!(http_err, Error::Http(_)));
+/// ```
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// A failed HTTP request.
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// A response with an unexpected status, e.g. a `200 OK` to a ranged request, rather than
+    /// `206 Partial Content`.
+    #[error("unexpected HTTP status {status} from {url}")]
+    UnexpectedStatus {
+        url: String,
+        status: reqwest::StatusCode,
+    },
+
+    /// A download (or a chunk of one) that ended at the wrong size.
+    #[error("incomplete download of {url}: expected {expected} bytes, received {received}")]
+    IncompleteDownload {
+        url: String,
+        expected: u64,
+        received: u64,
+    },
+
+    /// A batch of downloads, of which this many failed; each failure is logged as it happens.
+    #[error("{0} file download(s) failed")]
+    DownloadsFailed(usize),
+
+    /// A rate-limited (429) request to the URL, with no retries left in its
+    /// [`crate::extract::http::RetryBudget`].
+    #[error("{0} was rate limited (429), and the retry budget is exhausted")]
+    RetryBudgetExhausted(String),
+
+    /// A URL that could not be parsed.
+    #[error("invalid URL {url:?}: {reason}")]
+    InvalidUrl { url: String, reason: String },
+
+    /// Data that could not be deserialized, e.g. invalid JSON.
+    #[error("deserialization error: {0}")]
+    Deserialize(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    /// Data that could not be serialized, e.g. into MessagePack.
+    #[error("serialization error: {0}")]
+    Serialize(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    /// A failed Postgres query.
+    #[error("postgres error: {0}")]
+    Postgres(#[from] tokio_postgres::Error),
+
+    /// A failure to get a client from a Postgres pool.
+    #[error("postgres pool error: {0}")]
+    Pool(#[from] deadpool_postgres::PoolError),
+
+    /// A call rejected by an open [`crate::pool::CircuitBreaker`], without being attempted; it
+    /// stays open for (about) the given time, or is zero while the breaker's probe is in flight.
+    #[error("circuit breaker open: failing fast for another {0:?}")]
+    CircuitOpen(std::time::Duration),
+
+    /// A failed ClickHouse query.
+    #[cfg(feature = "clickhouse")]
+    #[error("clickhouse error: {0}")]
+    ClickHouse(#[from] clickhouse::error::Error),
+
+    /// A failed Kafka delivery (or client).
+    #[cfg(feature = "kafka")]
+    #[error("kafka error: {0}")]
+    Kafka(#[from] rdkafka::error::KafkaError),
+
+    /// A failure writing (or reading) a Parquet file.
+    #[cfg(feature = "parquet")]
+    #[error("parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    /// A failed object store (S3, GCS or Azure) request.
+    #[cfg(feature = "object-store")]
+    #[error("object store error: {0}")]
+    ObjectStore(#[from] object_store::Error),
+
+    /// A failed WebSocket connection.
+    #[cfg(feature = "websocket")]
+    #[error("websocket error: {0}")]
+    WebSocket(#[from] tokio_tungstenite::tungstenite::Error),
+
+    /// A failed filesystem (or other I/O) operation.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A failure reading a `.zip` archive.
+    #[error("zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    /// Rows whose values don't match the columns they're loaded into.
+    #[error("column count mismatch: expected {expected} columns, found {found}")]
+    ColumnCount { expected: usize, found: usize },
+
+    /// An input skopje has no support for, e.g. a file extension, or a column type.
+    #[error("unsupported: {0}")]
+    Unsupported(String),
+
+    /// A [`crate::KeyMap`] with no keys left to allocate.
+    #[error("keyspace exhausted: no keys left to allocate")]
+    KeyspaceExhausted,
+
+    /// Rows that can't be loaded into a [`crate::KeyMap`] without breaking its bijection, e.g.
+    /// the same value under two keys.
+    #[error("bijection violation: {0}")]
+    BijectionViolation(String),
+
+    /// A [`crate::keymap::KeyMapSnapshot`] taken before keys were persisted, which can't be
+    /// restored without handing those keys out again.
+    #[error("stale snapshot: keys have been persisted since it was taken")]
+    StaleSnapshot,
+
+    /// A load whose rows didn't all arrive: the target's count grew by `counted`, rather than the
+    /// `expected` size of the collection.
+    #[error("load verification failed: expected {expected} new rows, counted {counted}")]
+    LoadVerification { expected: u64, counted: i64 },
+
+    /// Any other error.
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl Error {
+    /// Wrap any deserialization error, e.g. from a non-JSON format.
+    pub fn deserialize(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::Deserialize(err.into())
+    }
+
+    /// Wrap any serialization error.
+    pub fn serialize(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::Serialize(err.into())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::deserialize(err)
+    }
+}
+
+/// A `Result` defaulting to a [`skopje::Error`](Error).
+pub type Result<T, E = Error> = std::result::Result<T, E>;
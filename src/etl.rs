@@ -1,5 +1,11 @@
-use anyhow::Result;
 use async_trait::async_trait;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// The `Result` of the ETL traits; an [`anyhow::Result`].
+pub use anyhow::Result;
 
 /// Marker trait to define what has extract functionality.
 pub trait Extractor {}
@@ -10,6 +16,74 @@ pub trait Loader {}
 impl<T> Loader for T {}
 
 /// Extract the data from some source.
+///
+/// Implementations return an [`anyhow::Result`], so that any error can be raised with `?`,
+/// including a [`crate::Error`] from skopje's own extract methods.
+///
+/// For a collection fetched from one or more URLs, the implementation can be generated with
+/// [`skopje::extract`](crate::extract), concatenating the collection of each URL:
+///
+/// ```rust
+/// use skopje::etl::Extract;
+/// use std::sync::OnceLock;
+/// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+///
+/// // The address of the API, only known once it's listening.
+/// static API: OnceLock<std::net::SocketAddr> = OnceLock::new();
+///
+/// #[skopje::extract(urls = [
+///     format!("http://{}/symbols?page=1", API.get().unwrap()),
+///     format!("http://{}/symbols?page=2", API.get().unwrap()),
+/// ])]
+/// struct Symbols(Vec<String>);
+///
+/// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+/// rt.block_on(async {
+///     // A mock API, serving a page of symbols per request.
+///     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+///     API.set(listener.local_addr().unwrap()).unwrap();
+///     tokio::spawn(async move {
+///         for body in [r#"["AAPL","MSFT"]"#, r#"["NVDA"]"#] {
+///             let (mut stream, _) = listener.accept().await.unwrap();
+///             let mut request = Vec::new();
+///             while !request.ends_with(b"\r\n\r\n") {
+///                 let mut buf = [0; 1024];
+///                 let n = stream.read(&mut buf).await.unwrap();
+///                 request.extend_from_slice(&buf[..n]);
+///             }
+///             let response = format!(
+///                 "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+///                 body.len()
+///             );
+///             stream.write_all(response.as_bytes()).await.unwrap();
+///         }
+///     });
+///
+///     let Symbols(symbols) = Symbols::extract(&skopje::HttpClient::new()).await.unwrap();
+///     assert_eq!(symbols, vec!["AAPL", "MSFT", "NVDA"]);
+/// });
+/// ```
+///
+/// Or, with `method = FS_READ`, from a JSON file:
+///
+/// ```rust
+/// use skopje::etl::Extract;
+/// use std::sync::OnceLock;
+///
+/// // The path of the file, only known once its (unique) directory is created.
+/// static SYMBOLS: OnceLock<String> = OnceLock::new();
+///
+/// #[skopje::extract(method = FS_READ, path = SYMBOLS.get().unwrap())]
+/// struct Symbols(Vec<String>);
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let path = dir.path().join("symbols.json");
+/// std::fs::write(&path, r#"["AAPL","MSFT"]"#).unwrap();
+/// SYMBOLS.set(path.to_str().unwrap().to_string()).unwrap();
+/// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+/// let Symbols(symbols) = rt.block_on(Symbols::extract(&())).unwrap();
+/// assert_eq!(symbols, vec!["AAPL", "MSFT"]);
+/// ```
 #[async_trait]
 pub trait Extract: Sized {
     type Client: Extractor + Send;
@@ -18,6 +92,118 @@ pub trait Extract: Sized {
     async fn extract(client: &Self::Client) -> Result<Self>;
 }
 
+/// Extract only the data which is new since some `Watermark`, e.g. a timestamp or an ID, for
+/// sources that support it (a `?since=` query parameter, or an SQL `WHERE ts > $1`).
+///
+/// Along with the data, implementations return the advanced watermark, to be passed to the next
+/// run.
+///
+/// ```rust
+/// use futures::executor::block_on;
+/// use skopje::async_trait;
+/// use skopje::etl::{Extract, IncrementalExtract};
+///
+/// struct FakeSource {
+///     rows: Vec<(u32, String)>,
+/// }
+///
+/// struct Rows(Vec<(u32, String)>);
+///
+/// #[async_trait]
+/// impl Extract for Rows {
+///     type Client = FakeSource;
+///
+///     async fn extract(client: &FakeSource) -> anyhow::Result<Self> {
+///         Ok(Self(client.rows.clone()))
+///     }
+/// }
+///
+/// #[async_trait]
+/// impl IncrementalExtract for Rows {
+///     type Watermark = u32;
+///
+///     async fn extract_since(client: &FakeSource, since: &u32) -> anyhow::Result<(Self, u32)> {
+///         let rows: Vec<_> = client.rows.iter().filter(|(id, _)| id > since).cloned().collect();
+///         let watermark = rows.iter().map(|(id, _)| *id).max().unwrap_or(*since);
+///         Ok((Self(rows), watermark))
+///     }
+/// }
+///
+/// let source = FakeSource {
+///     rows: vec![(1, "a".to_string()), (2, "b".to_string()), (3, "c".to_string())],
+/// };
+///
+/// let (Rows(rows), watermark) = block_on(Rows::extract_since(&source, &1)).unwrap();
+/// assert_eq!(rows, vec![(2, "b".to_string()), (3, "c".to_string())]);
+/// assert_eq!(watermark, 3);
+///
+/// let (Rows(rows), watermark) = block_on(Rows::extract_since(&source, &watermark)).unwrap();
+/// assert!(rows.is_empty());
+/// assert_eq!(watermark, 3); // unchanged when there's nothing new
+/// ```
+#[async_trait]
+pub trait IncrementalExtract: Extract {
+    type Watermark: Send + Sync;
+
+    /// How is the data since `watermark` extracted?
+    async fn extract_since(
+        client: &Self::Client,
+        watermark: &Self::Watermark,
+    ) -> Result<(Self, Self::Watermark)>;
+}
+
+/// Extract `A` & `B` concurrently, with their own clients, and `combine` them into one record;
+/// e.g. a quote from one endpoint, with its metadata from another. The first error, from
+/// either, is returned.
+///
+/// ```rust
+/// use futures::executor::block_on;
+/// use skopje::async_trait;
+/// use skopje::etl::{Extract, extract_join};
+///
+/// struct Quote(f64);
+/// struct Meta(String);
+///
+/// #[async_trait]
+/// impl Extract for Quote {
+///     type Client = ();
+///
+///     async fn extract(_: &()) -> anyhow::Result<Self> {
+///         Ok(Self(170.5))
+///     }
+/// }
+///
+/// #[async_trait]
+/// impl Extract for Meta {
+///     type Client = &'static str;
+///
+///     async fn extract(exchange: &&'static str) -> anyhow::Result<Self> {
+///         Ok(Self(exchange.to_string()))
+///     }
+/// }
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Listing {
+///     price: f64,
+///     exchange: String,
+/// }
+///
+/// let listing = block_on(extract_join(&(), &"NASDAQ", |Quote(price), Meta(exchange)| {
+///     Listing { price, exchange }
+/// }))
+/// .unwrap();
+/// assert_eq!(listing, Listing { price: 170.5, exchange: "NASDAQ".to_string() });
+/// ```
+pub async fn extract_join<A, B, R, F>(ca: &A::Client, cb: &B::Client, combine: F) -> Result<R>
+where
+    A: Extract,
+    B: Extract,
+    F: FnOnce(A, B) -> R,
+{
+    let (a, b) = futures::try_join!(A::extract(ca), B::extract(cb))?;
+    Ok(combine(a, b))
+}
+
 /// Load the data to some data center.
 #[async_trait]
 pub trait Load {
@@ -26,3 +212,183 @@ pub trait Load {
     /// How is the data loaded?
     async fn load(&self, client: &Self::Client) -> Result<()>;
 }
+
+/// How often a failed pipeline stage is retried, and how long to wait in between; the wait
+/// doubles after each retry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Fail on the first error.
+    pub const NONE: Self = Self {
+        retries: 0,
+        backoff: Duration::ZERO,
+    };
+
+    /// Retry up to `retries` times, waiting `backoff` before the first retry.
+    pub fn new(retries: u32, backoff: Duration) -> Self {
+        Self { retries, backoff }
+    }
+}
+
+/// The retry policy & timeout governing one stage of a [`Pipeline`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StagePolicy {
+    pub retry: RetryPolicy,
+    /// The limit on each attempt; an attempt that times out fails, and may be retried.
+    pub timeout: Option<Duration>,
+}
+
+/// Extract some data `T`, then load it, with each stage governed by its own [`StagePolicy`];
+/// e.g. retrying a flaky extract, while a failing load fails fast.
+///
+/// By default, neither stage is retried, nor timed out.
+///
+/// ```rust
+/// use skopje::async_trait;
+/// use skopje::etl::{Extract, Load, Pipeline, RetryPolicy};
+/// use std::sync::atomic::{AtomicU32, Ordering};
+/// use std::time::Duration;
+///
+/// static EXTRACTS: AtomicU32 = AtomicU32::new(0);
+/// static LOADS: AtomicU32 = AtomicU32::new(0);
+///
+/// struct Prices(Vec<f64>);
+///
+/// #[async_trait]
+/// impl Extract for Prices {
+///     type Client = ();
+///
+///     // fails twice, then succeeds
+///     async fn extract(_: &()) -> anyhow::Result<Self> {
+///         match EXTRACTS.fetch_add(1, Ordering::SeqCst) {
+///             0 | 1 => anyhow::bail!("connection reset"),
+///             _ => Ok(Self(vec![1.5, 2.5])),
+///         }
+///     }
+/// }
+///
+/// #[async_trait]
+/// impl Load for Prices {
+///     type Client = ();
+///
+///     // always fails
+///     async fn load(&self, _: &()) -> anyhow::Result<()> {
+///         LOADS.fetch_add(1, Ordering::SeqCst);
+///         anyhow::bail!("unique violation")
+///     }
+/// }
+///
+/// let pipeline = Pipeline::<Prices>::new()
+///     .extract_retry(RetryPolicy::new(3, Duration::from_millis(1)))
+///     .extract_timeout(Duration::from_secs(5))
+///     .load_retry(RetryPolicy::NONE);
+///
+/// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+/// let result = rt.block_on(pipeline.run(&(), &()));
+///
+/// assert!(result.is_err());
+/// assert_eq!(EXTRACTS.load(Ordering::SeqCst), 3); // succeeded on the 2nd retry
+/// assert_eq!(LOADS.load(Ordering::SeqCst), 1); // never retried
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Pipeline<T> {
+    pub extract: StagePolicy,
+    pub load: StagePolicy,
+    data: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for Pipeline<T> {
+    fn default() -> Self {
+        Self {
+            extract: StagePolicy::default(),
+            load: StagePolicy::default(),
+            data: PhantomData,
+        }
+    }
+}
+
+impl<T> Pipeline<T>
+where
+    T: Extract + Load + Sync,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the retry policy of the extract stage.
+    pub fn extract_retry(mut self, retry: RetryPolicy) -> Self {
+        self.extract.retry = retry;
+        self
+    }
+
+    /// Set the timeout of each extract attempt.
+    pub fn extract_timeout(mut self, timeout: Duration) -> Self {
+        self.extract.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the retry policy of the load stage.
+    pub fn load_retry(mut self, retry: RetryPolicy) -> Self {
+        self.load.retry = retry;
+        self
+    }
+
+    /// Set the timeout of each load attempt.
+    pub fn load_timeout(mut self, timeout: Duration) -> Self {
+        self.load.timeout = Some(timeout);
+        self
+    }
+
+    /// Extract the data with `extract_client`, and load it with `load_client`; returning the
+    /// error of the last attempt of whichever stage failed.
+    pub async fn run(
+        &self,
+        extract_client: &<T as Extract>::Client,
+        load_client: &<T as Load>::Client,
+    ) -> Result<()> {
+        let data = run_stage("extract", self.extract, || T::extract(extract_client)).await?;
+        run_stage("load", self.load, || data.load(load_client)).await
+    }
+}
+
+/// Run one stage, attempting it (again) as its `policy` allows.
+async fn run_stage<O, F, Fut>(stage: &str, policy: StagePolicy, mut attempt: F) -> Result<O>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<O>>,
+{
+    let mut backoff = policy.retry.backoff;
+    let mut retries = 0;
+
+    loop {
+        let outcome = match policy.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, attempt()).await {
+                Ok(outcome) => outcome,
+                Err(_) => Err(anyhow::anyhow!("{stage} timed out after {timeout:?}")),
+            },
+            None => attempt().await,
+        };
+
+        match outcome {
+            Ok(output) => {
+                debug!(stage = stage, retries = retries, "pipeline stage completed");
+                return Ok(output);
+            }
+            Err(e) if retries < policy.retry.retries => {
+                retries += 1;
+                warn!(
+                    stage = stage,
+                    retry = retries,
+                    "pipeline stage failed - retrying: {e}"
+                );
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
@@ -1,9 +1,11 @@
+use crate::Error;
 use rayon::prelude::{IntoParallelIterator, ParallelIterator};
 use std::sync::Arc;
+use tokio::io::AsyncReadExt;
 use tracing::{debug, error, trace};
 
 /// Reads a `.json` file from `path`.
-pub async fn read_json<T: serde::de::DeserializeOwned>(path: &str) -> anyhow::Result<T> {
+pub async fn read_json<T: serde::de::DeserializeOwned>(path: &str) -> crate::Result<T> {
     trace!(filepath=%path, "reading file");
     let file = tokio::fs::read(path).await?;
 
@@ -12,11 +14,431 @@ pub async fn read_json<T: serde::de::DeserializeOwned>(path: &str) -> anyhow::Re
     Ok(data)
 }
 
+/// Reads JSON from an already-open async `reader`, e.g. stdin, a socket, or a decompressor.
+///
+/// ```rust
+/// use futures::executor::block_on;
+/// use skopje::extract::fs::read_json_from;
+///
+/// let reader: &[u8] = br#"{"AAPL": 170, "MSFT": 420}"#;
+/// let data: std::collections::HashMap<String, u32> = block_on(read_json_from(reader)).unwrap();
+/// assert_eq!(data["MSFT"], 420);
+/// ```
+pub async fn read_json_from<T, R>(mut reader: R) -> crate::Result<T>
+where
+    T: serde::de::DeserializeOwned,
+    R: tokio::io::AsyncRead + Unpin,
+{
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).await?;
+
+    trace!("reader read - deserializing bytes");
+    let data: T = serde_json::from_slice(&bytes)?;
+    Ok(data)
+}
+
+/// Reads CSV from an already-open async `reader`, as [`read_json_from`]; each row is
+/// deserialized by its header, as [`read_file`] does for a `.csv` file.
+///
+/// ```rust
+/// use futures::executor::block_on;
+/// use skopje::extract::fs::read_csv_from;
+///
+/// #[derive(serde::Deserialize, Debug, PartialEq)]
+/// struct Price {
+///     symbol: String,
+///     close: f64,
+/// }
+///
+/// let reader: &[u8] = b"symbol,close\nAAPL,170.5\nMSFT,420\n";
+/// let prices: Vec<Price> = block_on(read_csv_from(reader)).unwrap();
+/// assert_eq!(prices[1], Price { symbol: "MSFT".to_string(), close: 420.0 });
+/// ```
+pub async fn read_csv_from<T, R>(mut reader: R) -> crate::Result<T>
+where
+    T: serde::de::DeserializeOwned,
+    R: tokio::io::AsyncRead + Unpin,
+{
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).await?;
+
+    trace!("reader read - deserializing CSV rows");
+    decode_csv(&bytes)
+}
+
+/// Reads a `.json` file from `path`, deserializing only the subtree at the JSON Pointer `pointer`.
+///
+/// See [`crate::util::from_json_pointer`].
+pub async fn read_json_path<T: serde::de::DeserializeOwned>(
+    path: &str,
+    pointer: &str,
+) -> crate::Result<T> {
+    let value: serde_json::Value = read_json(path).await?;
+    crate::util::from_json_pointer(value, pointer)
+}
+
+/// Path at which a raw response from `url`, fetched at `fetched_at`, is archived under
+/// `archive_dir`; see [`crate::extract::http::HttpExtractExt::fetch_archived`].
+///
+/// Each URL has its own directory, named by the URL with every byte other than an ASCII
+/// letter, digit or `-` percent-encoded (so distinct URLs never share a directory), and each
+/// response is named by its timestamp, so that archives sort chronologically.
+///
+/// ```rust
+/// use chrono::{TimeZone, Utc};
+/// use skopje::extract::fs::archive_path;
+///
+/// let fetched_at = Utc.with_ymd_and_hms(2025, 1, 31, 12, 30, 0).unwrap();
+/// let path = archive_path("./archive", "https://api.example.com/v1/prices?symbol=AAPL", fetched_at);
+/// assert_eq!(
+///     path,
+///     "./archive/https%3A%2F%2Fapi%2Eexample%2Ecom%2Fv1%2Fprices%3Fsymbol%3DAAPL/20250131T123000.000000Z.json"
+/// );
+///
+/// // URLs differing only in punctuation are archived apart.
+/// assert_ne!(
+///     archive_path("./archive", "https://api.example.com/v1/a_b", fetched_at),
+///     archive_path("./archive", "https://api.example.com/v1/a/b", fetched_at),
+/// );
+/// ```
+pub fn archive_path(
+    archive_dir: &str,
+    url: &str,
+    fetched_at: chrono::DateTime<chrono::Utc>,
+) -> String {
+    format!(
+        "{}/{}.json",
+        archive_url_dir(archive_dir, url),
+        fetched_at.format("%Y%m%dT%H%M%S%.6fZ")
+    )
+}
+
+/// Directory under `archive_dir` holding the archived responses from `url`.
+fn archive_url_dir(archive_dir: &str, url: &str) -> String {
+    let mut url_dir = String::with_capacity(url.len());
+    for byte in url.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' {
+            url_dir.push(byte as char);
+        } else {
+            url_dir.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    format!("{}/{url_dir}", archive_dir.trim_end_matches('/'))
+}
+
+/// Replay the latest archived response from `url`, under `archive_dir`, as if it was fetched;
+/// see [`crate::extract::http::HttpExtractExt::fetch_archived`].
+pub async fn replay_archived<T: serde::de::DeserializeOwned>(
+    archive_dir: &str,
+    url: &str,
+) -> crate::Result<T> {
+    let dir = archive_url_dir(archive_dir, url);
+
+    // Archives are named by timestamp, so the latest sorts last.
+    let mut latest: Option<String> = None;
+    let mut entries = tokio::fs::read_dir(&dir).await.inspect_err(|e| {
+        error!(url = %url, dir = %dir, "failed to read archive directory: {e}");
+    })?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.ends_with(".json") && latest.as_ref().is_none_or(|latest| name > *latest) {
+            latest = Some(name);
+        }
+    }
+
+    let latest = latest.ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("no archived responses for {url} in {dir}"),
+        )
+    })?;
+    debug!(url = %url, "replaying archived response {dir}/{latest}");
+    read_json(&format!("{dir}/{latest}")).await
+}
+
+/// A serialization format for reading & writing files.
+///
+/// ```rust
+/// use skopje::extract::fs::Codec;
+///
+/// let value = vec![("AAPL".to_string(), 170_u32), ("MSFT".to_string(), 420_u32)];
+/// for codec in [Codec::Json, Codec::MsgPack, Codec::Cbor] {
+///     let bytes = codec.encode(&value).unwrap();
+///     let decoded: Vec<(String, u32)> = codec.decode(&bytes).unwrap();
+///     assert_eq!(decoded, value);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Json,
+    MsgPack,
+    Cbor,
+}
+
+impl Codec {
+    /// Deserialize some bytes in this format.
+    pub fn decode<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> crate::Result<T> {
+        let data: T = match self {
+            Codec::Json => serde_json::from_slice(bytes)?,
+            Codec::MsgPack => rmp_serde::from_slice(bytes).map_err(Error::deserialize)?,
+            Codec::Cbor => ciborium::from_reader(bytes).map_err(Error::deserialize)?,
+        };
+        Ok(data)
+    }
+
+    /// Serialize a value in this format.
+    pub fn encode<T: serde::Serialize>(&self, value: &T) -> crate::Result<Vec<u8>> {
+        let bytes = match self {
+            Codec::Json => serde_json::to_vec(value).map_err(Error::serialize)?,
+            Codec::MsgPack => rmp_serde::to_vec(value).map_err(Error::serialize)?,
+            Codec::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes).map_err(Error::serialize)?;
+                bytes
+            }
+        };
+        Ok(bytes)
+    }
+}
+
+/// Reads a file from `path`, encoded in the format `codec`.
+pub async fn read_encoded<T: serde::de::DeserializeOwned>(
+    path: &str,
+    codec: Codec,
+) -> crate::Result<T> {
+    trace!(filepath=%path, ?codec, "reading file");
+    let file = tokio::fs::read(path).await?;
+
+    trace!(filepath=%path, ?codec, "file read - deserializing bytes");
+    codec.decode(&file).inspect_err(|e| {
+        error!(filepath=%path, ?codec, "failed to deserialize file: {e}");
+    })
+}
+
+/// Writes `value` to a file at `path`, encoded in the format `codec`.
+///
+/// Parent directories are created, as necessary.
+pub async fn write_encoded<T: serde::Serialize>(
+    path: &str,
+    value: &T,
+    codec: Codec,
+) -> crate::Result<()> {
+    trace!(filepath=%path, ?codec, "serializing value");
+    let bytes = codec.encode(value)?;
+
+    if let Some(dir) = std::path::Path::new(path).parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+
+    trace!(filepath=%path, ?codec, "writing file");
+    tokio::fs::write(path, bytes).await?;
+    Ok(())
+}
+
+/// Reads a file from `path`, choosing its format by extension: `.json`, `.msgpack`, `.cbor`, or
+/// `.csv`; a further `.gz` extension (e.g. `.json.gz`, `.csv.gz`) is decompressed first.
+///
+/// CSV rows are read by their header, with each value read as the type of its field, into a
+/// sequence; so `T` is a collection of rows, e.g. `Vec<Price>`.
+///
+/// ```rust
+/// use skopje::extract::fs::read_file;
+/// use std::io::Write;
+///
+/// #[derive(Debug, PartialEq, serde::Deserialize)]
+/// struct Price {
+///     symbol: String,
+///     close: f64,
+/// }
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let path = dir.path().join("prices.csv.gz");
+///
+/// let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+/// gz.write_all(b"symbol,close\nAAPL,170.5\nMSFT,420\n").unwrap();
+/// std::fs::write(&path, gz.finish().unwrap()).unwrap();
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// let prices: Vec<Price> = runtime.block_on(read_file(path.to_str().unwrap())).unwrap();
+/// assert_eq!(prices[0], Price { symbol: "AAPL".to_string(), close: 170.5 });
+/// assert_eq!(prices[1], Price { symbol: "MSFT".to_string(), close: 420.0 });
+/// ```
+pub async fn read_file<T: serde::de::DeserializeOwned>(path: &str) -> crate::Result<T> {
+    let lowercase = path.to_lowercase();
+    let (name, gzipped) = match lowercase.strip_suffix(".gz") {
+        Some(name) => (name, true),
+        None => (lowercase.as_str(), false),
+    };
+    let extension = std::path::Path::new(name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+
+    trace!(filepath=%path, extension, gzipped, "reading file");
+    let mut bytes = tokio::fs::read(path).await?;
+    if gzipped {
+        let mut decompressed = Vec::new();
+        std::io::Read::read_to_end(
+            &mut flate2::read::GzDecoder::new(&bytes[..]),
+            &mut decompressed,
+        )?;
+        bytes = decompressed;
+    }
+
+    trace!(filepath=%path, extension, "file read - deserializing bytes");
+    let data = match extension {
+        "json" => Codec::Json.decode(&bytes),
+        "msgpack" | "mp" => Codec::MsgPack.decode(&bytes),
+        "cbor" => Codec::Cbor.decode(&bytes),
+        "csv" => decode_csv(&bytes),
+        _ => Err(Error::Unsupported(format!("file extension of {path}"))),
+    };
+    data.inspect_err(|e| error!(filepath=%path, "failed to read file: {e}"))
+}
+
+/// Deserialize CSV rows, by header, into a `T` collection.
+///
+/// Each cell is read as the type of its field, as `csv::Reader::deserialize` reads it, rather
+/// than inferred up-front: text keeps its leading zeros, numbers & booleans are parsed, and an
+/// empty cell is `None`.
+fn decode_csv<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> crate::Result<T> {
+    let mut reader = csv::Reader::from_reader(bytes);
+    let headers = reader.headers().map_err(Error::deserialize)?.clone();
+    let records = reader
+        .records()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(Error::deserialize)?;
+
+    let rows = records.iter().map(|record| {
+        serde::de::value::MapDeserializer::new(headers.iter().zip(record.iter().map(CsvCell)))
+    });
+    T::deserialize(serde::de::value::SeqDeserializer::new(rows)).map_err(Error::deserialize)
+}
+
+/// A CSV cell, deserialized as whichever type is asked of it.
+struct CsvCell<'a>(&'a str);
+
+impl CsvCell<'_> {
+    fn parse<T>(&self) -> Result<T, serde::de::value::Error>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Display,
+    {
+        self.0
+            .trim()
+            .parse()
+            .map_err(|e| serde::de::Error::custom(format!("{:?}: {e}", self.0)))
+    }
+}
+
+impl<'de> serde::de::IntoDeserializer<'de, serde::de::value::Error> for CsvCell<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Forward `deserialize_<ty>` to parsing the cell as `ty`.
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident),* $(,)?) => {
+        $(
+            fn $method<V: serde::de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                visitor.$visit(self.parse()?)
+            }
+        )*
+    };
+}
+
+impl<'de> serde::Deserializer<'de> for CsvCell<'de> {
+    type Error = serde::de::value::Error;
+
+    fn deserialize_any<V: serde::de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_option<V: serde::de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.0.is_empty() {
+            true => visitor.visit_none(),
+            false => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: serde::de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: serde::de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_enum(serde::de::IntoDeserializer::<Self::Error>::into_deserializer(self.0))
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+/// Reads the rows of a Parquet file from `path`; see [`crate::load::parquet::ParquetLoadExt`].
+#[cfg(feature = "parquet")]
+pub async fn read_parquet<T: serde::de::DeserializeOwned>(path: &str) -> crate::Result<Vec<T>> {
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    trace!(filepath=%path, "reading file");
+    let file = bytes::Bytes::from(tokio::fs::read(path).await?);
+
+    trace!(filepath=%path, "file read - deserializing record batches");
+    let reader =
+        ParquetRecordBatchReaderBuilder::try_new(file).and_then(|builder| builder.build())?;
+
+    let mut rows = Vec::new();
+    for batch in reader {
+        let batch = batch.map_err(Error::deserialize)?;
+        let batch_rows: Vec<T> = serde_arrow::from_record_batch(&batch).map_err(|e| {
+            error!(filepath=%path, "failed to deserialize record batch: {e}");
+            Error::deserialize(e)
+        })?;
+        rows.extend(batch_rows);
+    }
+    Ok(rows)
+}
+
 /// Unzip a `.zip` file, `zip_file`, to a target directory, `dir`.
 ///
 /// `std::fs::create_dir_all(to_dir)?` is used in creating `to_dir` path,
 /// so directories will be created, as necessary, by the unzip() function.
-pub async fn unzip(zip_file: &str, dir: &str) -> anyhow::Result<()> {
+pub async fn unzip(zip_file: &str, dir: &str) -> crate::Result<()> {
     debug!("unzipping {zip_file} to {dir}");
 
     // Open the file, but `std::fs` has to be used, instead of tokio.
@@ -45,7 +467,7 @@ pub async fn unzip(zip_file: &str, dir: &str) -> anyhow::Result<()> {
 
         // If output directory does not exist, create it.
         if !outdir.exists() {
-            std::fs::create_dir_all(&outdir).expect("failed to create directory");
+            std::fs::create_dir_all(outdir).expect("failed to create directory");
         }
 
         // Extract the file.
@@ -1,22 +1,274 @@
 #![allow(unused_variables)]
 
-use anyhow::Result;
+use crate::{Error, Result};
 use async_trait::async_trait;
 use bytesize::ByteSize;
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+use serde::Serialize;
 use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::num::NonZeroU32;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::fs::File;
 use tokio::io::{AsyncSeekExt, AsyncWriteExt};
 use tokio::sync::Mutex;
+use tokio::task::{JoinHandle, JoinSet};
 use tracing::{debug, error, trace, warn};
 
 /// Size of each chunk when downloading; currently set to 100MB.
 const CHUNK_SIZE: u64 = 100 * 1024 * 1024; // 100 MegaBytes
 
+/// Maximum number of ranged requests a download is split into, so long as its chunks can grow
+/// to keep to it; see [`chunk_ranges`].
+pub const MAX_CHUNK_REQUESTS: u64 = 64;
+
+/// Maximum size chunks are grown to, to keep to [`MAX_CHUNK_REQUESTS`]; each chunk is buffered
+/// in memory before it's written, so this bounds the memory of a download (chunks are written one
+/// at a time), unless a larger `chunk_size` is asked for.
+pub const MAX_CHUNK_SIZE: u64 = CHUNK_SIZE;
+
+/// Maximum number of pages fetched at once by [`HttpExtractExt::fetch_all_by_count`].
+pub const PAGE_CONCURRENCY: usize = 4;
+
+/// Number of times a chunk download is retried, before failing.
+const MAX_CHUNK_RETRIES: u32 = 3;
+
+/// Initial wait between chunk retries; doubled on each subsequent retry.
+const CHUNK_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
 /// Extension of HTTP data-fetching methods for clients.
 #[async_trait]
 pub trait HttpExtractExt {
     async fn fetch<T: DeserializeOwned>(&self, url: &str) -> Result<T>;
+
+    /// Fetch, returning the response's headers & status alongside the deserialized body, e.g. to
+    /// read a rate-limit or pagination header.
+    ///
+    /// ```rust
+    /// use skopje::extract::http::HttpExtractExt;
+    /// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    ///
+    /// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    /// rt.block_on(async {
+    ///     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    ///     let url = format!("http://{}/prices", listener.local_addr().unwrap());
+    ///     tokio::spawn(async move {
+    ///         let (mut stream, _) = listener.accept().await.unwrap();
+    ///         let mut request = Vec::new();
+    ///         while !request.ends_with(b"\r\n\r\n") {
+    ///             let mut buf = [0; 1024];
+    ///             let n = stream.read(&mut buf).await.unwrap();
+    ///             request.extend_from_slice(&buf[..n]);
+    ///         }
+    ///         let body = "[1.5,2.5]";
+    ///         let response = format!(
+    ///             "HTTP/1.1 200 OK\r\nX-RateLimit-Remaining: 41\r\nContent-Length: {}\r\n\r\n{body}",
+    ///             body.len()
+    ///         );
+    ///         stream.write_all(response.as_bytes()).await.unwrap();
+    ///     });
+    ///
+    ///     let (prices, headers, status): (Vec<f64>, _, _) =
+    ///         skopje::HttpClient::new().fetch_with_meta(&url).await.unwrap();
+    ///     assert_eq!(prices, vec![1.5, 2.5]);
+    ///     assert_eq!(headers["x-ratelimit-remaining"], "41");
+    ///     assert_eq!(status, reqwest::StatusCode::OK);
+    /// });
+    /// ```
+    async fn fetch_with_meta<T: DeserializeOwned>(
+        &self,
+        url: &str,
+    ) -> Result<(T, reqwest::header::HeaderMap, reqwest::StatusCode)>;
+
+    /// Fetch a collection from an endpoint which returns a bare object when there's a single
+    /// result, and an array when there are several; either way, as a `Vec`.
+    ///
+    /// ```rust
+    /// use skopje::extract::http::HttpExtractExt;
+    /// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    ///
+    /// #[derive(serde::Deserialize)]
+    /// struct Quote {
+    ///     symbol: String,
+    /// }
+    ///
+    /// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    /// rt.block_on(async {
+    ///     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    ///     let url = format!("http://{}/quotes", listener.local_addr().unwrap());
+    ///     tokio::spawn(async move {
+    ///         for body in [r#"{"symbol":"AAPL"}"#, r#"[{"symbol":"AAPL"},{"symbol":"MSFT"}]"#] {
+    ///             let (mut stream, _) = listener.accept().await.unwrap();
+    ///             let mut request = Vec::new();
+    ///             while !request.ends_with(b"\r\n\r\n") {
+    ///                 let mut buf = [0; 1024];
+    ///                 let n = stream.read(&mut buf).await.unwrap();
+    ///                 request.extend_from_slice(&buf[..n]);
+    ///             }
+    ///             let response = format!(
+    ///                 "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+    ///                 body.len()
+    ///             );
+    ///             stream.write_all(response.as_bytes()).await.unwrap();
+    ///         }
+    ///     });
+    ///
+    ///     let client = skopje::HttpClient::new();
+    ///     let one: Vec<Quote> = client.fetch_one_or_many(&url).await.unwrap();
+    ///     assert_eq!(one.len(), 1);
+    ///     let many: Vec<Quote> = client.fetch_one_or_many(&url).await.unwrap();
+    ///     assert_eq!(many.len(), 2);
+    ///     assert_eq!(many[1].symbol, "MSFT");
+    /// });
+    /// ```
+    async fn fetch_one_or_many<T: DeserializeOwned>(&self, url: &str) -> Result<Vec<T>>;
+
+    /// Fetch a text response body, decoded by its charset; see [`decode_text`]. An error status
+    /// is an [`Error::Http`], rather than its error page as text.
+    async fn fetch_text(&self, url: &str) -> Result<String>;
+
+    /// POST `form` as an `application/x-www-form-urlencoded` body, e.g. to request an OAuth
+    /// token, deserializing the JSON response; an error status is an [`Error::Http`].
+    ///
+    /// ```rust
+    /// use skopje::extract::http::HttpExtractExt;
+    /// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    ///
+    /// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    /// rt.block_on(async {
+    ///     // A mock token endpoint, which replies once it has read the form.
+    ///     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    ///     let url = format!("http://{}/token", listener.local_addr().unwrap());
+    ///     tokio::spawn(async move {
+    ///         let (mut stream, _) = listener.accept().await.unwrap();
+    ///         let mut request = Vec::new();
+    ///         while !String::from_utf8_lossy(&request).ends_with("scope=read") {
+    ///             let mut buf = [0; 1024];
+    ///             let n = stream.read(&mut buf).await.unwrap();
+    ///             request.extend_from_slice(&buf[..n]);
+    ///         }
+    ///         let request = String::from_utf8(request).unwrap();
+    ///         assert!(request.starts_with("POST /token"));
+    ///         assert!(request.contains("application/x-www-form-urlencoded"));
+    ///         assert!(request.ends_with("grant_type=client_credentials&scope=read"));
+    ///
+    ///         let body = r#"{"access_token":"abc123","expires_in":3600}"#;
+    ///         let response = format!(
+    ///             "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+    ///             body.len()
+    ///         );
+    ///         stream.write_all(response.as_bytes()).await.unwrap();
+    ///     });
+    ///
+    ///     let form = [("grant_type", "client_credentials"), ("scope", "read")];
+    ///     let token: serde_json::Value = skopje::HttpClient::new()
+    ///         .fetch_post_form(&url, &form)
+    ///         .await
+    ///         .unwrap();
+    ///     assert_eq!(token["access_token"], "abc123");
+    /// });
+    /// ```
+    async fn fetch_post_form<T, F>(&self, url: &str, form: &F) -> Result<T>
+    where
+        T: DeserializeOwned,
+        F: Serialize + Sync + ?Sized;
+
+    /// Fetch a (top-level) JSON array, streaming its elements as they arrive, rather than
+    /// buffering the whole response; see [`json_array_stream`].
+    async fn fetch_json_array_stream<T>(&self, url: &str) -> Result<BoxStream<'static, Result<T>>>
+    where
+        T: DeserializeOwned + Send + 'static;
+
+    /// Fetch, falling back to a relaxed JSON5 parse (trailing commas, comments, etc.) if
+    /// the body fails strict JSON deserialization.
+    ///
+    /// Like [`HttpExtractExt::fetch`], a `429 Too Many Requests` is retried; any other error
+    /// status is an [`Error::Http`], and its body isn't parsed.
+    async fn fetch_relaxed<T: DeserializeOwned>(&self, url: &str) -> Result<T>;
+
+    /// Fetch, deserializing only the subtree at the JSON Pointer `pointer`, e.g. `/result/data`.
+    ///
+    /// See [`crate::util::from_json_pointer`].
+    async fn fetch_path<T: DeserializeOwned>(&self, url: &str, pointer: &str) -> Result<T>;
+
+    /// Fetch a protobuf-encoded response body, decoding it into the message `T`.
+    ///
+    /// A `429 Too Many Requests` is retried, as by [`HttpExtractExt::fetch`]; any other error
+    /// status is an [`Error::Http`], since an error page may well decode as a (bogus) message.
+    async fn fetch_protobuf<T: prost::Message + Default>(&self, url: &str) -> Result<T>;
+
+    /// Fetch, archiving the exact raw response body under `archive_dir` before deserializing it,
+    /// so that the run can be replayed offline with [`crate::extract::fs::replay_archived`].
+    ///
+    /// An error status is an [`Error::Http`], and isn't archived. See
+    /// [`crate::extract::fs::archive_path`] for the archive layout.
+    async fn fetch_archived<T: DeserializeOwned>(&self, url: &str, archive_dir: &str) -> Result<T>;
+
+    /// Fetch successive pages, `1, 2, 3, ...`, from the URLs built by `page_url`, concatenating
+    /// their items until a page comes back empty.
+    ///
+    /// If a `deadline` is given, it bounds the whole paginated run; once it passes, no further
+    /// pages are fetched (and any page in flight is abandoned), and the items gathered so far
+    /// are returned.
+    async fn fetch_paginated<T, F>(&self, page_url: F, deadline: Option<Instant>) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned + Send,
+        F: Fn(u32) -> String + Send + Sync;
+
+    /// Fetch every page of a collection whose size is advertised by an `X-Total-Count` header:
+    /// page 1 is fetched first, then the rest of the `ceil(total / size)` pages, concurrently
+    /// (at most [`PAGE_CONCURRENCY`] at a time), concatenated in page order.
+    ///
+    /// Each page is requested with the query parameter `page_param={page}` appended to `url`;
+    /// `size` is the number of items the API serves per page (e.g. as set by a `per_page`
+    /// parameter already in `url`). Without the header, only the first page is returned.
+    ///
+    /// ```rust
+    /// use skopje::extract::http::HttpExtractExt;
+    /// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    ///
+    /// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    /// rt.block_on(async {
+    ///     // A mock API, serving 5 items in pages of 2.
+    ///     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    ///     let url = format!("http://{}/items?per_page=2", listener.local_addr().unwrap());
+    ///     tokio::spawn(async move {
+    ///         loop {
+    ///             let (mut stream, _) = listener.accept().await.unwrap();
+    ///             let mut request = Vec::new();
+    ///             while !request.ends_with(b"\r\n\r\n") {
+    ///                 let mut buf = [0; 1024];
+    ///                 let n = stream.read(&mut buf).await.unwrap();
+    ///                 request.extend_from_slice(&buf[..n]);
+    ///             }
+    ///             let request = String::from_utf8(request).unwrap();
+    ///             let body = match request.split_whitespace().nth(1).unwrap() {
+    ///                 "/items?per_page=2&page=1" => "[1,2]",
+    ///                 "/items?per_page=2&page=2" => "[3,4]",
+    ///                 "/items?per_page=2&page=3" => "[5]",
+    ///                 _ => "[]",
+    ///             };
+    ///             let response = format!(
+    ///                 "HTTP/1.1 200 OK\r\nX-Total-Count: 5\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+    ///                 body.len()
+    ///             );
+    ///             stream.write_all(response.as_bytes()).await.unwrap();
+    ///         }
+    ///     });
+    ///
+    ///     let items: Vec<u32> = skopje::HttpClient::new()
+    ///         .fetch_all_by_count(&url, "page", 2)
+    ///         .await
+    ///         .unwrap();
+    ///     assert_eq!(items, vec![1, 2, 3, 4, 5]);
+    /// });
+    /// ```
+    async fn fetch_all_by_count<T>(&self, url: &str, page_param: &str, size: u64) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned + Send;
+
     async fn download_chunk(
         &self,
         url: &str,
@@ -26,18 +278,376 @@ pub trait HttpExtractExt {
     ) -> Result<()> {
         Ok(())
     }
+    /// Download a file to `path`, in parallel chunks; with the `object-store` feature, `s3://`,
+    /// `gs://` & `az://` URLs are downloaded from their object store instead.
+    ///
+    /// The file is written to `{path}.part`, and only renamed to `path` once it's complete (and
+    /// any `Content-Length` matches its size); so an interrupted download never leaves a
+    /// truncated file at `path`.
+    ///
+    /// ```rust
+    /// use skopje::extract::http::HttpExtractExt;
+    /// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    ///
+    /// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    /// rt.block_on(async {
+    ///     // A mock server, which drops the connection partway through a chunked body.
+    ///     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    ///     let url = format!("http://{}/bulk.csv", listener.local_addr().unwrap());
+    ///     tokio::spawn(async move {
+    ///         let (mut stream, _) = listener.accept().await.unwrap();
+    ///         let mut request = Vec::new();
+    ///         while !request.ends_with(b"\r\n\r\n") {
+    ///             let mut buf = [0; 1024];
+    ///             let n = stream.read(&mut buf).await.unwrap();
+    ///             request.extend_from_slice(&buf[..n]);
+    ///         }
+    ///         let response = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\na,b\n\r\n";
+    ///         stream.write_all(response.as_bytes()).await.unwrap();
+    ///     });
+    ///
+    ///     let dir = std::env::temp_dir().join(format!("skopje-part-{}", std::process::id()));
+    ///     let path = dir.join("bulk.csv").to_string_lossy().to_string();
+    ///
+    ///     let client = skopje::HttpClient::new();
+    ///     assert!(client.download_file(&url, &path).await.is_err());
+    ///     assert!(!std::path::Path::new(&path).exists());
+    ///     assert!(std::path::Path::new(&format!("{path}.part")).exists());
+    ///     std::fs::remove_dir_all(dir).unwrap();
+    /// });
+    /// ```
     async fn download_file(&self, url: &str, path: &str) -> Result<()> {
         Ok(())
     }
+
+    /// Download a file to `path`, unless the remote copy is unchanged since it was last
+    /// downloaded there; for bulk files mirrored on a schedule.
+    ///
+    /// If `path` exists, the request is made conditional, with `If-Modified-Since` its mtime
+    /// (and `If-None-Match` the ETag stored alongside it, at `{path}.etag`); a `304 Not
+    /// Modified` leaves the file untouched. Otherwise, the body is streamed to `path`, via
+    /// `{path}.part` (as [`HttpExtractExt::download_file`]), and only replaces the local copy
+    /// once complete; a body short of its `Content-Length` is an [`Error::IncompleteDownload`].
+    ///
+    /// ```rust
+    /// use skopje::extract::http::{DownloadStatus, HttpExtractExt};
+    /// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    ///
+    /// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    /// rt.block_on(async {
+    ///     // A mock mirror, serving a file with an ETag, then a 304 once it's asked for that ETag.
+    ///     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    ///     let url = format!("http://{}/bulk.csv", listener.local_addr().unwrap());
+    ///     tokio::spawn(async move {
+    ///         for response in [
+    ///             "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: 7\r\nConnection: close\r\n\r\na,b\n1,2",
+    ///             "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n",
+    ///         ] {
+    ///             let (mut stream, _) = listener.accept().await.unwrap();
+    ///             let mut request = Vec::new();
+    ///             while !request.ends_with(b"\r\n\r\n") {
+    ///                 let mut buf = [0; 1024];
+    ///                 let n = stream.read(&mut buf).await.unwrap();
+    ///                 request.extend_from_slice(&buf[..n]);
+    ///             }
+    ///             if response.contains("304") {
+    ///                 let request = String::from_utf8(request).unwrap().to_lowercase();
+    ///                 assert!(request.contains("if-none-match: \"v1\""));
+    ///                 assert!(request.contains("if-modified-since: "));
+    ///             }
+    ///             stream.write_all(response.as_bytes()).await.unwrap();
+    ///         }
+    ///     });
+    ///
+    ///     let dir = std::env::temp_dir().join(format!("skopje-if-modified-{}", std::process::id()));
+    ///     let path = dir.join("bulk.csv").to_string_lossy().to_string();
+    ///     let client = skopje::HttpClient::new();
+    ///
+    ///     let status = client.download_file_if_modified(&url, &path).await.unwrap();
+    ///     assert_eq!(status, DownloadStatus::Downloaded);
+    ///
+    ///     let status = client.download_file_if_modified(&url, &path).await.unwrap();
+    ///     assert_eq!(status, DownloadStatus::NotModified);
+    ///     assert_eq!(std::fs::read_to_string(&path).unwrap(), "a,b\n1,2");
+    ///     std::fs::remove_dir_all(dir).unwrap();
+    /// });
+    /// ```
+    async fn download_file_if_modified(&self, url: &str, path: &str) -> Result<DownloadStatus>;
+
+    /// See [`HttpExtractExt::download_file`]; but in chunks of `chunk_size` bytes, rather than
+    /// 100MB, coalesced as [`chunk_ranges`].
+    ///
+    /// ```rust
+    /// use skopje::extract::http::HttpExtractExt;
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    ///
+    /// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    /// rt.block_on(async {
+    ///     // A mock server of a 10 byte file, counting the ranged requests made of it.
+    ///     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    ///     let url = format!("http://{}/bulk.csv", listener.local_addr().unwrap());
+    ///     let ranges = Arc::new(AtomicUsize::new(0));
+    ///     let counter = ranges.clone();
+    ///     tokio::spawn(async move {
+    ///         let body = b"0123456789";
+    ///         loop {
+    ///             let (mut stream, _) = listener.accept().await.unwrap();
+    ///             let mut request = Vec::new();
+    ///             while !request.ends_with(b"\r\n\r\n") {
+    ///                 let mut buf = [0; 1024];
+    ///                 let n = stream.read(&mut buf).await.unwrap();
+    ///                 request.extend_from_slice(&buf[..n]);
+    ///             }
+    ///             let request = String::from_utf8(request).unwrap().to_lowercase();
+    ///             let range = request.lines().find_map(|line| line.strip_prefix("range: bytes="));
+    ///             let response = match range.and_then(|range| range.split_once('-')) {
+    ///                 Some((start, end)) => {
+    ///                     counter.fetch_add(1, Ordering::SeqCst);
+    ///                     let (start, end): (usize, usize) = (start.parse().unwrap(), end.parse().unwrap());
+    ///                     let chunk = std::str::from_utf8(&body[start..=end]).unwrap();
+    ///                     format!(
+    ///                         "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {start}-{end}/10\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{chunk}",
+    ///                         chunk.len()
+    ///                     )
+    ///                 }
+    ///                 None => "HTTP/1.1 200 OK\r\nContent-Length: 10\r\nConnection: close\r\n\r\n0123456789".to_string(),
+    ///             };
+    ///             stream.write_all(response.as_bytes()).await.unwrap();
+    ///         }
+    ///     });
+    ///
+    ///     let dir = std::env::temp_dir().join(format!("skopje-chunked-{}", std::process::id()));
+    ///     let path = dir.join("bulk.csv").to_string_lossy().to_string();
+    ///     skopje::HttpClient::new().download_file_chunked(&url, &path, 3).await.unwrap();
+    ///
+    ///     // 3 requests, rather than 4, as the trailing byte is coalesced
+    ///     assert_eq!(ranges.load(Ordering::SeqCst), 3);
+    ///     assert_eq!(std::fs::read_to_string(&path).unwrap(), "0123456789");
+    ///     std::fs::remove_dir_all(dir).unwrap();
+    /// });
+    /// ```
+    async fn download_file_chunked(&self, url: &str, path: &str, chunk_size: u64) -> Result<()>;
+
+    /// Download many `(url, path)` jobs, with at most `concurrency` downloads in flight.
+    ///
+    /// Jobs are pulled lazily from `jobs`, so only `concurrency` futures exist at a time, however
+    /// long the list is. Every job is attempted; an error is returned afterwards if any failed.
+    async fn download_files<I>(&self, jobs: I, concurrency: usize) -> Result<()>
+    where
+        I: IntoIterator<Item = (String, String)> + Send,
+        I::IntoIter: Send;
 }
 
 #[async_trait]
 impl HttpExtractExt for reqwest::Client {
     async fn fetch<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
-        let data = get_retry(self, url).await?;
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        let data = get_retry(self, url).await;
+        #[cfg(feature = "metrics")]
+        crate::metrics::record("fetch", started, &data);
+        data
+    }
+
+    async fn fetch_with_meta<T: DeserializeOwned>(
+        &self,
+        url: &str,
+    ) -> Result<(T, reqwest::header::HeaderMap, reqwest::StatusCode)> {
+        let response = send_request(self.get(url), url).await?;
+        let (response_status, headers) = (response.status(), response.headers().clone());
+        let data: T = json_body(url, response).await?;
+
+        Ok((data, headers, response_status))
+    }
+
+    async fn fetch_one_or_many<T: DeserializeOwned>(&self, url: &str) -> Result<Vec<T>> {
+        let value: serde_json::Value = self.fetch(url).await?;
+        let data = match value {
+            serde_json::Value::Array(_) => serde_json::from_value(value),
+            _ => serde_json::from_value(value).map(|one| vec![one]),
+        };
+        data.map_err(|e| {
+            error!(url = %url, "failed to deserialize JSON: {e}");
+            e.into()
+        })
+    }
+
+    async fn fetch_text(&self, url: &str) -> Result<String> {
+        let response = send_request(self.get(url), url).await?;
+
+        let response_status = response.status();
+        trace!("response code: {}", response_status);
+        let response = check_status(url, response)?;
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .map(str::to_string);
+        let body = response.bytes().await?;
+
+        Ok(decode_text(&body, content_type.as_deref()))
+    }
+
+    async fn fetch_post_form<T, F>(&self, url: &str, form: &F) -> Result<T>
+    where
+        T: DeserializeOwned,
+        F: Serialize + Sync + ?Sized,
+    {
+        let response = send_request(self.post(url).form(form), url).await?;
+        json_body(url, check_status(url, response)?).await
+    }
+
+    async fn fetch_json_array_stream<T>(&self, url: &str) -> Result<BoxStream<'static, Result<T>>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let response = send_request(self.get(url), url).await?;
+
+        let response_status = response.status();
+        trace!("response code: {}", response_status);
+        let response = response.error_for_status().map_err(|e| {
+            error!(url = %url, response_status=%response_status, "request failed: {e}");
+            e
+        })?;
+
+        Ok(json_array_stream(response.bytes_stream()).boxed())
+    }
+
+    async fn fetch_relaxed<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let response = send_retry(self, url, None).await?;
+
+        let response_status = response.status();
+        trace!("response code: {}", response_status);
+        let response = check_status(url, response)?;
+        let body = response.bytes().await?;
+
+        // Attempt a strict parse first, and only then fall back to JSON5.
+        match serde_json::from_slice(&body) {
+            Ok(data) => Ok(data),
+            Err(strict_err) => {
+                debug!(url = %url, "strict JSON deserialization failed, retrying as JSON5: {strict_err}");
+                let text = std::str::from_utf8(&body).map_err(Error::deserialize)?;
+                let data: T = json5::from_str(text).map_err(|e| {
+                    error!(url = %url, response_status=%response_status, "failed to deserialize JSON5: {e}");
+                    Error::deserialize(e)
+                })?;
+                Ok(data)
+            }
+        }
+    }
+
+    async fn fetch_path<T: DeserializeOwned>(&self, url: &str, pointer: &str) -> Result<T> {
+        let value: serde_json::Value = get_retry(self, url).await?;
+        crate::util::from_json_pointer(value, pointer)
+    }
+
+    async fn fetch_protobuf<T: prost::Message + Default>(&self, url: &str) -> Result<T> {
+        let response = send_retry(self, url, None).await?;
+
+        let response_status = response.status();
+        trace!("response code: {}", response_status);
+        let response = check_status(url, response)?;
+        let body = response.bytes().await?;
+
+        let data = T::decode(body).map_err(|e| {
+            error!(url = %url, response_status=%response_status, "failed to decode protobuf: {e}");
+            Error::deserialize(e)
+        })?;
+        Ok(data)
+    }
+
+    async fn fetch_archived<T: DeserializeOwned>(&self, url: &str, archive_dir: &str) -> Result<T> {
+        let response = send_request(self.get(url), url).await?;
+
+        let response_status = response.status();
+        trace!("response code: {}", response_status);
+        // Only a successful response is archived, so an error page is never replayed.
+        let response = check_status(url, response)?;
+        let fetched_at = chrono::Utc::now();
+        let body = response.bytes().await?;
+
+        // Archive the raw bytes, before any deserialization can fail.
+        let path = crate::extract::fs::archive_path(archive_dir, url, fetched_at);
+        if let Some(dir) = std::path::Path::new(&path).parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&path, &body).await?;
+        debug!(url = %url, "archived response to {path}");
+
+        let data: T = serde_json::from_slice(&body).map_err(|e| {
+            error!(url = %url, response_status=%response_status, "failed to deserialize JSON: {e}");
+            e
+        })?;
         Ok(data)
     }
 
+    async fn fetch_paginated<T, F>(&self, page_url: F, deadline: Option<Instant>) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned + Send,
+        F: Fn(u32) -> String + Send + Sync,
+    {
+        let mut items = Vec::new();
+
+        for page in 1.. {
+            let url = page_url(page);
+            let fetch = get_retry::<Vec<T>>(self, &url);
+
+            // Bound the page by whatever remains of the deadline.
+            let data = match deadline {
+                Some(deadline) => match tokio::time::timeout_at(deadline.into(), fetch).await {
+                    Ok(data) => data?,
+                    Err(_) => {
+                        warn!(url = %url, page = page, "pagination deadline exceeded - returning {} items", items.len());
+                        break;
+                    }
+                },
+                None => fetch.await?,
+            };
+
+            if data.is_empty() {
+                break;
+            }
+            trace!(url = %url, page = page, "fetched {} items", data.len());
+            items.extend(data);
+        }
+
+        Ok(items)
+    }
+
+    async fn fetch_all_by_count<T>(&self, url: &str, page_param: &str, size: u64) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned + Send,
+    {
+        let first_url = page_url(url, page_param, 1)?;
+        let (mut items, headers, _): (Vec<T>, _, _) = self.fetch_with_meta(&first_url).await?;
+
+        let total = headers
+            .get("x-total-count")
+            .and_then(|total| total.to_str().ok())
+            .and_then(|total| total.trim().parse::<u64>().ok());
+        let Some(total) = total else {
+            warn!(url = %url, "no X-Total-Count header - returning the first page only");
+            return Ok(items);
+        };
+        let pages = total.div_ceil(size.max(1));
+        debug!(url = %url, total = total, pages = pages, "fetching all pages by count");
+
+        // `buffered` keeps the pages in order, however they complete.
+        let mut rest = stream::iter(2..=pages)
+            .map(|page| async move {
+                let url = page_url(url, page_param, page)?;
+                get_retry::<Vec<T>>(self, &url).await
+            })
+            .buffered(PAGE_CONCURRENCY);
+        while let Some(page) = rest.next().await {
+            items.extend(page?);
+        }
+
+        Ok(items)
+    }
+
     async fn download_chunk(
         &self,
         url: &str,
@@ -45,103 +655,1408 @@ impl HttpExtractExt for reqwest::Client {
         end: u64,
         output_file: &mut File,
     ) -> Result<()> {
-        let url = url.to_string();
-        let range = format!("bytes={}-{}", start, end - 1);
+        download_chunk_budgeted(self, url, start, end, output_file, None).await
+    }
 
-        // download a range of bytes
-        let response = self
-            .get(url)
-            .header(reqwest::header::RANGE, range)
-            .send()
-            .await?;
+    async fn download_file(&self, url: &str, path: &str) -> Result<()> {
+        self.download_file_chunked(url, path, CHUNK_SIZE).await
+    }
 
-        // check the response status is 206 Partial Content
-        if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
-            return Err(anyhow::anyhow!(
-                "Failed to download chunk: expected 206 Partial Content, got {}",
-                response.status()
-            ));
-        }
+    async fn download_file_chunked(&self, url: &str, path: &str, chunk_size: u64) -> Result<()> {
+        download_file_limited(self, url, path, chunk_size, None, None).await
+    }
 
-        // seek the position of bytes and write to the file
-        let body = response.bytes().await?;
-        let _seek = output_file.seek(tokio::io::SeekFrom::Start(start)).await?;
-        let _write = output_file.write_all(&body).await?;
+    async fn download_file_if_modified(&self, url: &str, path: &str) -> Result<DownloadStatus> {
+        let etag_path = format!("{path}.etag");
+        let mut request = self.get(url);
 
-        Ok(())
-    }
+        // Make the request conditional on the local copy, if there is one.
+        if let Ok(metadata) = tokio::fs::metadata(path).await {
+            let modified = httpdate::fmt_http_date(metadata.modified()?);
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, modified);
+            if let Ok(etag) = tokio::fs::read_to_string(&etag_path).await {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag.trim());
+            }
+        }
 
-    async fn download_file(&self, url: &str, path: &str) -> Result<()> {
-        // Retrieve the HTTP response.
         trace!("fetching {url}");
-        let response = self.get(url).send().await?;
+        let response = send_request(request, url).await?;
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            debug!(url = %url, "{path} is up to date - skipping download");
+            return Ok(DownloadStatus::NotModified);
+        }
+        let mut response = response.error_for_status()?;
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string);
         let file_size = response
             .headers()
             .get(reqwest::header::CONTENT_LENGTH)
             .and_then(|len| len.to_str().ok())
-            .and_then(|len| len.parse::<u64>().ok())
-            .unwrap_or(0);
+            .and_then(|len| len.parse::<u64>().ok());
 
         // Guarantee the parent directory exists of the target path.
-        let dir = std::path::Path::new(path)
-            .parent()
-            .ok_or_else(|| anyhow::anyhow!("Failed to find parent for download path"))?;
-        tokio::fs::create_dir_all(dir).await?;
+        if let Some(dir) = std::path::Path::new(path).parent()
+            && !dir.as_os_str().is_empty()
+        {
+            tokio::fs::create_dir_all(dir).await?;
+        }
 
-        // Initialise async variables ...
-        let file = Arc::new(Mutex::new(File::create(path).await?));
-        let num_chunks = (file_size + CHUNK_SIZE - 1) / CHUNK_SIZE;
-        let mut tasks = Vec::with_capacity(num_chunks as usize);
-
-        debug!("Downloading {url} in chunks");
-        for i in 0..num_chunks {
-            let start = i * CHUNK_SIZE;
-            let end = std::cmp::min((i + 1) * CHUNK_SIZE, file_size);
-            let url = url.to_string();
-            let file = file.clone();
-            let client = self.clone();
-            tasks.push(tokio::spawn(async move {
-                let mut file = file.lock().await;
-                match client.download_chunk(&url, start, end, &mut file).await {
-                    Ok(_) => trace!(
-                        total_size=%ByteSize(file_size),
-                        "Downloaded chunk: ({start}, {end})",
-                        start=ByteSize(start),
-                        end=ByteSize(end)
-                    ),
-                    Err(e) => eprintln!("Error downloading chunk {}-{}: {}", start, end, e),
+        let part = part_path(path);
+        let mut file = File::create(&part).await?;
+        let mut written = 0;
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        file.flush().await?;
+
+        // Verify the file is complete, before it replaces the local copy.
+        if let Some(file_size) = file_size
+            && written != file_size
+        {
+            return Err(Error::IncompleteDownload {
+                url: url.to_string(),
+                expected: file_size,
+                received: written,
+            });
+        }
+        drop(file); // close the file before it's moved
+        tokio::fs::rename(&part, path).await?;
+        trace!(total_size=%ByteSize(written), "Downloaded {url} to {path}");
+
+        // Keep the ETag for the next conditional request; or forget a stale one.
+        match etag {
+            Some(etag) => tokio::fs::write(&etag_path, etag).await?,
+            None => match tokio::fs::remove_file(&etag_path).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            },
+        }
+
+        Ok(DownloadStatus::Downloaded)
+    }
+
+    async fn download_files<I>(&self, jobs: I, concurrency: usize) -> Result<()>
+    where
+        I: IntoIterator<Item = (String, String)> + Send,
+        I::IntoIter: Send,
+    {
+        let failures = stream::iter(jobs)
+            .map(|(url, path)| async move {
+                let result = self.download_file(&url, &path).await;
+                if let Err(e) = &result {
+                    error!(url = %url, path = %path, "failed to download file: {e}");
                 }
-            }));
+                result
+            })
+            .buffer_unordered(concurrency.max(1))
+            .filter(|result| futures::future::ready(result.is_err()))
+            .count()
+            .await;
+
+        if failures > 0 {
+            return Err(Error::DownloadsFailed(failures));
+        }
+
+        Ok(())
+    }
+}
+
+/// An extension validating JSON responses against a JSON Schema, with the `jsonschema` feature;
+/// kept apart from [`HttpExtractExt`], so that the feature doesn't change that trait's shape.
+#[cfg(feature = "jsonschema")]
+#[async_trait]
+pub trait HttpValidateExt {
+    /// Fetch a JSON response, and validate it against a JSON `schema` before deserializing; so
+    /// a structurally-wrong payload is rejected with the path of its first violation, rather than
+    /// whatever serde trips over first.
+    ///
+    /// ```rust
+    /// use skopje::extract::http::HttpValidateExt;
+    /// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    ///
+    /// #[derive(Debug, serde::Deserialize)]
+    /// struct Quote {
+    ///     symbol: String,
+    ///     price: f64,
+    /// }
+    ///
+    /// let schema = skopje::JsonSchema::new(&serde_json::json!({
+    ///     "type": "array",
+    ///     "items": {
+    ///         "type": "object",
+    ///         "properties": {"symbol": {"type": "string"}, "price": {"type": "number"}},
+    ///         "required": ["symbol", "price"]
+    ///     }
+    /// }))
+    /// .unwrap();
+    ///
+    /// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    /// rt.block_on(async {
+    ///     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    ///     let url = format!("http://{}/quotes", listener.local_addr().unwrap());
+    ///     tokio::spawn(async move {
+    ///         for body in [
+    ///             r#"[{"symbol":"AAPL","price":170.5}]"#,
+    ///             r#"[{"symbol":"AAPL","price":170.5},{"symbol":"MSFT","price":"n/a"}]"#,
+    ///         ] {
+    ///             let (mut stream, _) = listener.accept().await.unwrap();
+    ///             let mut request = Vec::new();
+    ///             while !request.ends_with(b"\r\n\r\n") {
+    ///                 let mut buf = [0; 1024];
+    ///                 let n = stream.read(&mut buf).await.unwrap();
+    ///                 request.extend_from_slice(&buf[..n]);
+    ///             }
+    ///             let response = format!(
+    ///                 "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+    ///                 body.len()
+    ///             );
+    ///             stream.write_all(response.as_bytes()).await.unwrap();
+    ///         }
+    ///     });
+    ///
+    ///     let client = skopje::HttpClient::new();
+    ///     let quotes: Vec<Quote> = client.fetch_validated(&url, &schema).await.unwrap();
+    ///     assert_eq!((quotes[0].symbol.as_str(), quotes[0].price), ("AAPL", 170.5));
+    ///
+    ///     let err = client.fetch_validated::<Vec<Quote>>(&url, &schema).await.unwrap_err();
+    ///     assert!(matches!(err, skopje::Error::Deserialize(_)));
+    ///     assert!(err.to_string().contains("/1/price"));
+    /// });
+    /// ```
+    async fn fetch_validated<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        schema: &jsonschema::Validator,
+    ) -> Result<T>;
+}
+
+#[cfg(feature = "jsonschema")]
+#[async_trait]
+impl HttpValidateExt for reqwest::Client {
+    async fn fetch_validated<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        schema: &jsonschema::Validator,
+    ) -> Result<T> {
+        let value: serde_json::Value = self.fetch(url).await?;
+        if let Err(e) = schema.validate(&value) {
+            let path = e.instance_path().to_string();
+            error!(url = %url, path = %path, "JSON failed schema validation: {e}");
+            return Err(Error::deserialize(format!(
+                "schema violation at {path:?}: {e}"
+            )));
+        }
+        serde_json::from_value(value).map_err(|e| {
+            error!(url = %url, "failed to deserialize JSON: {e}");
+            e.into()
+        })
+    }
+}
+
+/// Parse a stream of bytes, e.g. a response body, as a JSON array, incrementally; each element
+/// is deserialized as soon as it has been read in full, so only one element is held at a time.
+///
+/// The stream ends after the first error, e.g. malformed JSON, or a body that isn't an array.
+///
+/// ```rust
+/// use futures::{StreamExt, executor::block_on, stream};
+/// use skopje::extract::http::json_array_stream;
+///
+/// // chunk boundaries fall anywhere, even inside strings
+/// let chunks = [r#" [{"s": "a,]"}, {"#, r#""s": "b\"}"}"#, r#", {"s""#, r#": "c"}] "#];
+/// let body = stream::iter(chunks.map(Ok::<_, skopje::Error>));
+///
+/// let items: Vec<serde_json::Value> =
+///     block_on(json_array_stream(body).map(Result::unwrap).collect());
+/// assert_eq!(items.len(), 3);
+/// assert_eq!(items[0]["s"], "a,]");
+/// assert_eq!(items[1]["s"], "b\"}");
+///
+/// let unbalanced = stream::iter(["[1, ", "}, 2]"].map(Ok::<_, skopje::Error>));
+/// let items: Vec<skopje::Result<u32>> = block_on(json_array_stream(unbalanced).collect());
+/// assert_eq!(items.len(), 2);
+/// assert!(items[1].is_err());
+///
+/// let not_array = stream::iter([Ok::<_, skopje::Error>(r#"{"s": 1}"#)]);
+/// let items: Vec<skopje::Result<serde_json::Value>> = block_on(json_array_stream(not_array).collect());
+/// assert!(items[0].is_err());
+/// ```
+pub fn json_array_stream<S, B, E, T>(bytes: S) -> impl Stream<Item = Result<T>>
+where
+    S: Stream<Item = std::result::Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: Into<Error>,
+    T: DeserializeOwned,
+{
+    let mut splitter = JsonArraySplitter::default();
+
+    // `None` marks the end of the bytes, to check that the array was closed.
+    let items = bytes
+        .map(Some)
+        .chain(stream::once(async { None }))
+        .flat_map(move |chunk| {
+            let items: Vec<Result<T>> = match chunk {
+                Some(Ok(chunk)) => match splitter.push(chunk.as_ref()) {
+                    Ok(elements) => elements
+                        .iter()
+                        .map(|element| serde_json::from_slice(element).map_err(Error::from))
+                        .collect(),
+                    Err(e) => vec![Err(e)],
+                },
+                Some(Err(e)) => vec![Err(e.into())],
+                None => splitter.finish().err().into_iter().map(Err).collect(),
+            };
+            stream::iter(items)
+        });
+
+    // Stop after the first error.
+    items.scan(false, |failed, item| {
+        if *failed {
+            return futures::future::ready(None);
+        }
+        *failed = item.is_err();
+        futures::future::ready(Some(item))
+    })
+}
+
+/// Splits the bytes of a JSON array into the bytes of its elements, by tracking the nesting of
+/// brackets (outside of strings).
+#[derive(Debug, Default)]
+struct JsonArraySplitter {
+    started: bool,
+    finished: bool,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    element: Vec<u8>,
+}
+
+impl JsonArraySplitter {
+    /// Read the next chunk, returning any elements it completes.
+    fn push(&mut self, chunk: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let mut elements = Vec::new();
+        for &byte in chunk {
+            if self.finished || !self.started {
+                match byte {
+                    _ if byte.is_ascii_whitespace() => {}
+                    b'[' if !self.started => self.started = true,
+                    _ if !self.started => return Err(Error::deserialize("expected a JSON array")),
+                    _ => return Err(Error::deserialize("trailing characters after JSON array")),
+                }
+                continue;
+            }
+
+            if self.in_string {
+                self.element.push(byte);
+                match byte {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match byte {
+                b']' if self.depth == 0 => {
+                    // `[]` has no elements.
+                    if !self.element.is_empty() {
+                        elements.push(std::mem::take(&mut self.element));
+                    }
+                    self.finished = true;
+                }
+                b',' if self.depth == 0 => {
+                    if self.element.is_empty() {
+                        return Err(Error::deserialize("unexpected ',' in JSON array"));
+                    }
+                    elements.push(std::mem::take(&mut self.element));
+                }
+                _ if self.element.is_empty() && byte.is_ascii_whitespace() => {}
+                _ => {
+                    match byte {
+                        b'"' => self.in_string = true,
+                        b'[' | b'{' => self.depth += 1,
+                        b']' | b'}' => {
+                            self.depth = self.depth.checked_sub(1).ok_or_else(|| {
+                                Error::deserialize("unbalanced brackets in JSON array")
+                            })?
+                        }
+                        _ => {}
+                    }
+                    self.element.push(byte);
+                }
+            }
+        }
+        Ok(elements)
+    }
+
+    /// Check that the array was closed.
+    fn finish(&self) -> Result<()> {
+        if self.finished {
+            Ok(())
+        } else {
+            Err(Error::deserialize("unexpected end of JSON array"))
+        }
+    }
+}
+
+/// Decode a text body by its charset: a byte order mark takes precedence, then the `charset` of
+/// the `Content-Type` header, e.g. `text/csv; charset=ISO-8859-1`; otherwise, UTF-8 is assumed.
+///
+/// Malformed sequences are replaced with `U+FFFD`, rather than failing.
+///
+/// ```rust
+/// use skopje::extract::http::decode_text;
+///
+/// let latin1 = b"Caf\xe9 Cr\xe8me";
+/// assert_eq!(decode_text(latin1, Some("text/plain; charset=ISO-8859-1")), "Café Crème");
+///
+/// let utf8_bom = b"\xef\xbb\xbfCaf\xc3\xa9";
+/// assert_eq!(decode_text(utf8_bom, Some("text/plain; charset=ISO-8859-1")), "Café");
+/// assert_eq!(decode_text("Café".as_bytes(), None), "Café");
+/// ```
+pub fn decode_text(body: &[u8], content_type: Option<&str>) -> String {
+    let encoding = content_type
+        .and_then(|content_type| {
+            content_type.split(';').skip(1).find_map(|param| {
+                let (key, value) = param.split_once('=')?;
+                key.trim()
+                    .eq_ignore_ascii_case("charset")
+                    .then(|| value.trim().trim_matches('"'))
+            })
+        })
+        .and_then(|charset| encoding_rs::Encoding::for_label(charset.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+
+    // `decode` sniffs for a BOM first, which overrides the declared encoding.
+    let (text, encoding, malformed) = encoding.decode(body);
+    if malformed {
+        warn!(
+            encoding = encoding.name(),
+            "malformed text replaced while decoding"
+        );
+    }
+    text.into_owned()
+}
+
+/// A [`reqwest::Client`] wrapper sharing a token-bucket rate limiter across every request, e.g.
+/// to stay under a provider's global requests-per-second cap across concurrent extracts.
+///
+/// Each `fetch()`, and each chunk of a `download_file()`, waits for the limiter before its
+/// request is sent; clones share the same limiter.
+///
+/// ```rust
+/// use std::num::NonZeroU32;
+///
+/// let client = skopje::extract::http::RateLimitedClient::new(
+///     skopje::HttpClient::new(),
+///     NonZeroU32::new(2).unwrap(), // 2 requests/s
+/// );
+/// let shared = client.clone();
+/// ```
+#[derive(Clone)]
+pub struct RateLimitedClient {
+    pub client: reqwest::Client,
+    limiter: Arc<DefaultDirectRateLimiter>,
+}
+
+impl std::fmt::Debug for RateLimitedClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimitedClient")
+            .field("client", &self.client)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RateLimitedClient {
+    /// Allow (at most) `per_second` requests each second, in bursts of up to `per_second`.
+    pub fn new(client: reqwest::Client, per_second: NonZeroU32) -> Self {
+        Self::with_quota(client, Quota::per_second(per_second))
+    }
+
+    /// Allow requests at the rate of some [`Quota`], e.g. `Quota::per_minute(..)`.
+    pub fn with_quota(client: reqwest::Client, quota: Quota) -> Self {
+        Self {
+            client,
+            limiter: Arc::new(RateLimiter::direct(quota)),
+        }
+    }
+
+    /// See [`HttpExtractExt::fetch`].
+    pub async fn fetch<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        self.limiter.until_ready().await;
+        self.client.fetch(url).await
+    }
+
+    /// See [`HttpExtractExt::download_chunk`].
+    pub async fn download_chunk(
+        &self,
+        url: &str,
+        start: u64,
+        end: u64,
+        output_file: &mut File,
+    ) -> Result<()> {
+        self.limiter.until_ready().await;
+        self.client
+            .download_chunk(url, start, end, output_file)
+            .await
+    }
+
+    /// See [`HttpExtractExt::download_file`]; the initial request, and every chunk, are limited.
+    pub async fn download_file(&self, url: &str, path: &str) -> Result<()> {
+        download_file_limited(
+            &self.client,
+            url,
+            path,
+            CHUNK_SIZE,
+            Some(self.limiter.clone()),
+            None,
+        )
+        .await
+    }
+}
+
+/// A budget of retries, shared by every request of a [`RetryBudgetClient`]; so that when an
+/// upstream starts failing, the client as a whole backs off, instead of each call retrying on
+/// its own.
+///
+/// The budget holds up to `capacity` retries, and starts full. Each retry spends one, and each
+/// successful request earns back `refill` of one (e.g. `0.1`, a retry for every 10 successes);
+/// once it's spent, requests fail instead of retrying.
+///
+/// ```rust
+/// use skopje::extract::http::RetryBudget;
+///
+/// let budget = RetryBudget::new(2, 0.5);
+/// assert!(budget.try_withdraw());
+/// assert!(budget.try_withdraw());
+/// assert!(!budget.try_withdraw()); // exhausted
+///
+/// budget.deposit();
+/// assert!(!budget.try_withdraw()); // half a retry isn't enough
+/// budget.deposit();
+/// assert!(budget.try_withdraw());
+/// ```
+#[derive(Debug)]
+pub struct RetryBudget {
+    tokens: std::sync::Mutex<f64>,
+    capacity: f64,
+    refill: f64,
+}
+
+impl RetryBudget {
+    /// A full budget of `capacity` retries, earning back `refill` of one per success.
+    pub fn new(capacity: u32, refill: f64) -> Self {
+        Self {
+            tokens: std::sync::Mutex::new(f64::from(capacity)),
+            capacity: f64::from(capacity),
+            refill: refill.max(0.0),
+        }
+    }
+
+    /// Spend one retry, if there's one left; returns whether it was.
+    pub fn try_withdraw(&self) -> bool {
+        let mut tokens = self.tokens.lock().expect("retry budget lock poisoned");
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Earn back part of a retry, after a successful request.
+    pub fn deposit(&self) {
+        let mut tokens = self.tokens.lock().expect("retry budget lock poisoned");
+        *tokens = (*tokens + self.refill).min(self.capacity);
+    }
+
+    /// The number of whole retries left.
+    pub fn remaining(&self) -> u32 {
+        *self.tokens.lock().expect("retry budget lock poisoned") as u32
+    }
+}
+
+/// A [`reqwest::Client`] whose `fetch()` & `download_file()` retries are all drawn from one
+/// [`RetryBudget`]; like a [`RateLimitedClient`], it's cheap to clone, and clones share it.
+///
+/// ```rust
+/// use skopje::extract::http::{RetryBudget, RetryBudgetClient};
+/// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+///
+/// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+/// rt.block_on(async {
+///     // A mock upstream, rate limiting every request.
+///     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+///     let url = format!("http://{}/prices", listener.local_addr().unwrap());
+///     tokio::spawn(async move {
+///         loop {
+///             let (mut stream, _) = listener.accept().await.unwrap();
+///             let mut request = Vec::new();
+///             while !request.ends_with(b"\r\n\r\n") {
+///                 let mut buf = [0; 1024];
+///                 let n = stream.read(&mut buf).await.unwrap();
+///                 request.extend_from_slice(&buf[..n]);
+///             }
+///             let response = "HTTP/1.1 429 Too Many Requests\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+///             stream.write_all(response.as_bytes()).await.unwrap();
+///         }
+///     });
+///
+///     // With the budget spent, the 429 fails straight away, rather than waiting to retry.
+///     let client = RetryBudgetClient::new(skopje::HttpClient::new(), RetryBudget::new(0, 0.1));
+///     let result = client.fetch::<Vec<f64>>(&url).await;
+///     assert!(result.unwrap_err().to_string().contains("retry budget is exhausted"));
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryBudgetClient {
+    pub client: reqwest::Client,
+    budget: Arc<RetryBudget>,
+}
+
+impl RetryBudgetClient {
+    pub fn new(client: reqwest::Client, budget: RetryBudget) -> Self {
+        Self {
+            client,
+            budget: Arc::new(budget),
+        }
+    }
+
+    /// The budget shared by this client, and its clones.
+    pub fn budget(&self) -> &RetryBudget {
+        &self.budget
+    }
+
+    /// See [`HttpExtractExt::fetch`].
+    pub async fn fetch<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        get_retry_budgeted(&self.client, url, Some(&self.budget)).await
+    }
+
+    /// See [`HttpExtractExt::download_file`]; every chunk's retries are drawn from the budget.
+    pub async fn download_file(&self, url: &str, path: &str) -> Result<()> {
+        download_file_limited(
+            &self.client,
+            url,
+            path,
+            CHUNK_SIZE,
+            None,
+            Some(self.budget.clone()),
+        )
+        .await
+    }
+}
+
+/// The outcome of [`HttpExtractExt::download_file_if_modified`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadStatus {
+    /// The file was (re-)downloaded.
+    Downloaded,
+    /// The remote file was unchanged (`304 Not Modified`), so the local copy was kept.
+    NotModified,
+}
+
+/// A client certificate & private key, presented to endpoints requiring mutual TLS (mTLS).
+#[derive(Clone)]
+pub enum ClientIdentity {
+    /// A DER-encoded PKCS#12 archive, e.g. a `.p12`/`.pfx` file, and its password.
+    Pkcs12 { der: Vec<u8>, password: String },
+    /// A PEM-encoded certificate (chain), and its PEM-encoded PKCS#8 private key.
+    Pem { cert: Vec<u8>, key: Vec<u8> },
+}
+
+impl std::fmt::Debug for ClientIdentity {
+    // Never print the key material, nor the password.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pkcs12 { .. } => f.write_str("ClientIdentity::Pkcs12"),
+            Self::Pem { .. } => f.write_str("ClientIdentity::Pem"),
         }
+    }
+}
+
+impl ClientIdentity {
+    fn to_reqwest(&self) -> Result<reqwest::Identity> {
+        let identity = match self {
+            Self::Pkcs12 { der, password } => reqwest::Identity::from_pkcs12_der(der, password),
+            Self::Pem { cert, key } => reqwest::Identity::from_pkcs8_pem(cert, key),
+        };
+        identity.map_err(|e| {
+            error!("Failed to load client identity: {e}");
+            e.into()
+        })
+    }
+}
+
+/// Build a [`reqwest::Client`] which presents `identity` for mutual TLS; `fetch()`,
+/// `download_file()` and the rest of [`HttpExtractExt`] then work as with any other client.
+///
+/// Further options can be set on `builder`, e.g. a timeout, before it is passed in.
+///
+/// ```rust
+/// use skopje::Error;
+/// use skopje::extract::http::{ClientIdentity, client_with_identity};
+///
+/// let identity = ClientIdentity::Pkcs12 {
+///     der: b"not a PKCS#12 archive".to_vec(),
+///     password: "secret".to_string(),
+/// };
+/// let result = client_with_identity(reqwest::Client::builder(), &identity);
+/// assert!(matches!(result, Err(Error::Http(_))));
+/// ```
+pub fn client_with_identity(
+    builder: reqwest::ClientBuilder,
+    identity: &ClientIdentity,
+) -> Result<reqwest::Client> {
+    let client = builder
+        .use_native_tls()
+        .identity(identity.to_reqwest()?)
+        .build()?;
+    Ok(client)
+}
 
-        // join all async tasks together, in order to execute
-        let mut outputs = Vec::with_capacity(tasks.len());
-        for task in tasks {
-            outputs.push(task.await.expect("Failed to unwrap Future task"));
+/// Where a client built by [`client_with_proxy`] sends its requests.
+#[derive(Debug, Clone, Default)]
+pub enum ProxyConfig {
+    /// The proxies set by the `HTTP_PROXY`, `HTTPS_PROXY` & `NO_PROXY` environment variables
+    /// (or their lowercase forms), if any; as with the default [`reqwest::Client`].
+    #[default]
+    Env,
+    /// Every request through the proxy at `url`, e.g. `http://proxy.corp:3128`, except hosts
+    /// matching `no_proxy`, a comma-separated list in the `NO_PROXY` format.
+    Url {
+        url: String,
+        no_proxy: Option<String>,
+    },
+    /// Connect directly, ignoring any proxy environment variables.
+    None,
+}
+
+/// Build a [`reqwest::Client`] which connects through the `proxy`; e.g. behind a corporate
+/// proxy, for the rest of [`HttpExtractExt`].
+///
+/// Further options can be set on `builder`, e.g. a timeout, before it is passed in.
+///
+/// ```rust
+/// use skopje::extract::http::{HttpExtractExt, ProxyConfig, client_with_proxy};
+/// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+///
+/// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+/// rt.block_on(async {
+///     // A mock proxy, which answers for the upstream itself.
+///     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+///     let proxy = format!("http://{}", listener.local_addr().unwrap());
+///     let server = tokio::spawn(async move {
+///         let (mut stream, _) = listener.accept().await.unwrap();
+///         let mut request = Vec::new();
+///         while !request.ends_with(b"\r\n\r\n") {
+///             let mut buf = [0; 1024];
+///             let n = stream.read(&mut buf).await.unwrap();
+///             request.extend_from_slice(&buf[..n]);
+///         }
+///         let response = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n42";
+///         stream.write_all(response.as_bytes()).await.unwrap();
+///         String::from_utf8(request).unwrap()
+///     });
+///
+///     let config = ProxyConfig::Url { url: proxy, no_proxy: None };
+///     let client = client_with_proxy(reqwest::Client::builder(), &config).unwrap();
+///     let answer: u32 = client.fetch("http://upstream.invalid/answer").await.unwrap();
+///     assert_eq!(answer, 42);
+///
+///     // The proxy was asked for the upstream URL.
+///     let request = server.await.unwrap();
+///     assert!(request.starts_with("GET http://upstream.invalid/answer HTTP/1.1"));
+/// });
+/// ```
+pub fn client_with_proxy(
+    builder: reqwest::ClientBuilder,
+    proxy: &ProxyConfig,
+) -> Result<reqwest::Client> {
+    let builder = match proxy {
+        // reqwest reads the proxy environment variables by default.
+        ProxyConfig::Env => builder,
+        ProxyConfig::Url { url, no_proxy } => {
+            let proxy = reqwest::Proxy::all(url.as_str()).map_err(|e| {
+                error!("Failed to configure proxy {url:?}: {e}");
+                e
+            })?;
+            builder
+                .proxy(proxy.no_proxy(no_proxy.as_deref().and_then(reqwest::NoProxy::from_string)))
+        }
+        ProxyConfig::None => builder.no_proxy(),
+    };
+    Ok(builder.build()?)
+}
+
+/// The OAuth2 client-credentials an [`OAuthClient`] exchanges for bearer tokens.
+#[derive(Clone)]
+pub struct OAuthConfig {
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scope: Option<String>,
+}
+
+impl std::fmt::Debug for OAuthConfig {
+    // Never print the client secret.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OAuthConfig")
+            .field("token_url", &self.token_url)
+            .field("client_id", &self.client_id)
+            .field("scope", &self.scope)
+            .finish_non_exhaustive()
+    }
+}
+
+/// How long before its expiry a cached token is refreshed.
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// A [`reqwest::Client`] wrapper for OAuth2-protected APIs, which obtains a bearer token with the
+/// client-credentials grant, caches it, and sends it with every `fetch()`.
+///
+/// The token is refreshed shortly before it expires (by its `expires_in`), or whenever a request
+/// is rejected with `401 Unauthorized`, after which the request is retried once; clones share the
+/// same token.
+///
+/// ```rust
+/// use skopje::extract::http::{OAuthClient, OAuthConfig};
+/// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+/// use tokio::net::TcpStream;
+///
+/// // Read a request's head, and its body (by its Content-Length).
+/// async fn read_request(stream: &mut TcpStream) -> String {
+///     let mut request = Vec::new();
+///     let mut buf = [0; 1024];
+///     while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+///         let n = stream.read(&mut buf).await.unwrap();
+///         request.extend_from_slice(&buf[..n]);
+///     }
+///     let head = String::from_utf8_lossy(&request).to_lowercase();
+///     let head_len = head.find("\r\n\r\n").unwrap() + 4;
+///     let body_len: usize = head
+///         .lines()
+///         .find_map(|line| line.strip_prefix("content-length: "))
+///         .map_or(0, |len| len.trim().parse().unwrap());
+///     while request.len() < head_len + body_len {
+///         let n = stream.read(&mut buf).await.unwrap();
+///         request.extend_from_slice(&buf[..n]);
+///     }
+///     String::from_utf8(request).unwrap()
+/// }
+///
+/// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+/// rt.block_on(async {
+///     // A mock API, which issues tokens "t1", then "t2", but only accepts "t2".
+///     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+///     let base = format!("http://{}", listener.local_addr().unwrap());
+///     tokio::spawn(async move {
+///         let mut issued = 0;
+///         loop {
+///             let (mut stream, _) = listener.accept().await.unwrap();
+///             let request = read_request(&mut stream).await;
+///             let (status, body) = if request.starts_with("POST /token") {
+///                 assert!(request.contains("grant_type=client_credentials"));
+///                 issued += 1;
+///                 ("200 OK", format!(r#"{{"access_token":"t{issued}","expires_in":3600}}"#))
+///             } else if request.contains("Bearer t2") {
+///                 ("200 OK", r#"{"price":1.5}"#.to_string())
+///             } else {
+///                 ("401 Unauthorized", String::new())
+///             };
+///             let response = format!(
+///                 "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+///                 body.len()
+///             );
+///             stream.write_all(response.as_bytes()).await.unwrap();
+///         }
+///     });
+///
+///     let client = OAuthClient::new(
+///         skopje::HttpClient::new(),
+///         OAuthConfig {
+///             token_url: format!("{base}/token"),
+///             client_id: "etl".to_string(),
+///             client_secret: "secret".to_string(),
+///             scope: None,
+///         },
+///     );
+///
+///     // "t1" is rejected, so the token is refreshed, and the request retried.
+///     let data: serde_json::Value = client.fetch(&format!("{base}/prices")).await.unwrap();
+///     assert_eq!(data["price"], 1.5);
+///     assert_eq!(client.access_token().await.unwrap(), "t2");
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct OAuthClient {
+    pub client: reqwest::Client,
+    config: OAuthConfig,
+    token: Arc<Mutex<Option<CachedToken>>>,
+}
+
+/// A bearer token, and when it expires (if it does).
+type CachedToken = (String, Option<Instant>);
+
+impl OAuthClient {
+    pub fn new(client: reqwest::Client, config: OAuthConfig) -> Self {
+        Self {
+            client,
+            config,
+            token: Arc::default(),
+        }
+    }
+
+    /// See [`HttpExtractExt::fetch`]; sent with the bearer token, and retried once with a
+    /// refreshed token on `401 Unauthorized`.
+    pub async fn fetch<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let mut response = self.send(url, false).await?;
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+            debug!(url = %url, "bearer token rejected (401) - refreshing token");
+            response = self.send(url, true).await?;
+        }
+
+        let response = response.error_for_status().map_err(|e| {
+            error!(url = %url, "request failed: {e}");
+            e
+        })?;
+        json_body(url, response).await
+    }
+
+    /// The current bearer token; fetching a new one if there is none yet, or it's expiring.
+    pub async fn access_token(&self) -> Result<String> {
+        self.token(false).await
+    }
+
+    /// Send a GET request to `url` with the bearer token, optionally refreshed first.
+    async fn send(&self, url: &str, refresh: bool) -> Result<reqwest::Response> {
+        let token = self.token(refresh).await?;
+        let response = send_request(self.client.get(url).bearer_auth(token), url).await?;
+        Ok(response)
+    }
+
+    /// Return the cached token, unless it's expiring (or `refresh` is set); otherwise, request
+    /// a new one from the token endpoint.
+    async fn token(&self, refresh: bool) -> Result<String> {
+        // Held across the token request, so that concurrent fetches share a single refresh.
+        let mut cached = self.token.lock().await;
+        if !refresh
+            && let Some((token, expires_at)) = &*cached
+            && expires_at.is_none_or(|expires_at| Instant::now() + TOKEN_EXPIRY_MARGIN < expires_at)
+        {
+            return Ok(token.clone());
+        }
+
+        let mut form = vec![
+            ("grant_type", "client_credentials"),
+            ("client_id", &self.config.client_id),
+            ("client_secret", &self.config.client_secret),
+        ];
+        if let Some(scope) = &self.config.scope {
+            form.push(("scope", scope));
+        }
+        let token_url = &self.config.token_url;
+        trace!(token_url = %token_url, "requesting OAuth2 token");
+        let response: serde_json::Value = self.client.fetch_post_form(token_url, &form).await?;
+
+        let token = response["access_token"]
+            .as_str()
+            .ok_or_else(|| {
+                error!(token_url = %token_url, "token response has no access_token");
+                Error::deserialize(format!(
+                    "OAuth2 token response from {token_url} has no access_token"
+                ))
+            })?
+            .to_string();
+        let expires_at = response["expires_in"]
+            .as_u64()
+            .map(|secs| Instant::now() + Duration::from_secs(secs));
+
+        *cached = Some((token.clone(), expires_at));
+        Ok(token)
+    }
+}
+
+/// A [`reqwest::Client`] wrapper with an in-process response cache, keyed by URL.
+///
+/// Raw response bodies are cached, so repeated fetches of the same URL within a TTL are served
+/// without hitting the network; clones share the same cache.
+#[derive(Debug, Clone, Default)]
+pub struct CachedHttpClient {
+    pub client: reqwest::Client,
+    cache: Arc<std::sync::Mutex<HashMap<String, CachedResponse>>>,
+}
+
+/// When a response body was fetched, and the body itself.
+type CachedResponse = (Instant, Arc<[u8]>);
+
+impl CachedHttpClient {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            cache: Arc::default(),
+        }
+    }
+
+    /// Fetch `url`, using the cached response body if it was fetched less than `ttl` ago.
+    pub async fn fetch_cached<T: DeserializeOwned>(&self, url: &str, ttl: Duration) -> Result<T> {
+        let cached = self
+            .cache
+            .lock()
+            .expect("lock response cache")
+            .get(url)
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < ttl)
+            .map(|(_, body)| body.clone());
+
+        let body = match cached {
+            Some(body) => {
+                trace!(url = %url, "serving cached response");
+                body
+            }
+            None => {
+                let body: Arc<[u8]> = self.fetch_bytes(url).await?.into();
+                self.cache
+                    .lock()
+                    .expect("lock response cache")
+                    .insert(url.to_string(), (Instant::now(), body.clone()));
+                body
+            }
+        };
+
+        let data: T = serde_json::from_slice(&body).map_err(|e| {
+            error!(url = %url, "failed to deserialize JSON: {e}");
+            e
+        })?;
+        Ok(data)
+    }
+
+    /// Remove the cached response for `url`, if there is one.
+    pub fn invalidate(&self, url: &str) {
+        self.cache.lock().expect("lock response cache").remove(url);
+    }
+
+    /// Remove all cached responses.
+    pub fn clear(&self) {
+        self.cache.lock().expect("lock response cache").clear();
+    }
+
+    /// Fetch the raw response body; error responses are not cached.
+    async fn fetch_bytes(&self, url: &str) -> Result<Vec<u8>> {
+        let response = send_request(self.client.get(url), url).await?;
+
+        let response_status = response.status();
+        trace!("response code: {}", response_status);
+        let response = response.error_for_status().map_err(|e| {
+            error!(url = %url, response_status=%response_status, "request failed: {e}");
+            e
+        })?;
+
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+/// A registry of in-flight [`HttpExtractExt::download_file`] tasks, so that they can be drained
+/// when a long-running service shuts down.
+///
+/// On [`DownloadRegistry::shutdown`], downloads are given a grace period to complete; any still
+/// running afterwards are aborted, and the partial `.part` files of aborted (or failed) downloads
+/// are removed; any previous, complete, file at the output path is left in place.
+///
+/// ```rust,no_run
+/// use skopje::extract::http::DownloadRegistry;
+///
+/// # async fn run() -> anyhow::Result<()> {
+/// let client = skopje::HttpClient::new();
+/// let downloads = DownloadRegistry::new();
+/// downloads.spawn(&client, "https://example.com/data.zip", "./data/data.zip");
+///
+/// // ... on SIGTERM
+/// downloads.shutdown(std::time::Duration::from_secs(10)).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DownloadRegistry {
+    downloads: Arc<std::sync::Mutex<Vec<ActiveDownload>>>,
+}
+
+/// An output path, and the task downloading to it.
+type ActiveDownload = (String, JoinHandle<Result<()>>);
+
+impl DownloadRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawn a download of `url` to `path`, tracked by the registry.
+    pub fn spawn(&self, client: &reqwest::Client, url: &str, path: &str) {
+        let client = client.clone();
+        let (url, path) = (url.to_string(), path.to_string());
+        let mut downloads = self.downloads.lock().expect("lock download registry");
+
+        // Prune any downloads that have already finished.
+        downloads.retain(|(_, handle)| !handle.is_finished());
+
+        let task_path = path.clone();
+        let handle = tokio::spawn(async move { client.download_file(&url, &task_path).await });
+        downloads.push((path, handle));
+    }
+
+    /// Wait up to `grace` for all in-flight downloads to complete, then abort the remainder.
+    ///
+    /// The `.part` files of aborted, or failed, downloads are removed, so no partial files are
+    /// left; the output path itself is never touched, so a previous download there survives.
+    pub async fn shutdown(&self, grace: Duration) -> Result<()> {
+        let downloads =
+            std::mem::take(&mut *self.downloads.lock().expect("lock download registry"));
+        let deadline = tokio::time::Instant::now() + grace;
+
+        for (path, mut handle) in downloads {
+            let outcome = match tokio::time::timeout_at(deadline, &mut handle).await {
+                Ok(joined) => joined,
+                Err(_) => {
+                    handle.abort();
+                    handle.await
+                }
+            };
+
+            match outcome {
+                Ok(Ok(())) => {
+                    trace!(path = %path, "download completed before shutdown");
+                    continue;
+                }
+                Ok(Err(e)) => warn!(path = %path, "download failed before shutdown: {e}"),
+                Err(e) => warn!(path = %path, "download aborted on shutdown: {e}"),
+            }
+
+            // Remove the partial output file.
+            match tokio::fs::remove_file(part_path(&path)).await {
+                Ok(()) => debug!(path = %path, "removed partial download"),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    error!(path = %path, "failed to remove partial download: {e}");
+                    return Err(e.into());
+                }
+            }
         }
 
         Ok(())
     }
 }
 
+/// Download a range of bytes, as [`HttpExtractExt::download_chunk`]; with a `budget`, each
+/// retry is drawn from it, and the chunk fails once it's exhausted.
+async fn download_chunk_budgeted(
+    client: &reqwest::Client,
+    url: &str,
+    start: u64,
+    end: u64,
+    output_file: &mut File,
+    budget: Option<&RetryBudget>,
+) -> Result<()> {
+    let range = format!("bytes={}-{}", start, end - 1);
+    let mut attempt = 0;
+
+    // download a range of bytes, re-issuing the request on a transport error, or on a
+    // response that isn't the expected 206 Partial Content of the expected length
+    let body = loop {
+        attempt += 1;
+
+        let result = async {
+            let response = client
+                .get(url)
+                .header(reqwest::header::RANGE, &range)
+                .send()
+                .await?;
+
+            // check the response status is 206 Partial Content
+            if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+                return Err(Error::UnexpectedStatus {
+                    url: url.to_string(),
+                    status: response.status(),
+                });
+            }
+
+            let body = response.bytes().await?;
+            if body.len() as u64 != end - start {
+                return Err(Error::IncompleteDownload {
+                    url: url.to_string(),
+                    expected: end - start,
+                    received: body.len() as u64,
+                });
+            }
+
+            Ok::<_, Error>(body)
+        }
+        .await;
+
+        match result {
+            Ok(body) => {
+                if let Some(budget) = budget {
+                    budget.deposit();
+                }
+                break body;
+            }
+            Err(e)
+                if attempt <= MAX_CHUNK_RETRIES && budget.is_none_or(RetryBudget::try_withdraw) =>
+            {
+                let backoff = CHUNK_RETRY_BACKOFF * 2u32.pow(attempt - 1);
+                warn!(url = %url, attempt = attempt, "{e} - retrying chunk in {backoff:?}");
+                tokio::time::sleep(backoff).await;
+            }
+            Err(e) => return Err(e),
+        }
+    };
+
+    // seek the position of bytes and write to the file
+    output_file.seek(tokio::io::SeekFrom::Start(start)).await?;
+    output_file.write_all(&body).await?;
+
+    Ok(())
+}
+
+/// Download a file in chunks, as [`HttpExtractExt::download_file`]; with a `limiter`, each
+/// request waits for it first, and with a `budget`, chunk retries are drawn from it.
+async fn download_file_limited(
+    client: &reqwest::Client,
+    url: &str,
+    path: &str,
+    chunk_size: u64,
+    limiter: Option<Arc<DefaultDirectRateLimiter>>,
+    budget: Option<Arc<RetryBudget>>,
+) -> Result<()> {
+    #[cfg(feature = "metrics")]
+    let started = std::time::Instant::now();
+    let downloaded = download_to_part(client, url, path, chunk_size, limiter, budget).await;
+    #[cfg(feature = "metrics")]
+    {
+        crate::metrics::record("download_file", started, &downloaded);
+        if let Ok(bytes) = &downloaded {
+            crate::metrics::downloaded_bytes(*bytes);
+        }
+    }
+    downloaded.map(|_| ())
+}
+
+/// Download a file, as [`download_file_limited`], via its `.part` file; returns its size.
+async fn download_to_part(
+    client: &reqwest::Client,
+    url: &str,
+    path: &str,
+    chunk_size: u64,
+    limiter: Option<Arc<DefaultDirectRateLimiter>>,
+    budget: Option<Arc<RetryBudget>>,
+) -> Result<u64> {
+    // Object store URLs are read by the object store, via their own `.part` file.
+    #[cfg(feature = "object-store")]
+    if crate::extract::object_store::is_object_store_url(url) {
+        crate::extract::object_store::download_object(url, path).await?;
+        return Ok(tokio::fs::metadata(path).await?.len());
+    }
+
+    // Retrieve the HTTP response.
+    trace!("fetching {url}");
+    if let Some(limiter) = &limiter {
+        limiter.until_ready().await;
+    }
+    let mut response = client.get(url).send().await?;
+    let file_size = response
+        .headers()
+        .get(reqwest::header::CONTENT_LENGTH)
+        .and_then(|len| len.to_str().ok())
+        .and_then(|len| len.parse::<u64>().ok());
+
+    // Guarantee the parent directory exists of the target path; a bare filename has an
+    // empty parent, and is downloaded to the current directory.
+    if let Some(dir) = std::path::Path::new(path).parent()
+        && !dir.as_os_str().is_empty()
+    {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+
+    // Without a Content-Length (e.g. `Transfer-Encoding: chunked`), the file can't be split
+    // into ranges; so stream the response body straight to the file instead.
+    // The download is written to a `.part` file, and only renamed to `path` once complete.
+    let part = part_path(path);
+
+    let Some(file_size) = file_size else {
+        debug!("No Content-Length for {url} - streaming download");
+        let mut file = File::create(&part).await?;
+        let mut written = 0;
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        file.flush().await?;
+        tokio::fs::rename(&part, path).await?;
+        trace!(total_size=%ByteSize(written), "Streamed {url} to {path}");
+        return Ok(written);
+    };
+
+    // Initialise async variables ...
+    let file = Arc::new(Mutex::new(File::create(&part).await?));
+
+    // Chunk tasks are held in a `JoinSet`, so that they are aborted if this future is dropped
+    // (e.g. by a [`DownloadRegistry`] shutdown), rather than left writing to the file.
+    let mut tasks = JoinSet::new();
+
+    debug!("Downloading {url} in chunks");
+    for (start, end) in chunk_ranges(file_size, chunk_size) {
+        let url = url.to_string();
+        let file = file.clone();
+        let client = client.clone();
+        let limiter = limiter.clone();
+        let budget = budget.clone();
+        tasks.spawn(async move {
+            let mut file = file.lock().await;
+            if let Some(limiter) = &limiter {
+                limiter.until_ready().await;
+            }
+            let result =
+                download_chunk_budgeted(&client, &url, start, end, &mut file, budget.as_deref())
+                    .await;
+            match &result {
+                Ok(_) => trace!(
+                    total_size=%ByteSize(file_size),
+                    "Downloaded chunk: ({start}, {end})",
+                    start=ByteSize(start),
+                    end=ByteSize(end)
+                ),
+                Err(e) => error!(url = %url, "failed to download chunk {start}-{end}: {e}"),
+            }
+            result
+        });
+    }
+
+    // join all async tasks together, in order to execute; a failed chunk fails the download
+    while let Some(task) = tasks.join_next().await {
+        task.expect("Failed to unwrap Future task")?;
+    }
+
+    // Verify the file is complete, before moving it into place.
+    let mut file = file.lock().await;
+    file.flush().await?;
+    let written = file.metadata().await?.len();
+    if written != file_size {
+        return Err(Error::IncompleteDownload {
+            url: url.to_string(),
+            expected: file_size,
+            received: written,
+        });
+    }
+    drop(file); // close the file before it's moved
+    tokio::fs::rename(&part, path).await?;
+
+    Ok(written)
+}
+
+/// Split a download of `file_size` bytes into the `(start, end)` byte ranges (end exclusive) of
+/// its ranged requests, of `chunk_size` bytes each; coalesced, to spare the overhead of many
+/// small requests:
+///
+/// - the chunks grow, as needed, to keep to [`MAX_CHUNK_REQUESTS`] requests, but never past
+///   [`MAX_CHUNK_SIZE`]; so a very large file takes more requests, rather than larger chunks;
+/// - a trailing range of less than half a chunk is merged into the one before it, e.g. for a
+///   file just over a chunk boundary.
+///
+/// ```rust
+/// use skopje::extract::http::{MAX_CHUNK_REQUESTS, MAX_CHUNK_SIZE, chunk_ranges};
+///
+/// assert_eq!(chunk_ranges(10, 3), vec![(0, 3), (3, 6), (6, 10)]);
+/// assert_eq!(chunk_ranges(11, 3), vec![(0, 3), (3, 6), (6, 9), (9, 11)]);
+/// assert_eq!(chunk_ranges(2, 3), vec![(0, 2)]);
+/// assert!(chunk_ranges(0, 3).is_empty());
+///
+/// // a million 1 byte chunks are capped
+/// let ranges = chunk_ranges(1_000_000, 1);
+/// assert_eq!(ranges.len() as u64, MAX_CHUNK_REQUESTS);
+/// assert_eq!(ranges.last().unwrap().1, 1_000_000);
+///
+/// // but a 20GB file, in 100MB chunks, isn't squeezed into 64 (320MB) chunks
+/// let ranges = chunk_ranges(20_000 * 1024 * 1024, 100 * 1024 * 1024);
+/// assert_eq!(ranges.len(), 200);
+/// assert!(ranges.iter().all(|(start, end)| end - start <= MAX_CHUNK_SIZE));
+/// ```
+pub fn chunk_ranges(file_size: u64, chunk_size: u64) -> Vec<(u64, u64)> {
+    let grown = file_size.div_ceil(MAX_CHUNK_REQUESTS).min(MAX_CHUNK_SIZE);
+    let chunk_size = chunk_size.max(1).max(grown);
+
+    let mut ranges: Vec<(u64, u64)> = (0..file_size.div_ceil(chunk_size))
+        .map(|i| (i * chunk_size, ((i + 1) * chunk_size).min(file_size)))
+        .collect();
+    if ranges.len() > 1
+        && let Some(&(start, end)) = ranges.last()
+        && (end - start) * 2 < chunk_size
+    {
+        ranges.pop();
+        if let Some(last) = ranges.last_mut() {
+            last.1 = end;
+        }
+    }
+    ranges
+}
+
+/// The temporary path a download to `path` is written to, until it's complete.
+pub(crate) fn part_path(path: &str) -> String {
+    format!("{path}.part")
+}
+
+/// Append the query parameter `page_param={page}` to `url`.
+fn page_url(url: &str, page_param: &str, page: u64) -> Result<String> {
+    let mut url = reqwest::Url::parse(url).map_err(|e| Error::InvalidUrl {
+        url: url.to_string(),
+        reason: e.to_string(),
+    })?;
+    url.query_pairs_mut()
+        .append_pair(page_param, &page.to_string());
+    Ok(url.into())
+}
+
 /// Send a HTTP GET request, using a referenced [`reqweest::Client`] and a URL.
 ///
 /// This function mainly aims to standardize any error handling.
 pub async fn get<T: serde::de::DeserializeOwned>(client: &reqwest::Client, url: &str) -> Result<T> {
-    let response = client.get(url).send().await.map_err(|e| {
-        error!(url = %url, "Failed to send GET request: {e}");
-        e
-    })?;
+    let response = send_request(client.get(url), url).await?;
+    json_body(url, response).await
+}
 
+/// Send a `request`, to `url`, logging any failure to send it.
+async fn send_request(request: reqwest::RequestBuilder, url: &str) -> Result<reqwest::Response> {
+    request.send().await.map_err(|e| {
+        error!(url = %url, "failed to send request: {e}");
+        e.into()
+    })
+}
+
+/// Fail a `response` from `url` with an error status (4xx or 5xx), as an [`Error::Http`], rather
+/// than have the error page read as if it was the payload.
+fn check_status(url: &str, response: reqwest::Response) -> Result<reqwest::Response> {
+    let response_status = response.status();
+    response.error_for_status().map_err(|e| {
+        error!(url = %url, response_status=%response_status, "request failed: {e}");
+        e.into()
+    })
+}
+
+/// Deserialize the JSON body of a `response` from `url`; a body that can't be read is an
+/// [`Error::Http`], but one that isn't valid JSON (for `T`) is an [`Error::Deserialize`].
+async fn json_body<T: DeserializeOwned>(url: &str, response: reqwest::Response) -> Result<T> {
     let response_status = response.status();
     trace!("response code: {}", response_status);
 
-    let data: T = response.json().await.map_err(|e| {
-        error!(url = %url, response_status=%response_status, "Failed to deserialize JSON: {e}");
+    let body = response.bytes().await.map_err(|e| {
+        error!(url = %url, response_status=%response_status, "failed to read response body: {e}");
         e
     })?;
-
-    Ok(data)
+    serde_json::from_slice(&body).map_err(|e| {
+        error!(url = %url, response_status=%response_status, "failed to deserialize JSON: {e}");
+        e.into()
+    })
 }
 
 /// Send a HTTP GET request, using a referenced [`reqweest::Client`] and a URL.
@@ -154,33 +2069,54 @@ async fn get_retry<T>(client: &reqwest::Client, url: &str) -> Result<T>
 where
     T: DeserializeOwned,
 {
+    get_retry_budgeted(client, url, None).await
+}
+
+/// See [`get_retry`]; with a `budget`, each retry is drawn from it, and a 429 fails once it's
+/// exhausted.
+async fn get_retry_budgeted<T>(
+    client: &reqwest::Client,
+    url: &str,
+    budget: Option<&RetryBudget>,
+) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let response = send_retry(client, url, budget).await?;
+    let data: T = json_body(url, response).await?;
+    if let Some(budget) = budget {
+        budget.deposit();
+    }
+
+    Ok(data)
+}
+
+/// Send a HTTP GET request, retrying on `429 Too Many Requests` as [`get_retry_budgeted`] does;
+/// returns the first response that isn't a 429 (or the last, once out of retries).
+async fn send_retry(
+    client: &reqwest::Client,
+    url: &str,
+    budget: Option<&RetryBudget>,
+) -> Result<reqwest::Response> {
     const MAX_RETRIES: u32 = 3;
     let mut attempt = 0;
 
     loop {
         attempt += 1;
 
-        let response = client.get(url).send().await.map_err(|e| {
-            error!(url = %url, "failed to send GET request: {e}");
-            e
-        })?;
-
-        let response_status = response.status();
-        trace!("response code: {}", response_status);
-
-        if response_status == reqwest::StatusCode::TOO_MANY_REQUESTS {
-            if attempt <= MAX_RETRIES {
-                warn!(url = %url, attempt = attempt, "rate limited (429) - waiting 2 minutes before retry");
-                tokio::time::sleep(std::time::Duration::from_secs(120)).await;
-                continue;
+        let response = send_request(client.get(url), url).await?;
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS && attempt <= MAX_RETRIES {
+            if let Some(budget) = budget
+                && !budget.try_withdraw()
+            {
+                warn!(url = %url, "rate limited (429) - retry budget exhausted");
+                return Err(Error::RetryBudgetExhausted(url.to_string()));
             }
+            warn!(url = %url, attempt = attempt, "rate limited (429) - waiting 2 minutes before retry");
+            tokio::time::sleep(Duration::from_secs(120)).await;
+            continue;
         }
 
-        let data: T = response.json().await.map_err(|e| {
-            error!(url = %url, response_status=%response_status, "failed to deserialize JSON: {e}");
-            e
-        })?;
-
-        return Ok(data);
+        return Ok(response);
     }
 }
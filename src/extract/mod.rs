@@ -1,3 +1,8 @@
 pub mod fs;
 pub mod http;
+#[cfg(feature = "object-store")]
+pub mod object_store;
 pub mod pg;
+pub mod stdin;
+#[cfg(feature = "websocket")]
+pub mod ws;
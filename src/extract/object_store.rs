@@ -0,0 +1,106 @@
+use crate::Result;
+use ::object_store::{ObjectStore, ObjectStoreExt, path::Path};
+use bytesize::ByteSize;
+use std::sync::Arc;
+use tokio::fs::File;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+use tracing::{debug, error, trace};
+
+/// Size of each ranged read when downloading an object; as with HTTP downloads, 100MB.
+const CHUNK_SIZE: u64 = 100 * 1024 * 1024; // 100 MegaBytes
+
+/// URL schemes served by an object store, rather than HTTP.
+const SCHEMES: [&str; 8] = ["s3", "s3a", "gs", "az", "adl", "azure", "abfs", "file"];
+
+/// Check if `url` is for an object store, e.g. `s3://bucket/key`, rather than HTTP.
+pub fn is_object_store_url(url: &str) -> bool {
+    url.split_once("://")
+        .is_some_and(|(scheme, _)| SCHEMES.contains(&scheme.to_ascii_lowercase().as_str()))
+}
+
+/// Download an object from an `s3://`, `gs://` or `az://` (or `file://`) URL to the local
+/// `path`, in parallel ranged reads; the object store equivalent of
+/// [`crate::extract::http::HttpExtractExt::download_file`], which defers to this for such URLs.
+///
+/// Credentials and other options are read from the environment, by their usual names; e.g.
+/// `AWS_ACCESS_KEY_ID`, `AWS_REGION`, `GOOGLE_SERVICE_ACCOUNT` or `AZURE_STORAGE_ACCOUNT_NAME`.
+///
+/// As with HTTP downloads, the object is written to `{path}.part`, and only renamed to `path`
+/// once complete.
+///
+/// ```rust
+/// use skopje::extract::object_store::download_object;
+///
+/// let dir = std::env::temp_dir().join("skopje-object-store-doctest");
+/// std::fs::create_dir_all(&dir).unwrap();
+/// let source = dir.join("source.json");
+/// std::fs::write(&source, br#"{"AAPL": 170.5}"#).unwrap();
+///
+/// let url = format!("file://{}", source.display());
+/// let target = dir.join("downloads/target.json");
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// runtime.block_on(download_object(&url, target.to_str().unwrap())).unwrap();
+/// assert_eq!(std::fs::read(&target).unwrap(), br#"{"AAPL": 170.5}"#);
+/// ```
+pub async fn download_object(url: &str, path: &str) -> Result<()> {
+    let parsed = url::Url::parse(url).map_err(|e| {
+        error!(url = %url, "invalid object store URL: {e}");
+        crate::Error::InvalidUrl {
+            url: url.to_string(),
+            reason: e.to_string(),
+        }
+    })?;
+    let (store, location) = ::object_store::parse_url_opts(&parsed, std::env::vars())?;
+    let store: Arc<dyn ObjectStore> = Arc::from(store);
+
+    trace!("fetching {url}");
+    let size = store.head(&location).await?.size;
+
+    // Guarantee the parent directory exists of the target path; a bare filename has an
+    // empty parent, and is downloaded to the current directory.
+    if let Some(dir) = std::path::Path::new(path).parent()
+        && !dir.as_os_str().is_empty()
+    {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+
+    let part = crate::extract::http::part_path(path);
+    let file = Arc::new(Mutex::new(File::create(&part).await?));
+    let num_chunks = size.div_ceil(CHUNK_SIZE);
+
+    debug!("Downloading {url} in chunks");
+    let mut tasks = JoinSet::new();
+    for i in 0..num_chunks {
+        let start = i * CHUNK_SIZE;
+        let end = std::cmp::min((i + 1) * CHUNK_SIZE, size);
+        let store = store.clone();
+        let location: Path = location.clone();
+        let file = file.clone();
+        tasks.spawn(async move {
+            let bytes = store.get_range(&location, start..end).await?;
+            let mut file = file.lock().await;
+            file.seek(std::io::SeekFrom::Start(start)).await?;
+            file.write_all(&bytes).await?;
+            trace!(
+                total_size=%ByteSize(size),
+                "Downloaded chunk: ({start}, {end})",
+                start=ByteSize(start),
+                end=ByteSize(end)
+            );
+            Ok::<_, crate::Error>(())
+        });
+    }
+
+    // Unlike HTTP chunks, a failed range fails the download.
+    while let Some(task) = tasks.join_next().await {
+        task.expect("Failed to unwrap Future task")?;
+    }
+    file.lock().await.flush().await?;
+    drop(file); // close the file before it's moved
+    tokio::fs::rename(&part, path).await?;
+
+    Ok(())
+}
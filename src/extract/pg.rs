@@ -1,8 +1,14 @@
-use anyhow::Result;
+use crate::pool::timed;
+use crate::{Error, Result};
 use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 use tokio_postgres::types::{FromSql, ToSql};
 use tracing::{debug, error, trace};
 
+pub use tokio_postgres::Row;
+
 /// An extension for shortcutting some Postgres-scraping protocols.
 #[async_trait]
 pub trait PgExtractExt {
@@ -15,6 +21,16 @@ pub trait PgExtractExt {
     where
         T: for<'b> FromSql<'b>;
 
+    /// If a single row exists, return all of its columns, mapped by [`FromRow`]; e.g. into a
+    /// tuple struct deriving it, `struct Quote(String, f64)`.
+    async fn fetch_row_as<'a, T>(
+        &self,
+        fetch_stmt: &'a str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<T>
+    where
+        T: FromRow;
+
     /// If a single entity exists, return it; if not, insert it.
     async fn fetch_or_insert<'a, T>(
         &self,
@@ -26,6 +42,9 @@ pub trait PgExtractExt {
         T: for<'b> FromSql<'b> + 'static;
 
     /// Return some collection from the database.
+    ///
+    /// The `params` are bound positionally, to `$1, $2, ...`; a statement without any takes an
+    /// empty slice, `&[]`, or see [`PgExtractExt::fetch_collection_all`].
     async fn fetch_collection<C, T, F, 'a>(
         &self,
         fetch_stmt: &'a str,
@@ -36,6 +55,216 @@ pub trait PgExtractExt {
         C: FromIterator<T>,
         T: Send,
         F: FnMut(&tokio_postgres::Row) -> T + Send;
+
+    /// See [`PgExtractExt::fetch_collection`]; for a statement without any params, e.g. to
+    /// select an entire table.
+    ///
+    /// ```rust,no_run
+    /// use skopje::PgPool;
+    /// use skopje::extract::pg::PgExtractExt;
+    ///
+    /// async fn symbols(pool: &PgPool) -> skopje::Result<Vec<String>> {
+    ///     pool.fetch_collection_all("SELECT symbol FROM stocks", |row| row.get(0))
+    ///         .await
+    /// }
+    /// ```
+    async fn fetch_collection_all<C, T, F, 'a>(&self, fetch_stmt: &'a str, f: F) -> Result<C>
+    where
+        C: FromIterator<T>,
+        T: Send,
+        F: FnMut(&tokio_postgres::Row) -> T + Send;
+
+    /// See [`PgExtractExt::fetch_collection`]; with the `ids` bound as a single array parameter,
+    /// `$1`, for a statement matching on `= ANY($1)`. So a single statement, the same for any
+    /// number of ids, replaces a built `IN (...)` list.
+    ///
+    /// ```rust,no_run
+    /// use skopje::PgPool;
+    /// use skopje::extract::pg::PgExtractExt;
+    ///
+    /// async fn closes(pool: &PgPool, ids: &[i64]) -> skopje::Result<Vec<(i64, f64)>> {
+    ///     pool.fetch_collection_any(
+    ///         "SELECT id, close FROM prices WHERE id = ANY($1)",
+    ///         ids,
+    ///         |row| (row.get(0), row.get(1)),
+    ///     )
+    ///     .await
+    /// }
+    /// ```
+    async fn fetch_collection_any<C, T, F, P, 'a>(
+        &self,
+        fetch_stmt: &'a str,
+        ids: &[P],
+        f: F,
+    ) -> Result<C>
+    where
+        C: FromIterator<T>,
+        T: Send,
+        F: FnMut(&tokio_postgres::Row) -> T + Send,
+        P: ToSql + Sync;
+
+    /// See [`PgExtractExt::fetch_collection`]; but the rows are transformed by `f` in parallel,
+    /// across the rayon thread pool, for CPU-heavy transforms (e.g. parsing or decompression).
+    ///
+    /// The collection keeps the order of the rows.
+    async fn fetch_collection_parallel<C, T, F, 'a>(
+        &self,
+        fetch_stmt: &'a str,
+        params: &[&(dyn ToSql + Sync)],
+        f: F,
+    ) -> Result<C>
+    where
+        C: FromIterator<T>,
+        T: Send,
+        F: Fn(&tokio_postgres::Row) -> T + Send + Sync;
+
+    /// Return a collection from a large table in pages of `page_size` rows, by keyset
+    /// pagination over the unique column `order_col`; each page continues from the last key of
+    /// the previous one (`WHERE order_col > $1 ORDER BY order_col LIMIT n`), rather than an
+    /// `OFFSET`, so every page is an index seek and no rows are skipped or repeated by
+    /// concurrent writes.
+    ///
+    /// `base_stmt` is wrapped as a subquery, so it may have its own `WHERE` clause; the key, of
+    /// type `K`, is read from the column named `order_col` of its output.
+    ///
+    /// ```rust,no_run
+    /// use skopje::PgPool;
+    /// use skopje::extract::pg::PgExtractExt;
+    ///
+    /// async fn prices(pool: &PgPool) -> skopje::Result<Vec<(i64, f64)>> {
+    ///     pool.fetch_keyset::<_, i64, _, _>(
+    ///         "SELECT id, close FROM prices WHERE symbol = 'AAPL'",
+    ///         "id",
+    ///         10_000,
+    ///         |row| (row.get(0), row.get(1)),
+    ///     )
+    ///     .await
+    /// }
+    /// ```
+    async fn fetch_keyset<C, K, T, F, 'a>(
+        &self,
+        base_stmt: &'a str,
+        order_col: &'a str,
+        page_size: u32,
+        f: F,
+    ) -> Result<C>
+    where
+        C: FromIterator<T>,
+        K: for<'b> FromSql<'b> + ToSql + Send + Sync,
+        T: Send,
+        F: FnMut(&tokio_postgres::Row) -> T + Send;
+
+    /// Return a stream of rows from the database, each transformed by `f`, as they arrive from
+    /// the server; rather than collecting them all, as [`PgExtractExt::fetch_collection`] does.
+    ///
+    /// The pooled client is held by the stream, and returns to the pool once it is dropped.
+    async fn fetch_try_stream<'a, T, F>(
+        &self,
+        fetch_stmt: &'a str,
+        params: &[&(dyn ToSql + Sync)],
+        f: F,
+    ) -> Result<BoxStream<'static, Result<T>>>
+    where
+        T: Send + 'static,
+        F: FnMut(&tokio_postgres::Row) -> T + Send + 'static;
+
+    /// Prepare a statement once, for reuse across repeated fetches; see [`PreparedFetch`].
+    async fn prepare_fetch(&self, fetch_stmt: &str) -> Result<PreparedFetch>;
+
+    /// Export data with a `COPY ... TO STDOUT` statement, streaming it into `writer`; e.g.
+    /// `COPY prices TO STDOUT (FORMAT csv, HEADER)`.
+    ///
+    /// Returns the number of bytes written.
+    async fn copy_out<'a, W>(&self, copy_stmt: &'a str, writer: W) -> Result<u64>
+    where
+        W: AsyncWrite + Unpin + Send;
+}
+
+/// Map a [`Row`] into a struct; the read-side counterpart to [`crate::load::pg::SqlMap`].
+///
+/// Usually derived, with [`skopje::FromRow`](crate::FromRow): structs with named fields read each
+/// field from the column of the same name, and tuple structs read their fields by position.
+/// `from_row` can then be passed straight to [`PgExtractExt::fetch_collection`]:
+///
+/// ```rust,no_run
+/// use skopje::FromRow;
+/// use skopje::extract::pg::{FromRow as _, PgExtractExt};
+///
+/// #[derive(FromRow)]
+/// struct Price {
+///     symbol: String,
+///     close: f64,
+/// }
+///
+/// async fn prices(pool: &skopje::PgPool) -> skopje::Result<Vec<Price>> {
+///     let prices: skopje::Result<Vec<Price>> = pool
+///         .fetch_collection("SELECT symbol, close FROM prices", &[], Price::from_row)
+///         .await?;
+///     prices
+/// }
+/// ```
+pub trait FromRow: Sized {
+    fn from_row(row: &Row) -> Result<Self>;
+}
+
+/// A statement prepared once, on a single pooled client, and reused for every fetch.
+///
+/// `fetch_if_exists` and friends re-parse their statement on every call; for hot lookups in a
+/// tight loop, a `PreparedFetch` saves that round-trip. The client is held for the lifetime
+/// of the handle, and returns to the pool when it is dropped.
+pub struct PreparedFetch {
+    client: deadpool_postgres::Object,
+    stmt: tokio_postgres::Statement,
+    sql: String,
+}
+
+impl PreparedFetch {
+    /// Prepare `fetch_stmt` on a client taken from `pool`.
+    pub async fn new(pool: &deadpool_postgres::Pool, fetch_stmt: &str) -> Result<Self> {
+        let client = pool.get().await?;
+
+        trace!(fetch_stmt = %fetch_stmt, "Preparing statement");
+        let stmt = client.prepare(fetch_stmt).await.map_err(|e| {
+            error!(stmt = %fetch_stmt, "Failed to prepare statement: {e}");
+            e
+        })?;
+
+        Ok(Self {
+            client,
+            stmt,
+            sql: fetch_stmt.to_string(),
+        })
+    }
+
+    /// If a single entity exists, return it.
+    pub async fn fetch<T>(&self, params: &[&(dyn ToSql + Sync)]) -> Result<T>
+    where
+        T: for<'b> FromSql<'b>,
+    {
+        let data = timed(&self.sql, self.client.query_one(&self.stmt, params))
+            .await
+            .map_err(|e| {
+                error!(stmt = ?self.stmt, "{e}");
+                e
+            })?;
+
+        Ok(data.get(0))
+    }
+
+    /// Return the single entity if it exists, or `None` if no row is found.
+    pub async fn fetch_optional<T>(&self, params: &[&(dyn ToSql + Sync)]) -> Result<Option<T>>
+    where
+        T: for<'b> FromSql<'b>,
+    {
+        let data = timed(&self.sql, self.client.query_opt(&self.stmt, params))
+            .await
+            .map_err(|e| {
+                error!(stmt = ?self.stmt, "{e}");
+                e
+            })?;
+
+        Ok(data.map(|row| row.get(0)))
+    }
 }
 
 #[async_trait]
@@ -48,20 +277,45 @@ impl PgExtractExt for &deadpool_postgres::Pool {
     where
         T: for<'b> FromSql<'b>,
     {
-        let client = self.get().await.expect("Failed to get client from pool");
+        let client = self.get().await?;
 
         trace!(fetch_stmt = %fetch_stmt, "Fetching data for query");
-        let data: tokio_postgres::Row = match client.query_one(fetch_stmt, params).await {
-            Ok(response) => response,
+        let data: tokio_postgres::Row =
+            match timed(fetch_stmt, client.query_one(fetch_stmt, params)).await {
+                Ok(response) => response,
+                Err(e) => {
+                    error!(stmt = %fetch_stmt, "{e}");
+                    return Err(e.into());
+                }
+            };
+
+        drop(client);
+
+        Ok(data.get(0))
+    }
+
+    async fn fetch_row_as<'a, T>(
+        &self,
+        fetch_stmt: &'a str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<T>
+    where
+        T: FromRow,
+    {
+        let client = self.get().await?;
+
+        trace!(fetch_stmt = %fetch_stmt, "Fetching row for query");
+        let row = match timed(fetch_stmt, client.query_one(fetch_stmt, params)).await {
+            Ok(row) => row,
             Err(e) => {
                 error!(stmt = %fetch_stmt, "{e}");
-                return Err(anyhow::anyhow!(e));
+                return Err(e.into());
             }
         };
 
         drop(client);
 
-        Ok(data.get(0))
+        T::from_row(&row)
     }
 
     async fn fetch_or_insert<'a, T>(
@@ -73,21 +327,26 @@ impl PgExtractExt for &deadpool_postgres::Pool {
     where
         T: for<'b> FromSql<'b>,
     {
-        let client = self.get().await.expect("Failed to get client from pool");
+        let client = self.get().await?;
 
         // Attempt to find the Source PK in the existing table.
-        let data: tokio_postgres::Row = match client.query_one(fetch_stmt, params).await {
+        let data: tokio_postgres::Row = match timed(
+            fetch_stmt,
+            client.query_one(fetch_stmt, params),
+        )
+        .await
+        {
             Ok(response) => response,
 
             // If no PK is found, insert a new one, and reattempt to find it.
             Err(e) => {
                 debug!("Did not find data for query: {fetch_stmt} - inserting data instead: {e}");
-                client.query_one(insert_stmt, params).await?;
-                match client.query_one(fetch_stmt, params).await {
+                timed(insert_stmt, client.query_one(insert_stmt, params)).await?;
+                match timed(fetch_stmt, client.query_one(fetch_stmt, params)).await {
                     Ok(second_response) => second_response,
                     Err(e) => {
                         error!(fetch_stmt = %fetch_stmt, insert_stmt = %insert_stmt, "Failed to insert and retrieve new data");
-                        return Err(anyhow::anyhow!(e));
+                        return Err(e.into());
                     }
                 }
             }
@@ -109,18 +368,198 @@ impl PgExtractExt for &deadpool_postgres::Pool {
         // T: Send,
         F: FnMut(&tokio_postgres::Row) -> T + Send,
     {
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+
         // Return the collection from the pg database.
-        let client = self.get().await.expect("Failed to get client from Pool");
-        let data: Vec<tokio_postgres::Row> = match client.query(fetch_stmt, params).await {
-            Ok(response) => response,
-            Err(e) => {
-                error!(fetch_stmt = %fetch_stmt, "Failed to fetch collection");
-                return Err(anyhow::anyhow!(e));
+        let client = self.get().await?;
+        let data: Result<Vec<tokio_postgres::Row>> =
+            match timed(fetch_stmt, client.query(fetch_stmt, params)).await {
+                Ok(response) => Ok(response),
+                Err(e) => {
+                    error!(fetch_stmt = %fetch_stmt, "Failed to fetch collection");
+                    Err(e.into())
+                }
+            };
+        #[cfg(feature = "metrics")]
+        {
+            crate::metrics::record("fetch_collection", started, &data);
+            if let Ok(data) = &data {
+                crate::metrics::extracted_rows("fetch_collection", data.len() as u64);
             }
-        };
+        }
 
         // Transform the array of [`tokio_postgres::Row`] with some closure.
-        let output: C = data.iter().map(f).collect();
+        let output: C = data?.iter().map(f).collect();
         Ok(output)
     }
+
+    async fn fetch_collection_all<'a, C, T, F>(&self, fetch_stmt: &'a str, f: F) -> Result<C>
+    where
+        C: FromIterator<T>,
+        T: Send,
+        F: FnMut(&tokio_postgres::Row) -> T + Send,
+    {
+        self.fetch_collection(fetch_stmt, &[], f).await
+    }
+
+    async fn fetch_collection_any<'a, C, T, F, P>(
+        &self,
+        fetch_stmt: &'a str,
+        ids: &[P],
+        f: F,
+    ) -> Result<C>
+    where
+        C: FromIterator<T>,
+        T: Send,
+        F: FnMut(&tokio_postgres::Row) -> T + Send,
+        P: ToSql + Sync,
+    {
+        trace!(ids = ids.len(), "fetching collection with {fetch_stmt:?}");
+        self.fetch_collection(fetch_stmt, &[&ids], f).await
+    }
+
+    async fn fetch_collection_parallel<'a, C, T, F>(
+        &self,
+        fetch_stmt: &'a str,
+        params: &[&(dyn ToSql + Sync)],
+        f: F,
+    ) -> Result<C>
+    where
+        C: FromIterator<T>,
+        T: Send,
+        F: Fn(&tokio_postgres::Row) -> T + Send + Sync,
+    {
+        // Return the collection from the pg database.
+        let client = self.get().await?;
+        let data: Vec<tokio_postgres::Row> =
+            match timed(fetch_stmt, client.query(fetch_stmt, params)).await {
+                Ok(response) => response,
+                Err(e) => {
+                    error!(fetch_stmt = %fetch_stmt, "Failed to fetch collection");
+                    return Err(e.into());
+                }
+            };
+        drop(client);
+
+        // Transform the rows across the rayon pool; `collect` into a `Vec` keeps their order.
+        let output: Vec<T> = data.par_iter().map(f).collect();
+        Ok(output.into_iter().collect())
+    }
+
+    async fn fetch_keyset<'a, C, K, T, F>(
+        &self,
+        base_stmt: &'a str,
+        order_col: &'a str,
+        page_size: u32,
+        mut f: F,
+    ) -> Result<C>
+    where
+        C: FromIterator<T>,
+        K: for<'b> FromSql<'b> + ToSql + Send + Sync,
+        T: Send,
+        F: FnMut(&tokio_postgres::Row) -> T + Send,
+    {
+        let page_size = page_size.max(1);
+        let first_stmt =
+            format!("SELECT * FROM ({base_stmt}) AS keyset ORDER BY {order_col} LIMIT {page_size}");
+        let next_stmt = format!(
+            "SELECT * FROM ({base_stmt}) AS keyset WHERE {order_col} > $1 \
+             ORDER BY {order_col} LIMIT {page_size}"
+        );
+
+        let client = self.get().await?;
+        let mut output = Vec::new();
+        let mut last: Option<K> = None;
+        loop {
+            let page = match &last {
+                None => timed(&first_stmt, client.query(&first_stmt, &[])).await,
+                Some(last) => timed(&next_stmt, client.query(&next_stmt, &[last])).await,
+            }
+            .map_err(|e| {
+                error!(fetch_stmt = %base_stmt, "Failed to fetch keyset page: {e}");
+                e
+            })?;
+            trace!(fetch_stmt = %base_stmt, rows = page.len(), "fetched keyset page");
+
+            // Continue from the last key of the page; a short page is the last one.
+            let Some(row) = page.last() else { break };
+            last = Some(row.try_get(order_col)?);
+            output.extend(page.iter().map(&mut f));
+            if page.len() < page_size as usize {
+                break;
+            }
+        }
+        drop(client);
+
+        Ok(output.into_iter().collect())
+    }
+
+    async fn fetch_try_stream<'a, T, F>(
+        &self,
+        fetch_stmt: &'a str,
+        params: &[&(dyn ToSql + Sync)],
+        f: F,
+    ) -> Result<BoxStream<'static, Result<T>>>
+    where
+        T: Send + 'static,
+        F: FnMut(&tokio_postgres::Row) -> T + Send + 'static,
+    {
+        let client = self.get().await?;
+        let rows = match timed(
+            fetch_stmt,
+            client.query_raw(fetch_stmt, params.iter().copied()),
+        )
+        .await
+        {
+            Ok(rows) => Box::pin(rows),
+            Err(e) => {
+                error!(fetch_stmt = %fetch_stmt, "Failed to fetch stream");
+                return Err(e.into());
+            }
+        };
+
+        // Keep the client alive alongside the rows, and transform each row with the closure.
+        let stream = stream::unfold((client, rows, f), |(client, mut rows, mut f)| async move {
+            let item = rows.next().await?.map(|row| f(&row)).map_err(Error::from);
+            Some((item, (client, rows, f)))
+        });
+
+        Ok(stream.boxed())
+    }
+
+    async fn prepare_fetch(&self, fetch_stmt: &str) -> Result<PreparedFetch> {
+        PreparedFetch::new(self, fetch_stmt).await
+    }
+
+    async fn copy_out<'a, W>(&self, copy_stmt: &'a str, mut writer: W) -> Result<u64>
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
+        let client = self.get().await?;
+        let written = timed(copy_stmt, async {
+            let stream = match client.copy_out(copy_stmt).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    error!(copy_stmt = %copy_stmt, "Failed to start COPY out");
+                    return Err(Error::from(e));
+                }
+            };
+            futures::pin_mut!(stream);
+
+            // Write each chunk of the export to the sink, as it arrives.
+            let mut written = 0;
+            while let Some(chunk) = stream.next().await {
+                let chunk = chunk?;
+                writer.write_all(&chunk).await?;
+                written += chunk.len() as u64;
+            }
+            writer.flush().await?;
+            Ok(written)
+        })
+        .await?;
+        trace!(copy_stmt = %copy_stmt, bytes = written, "COPY out executed successfully");
+
+        Ok(written)
+    }
 }
@@ -0,0 +1,20 @@
+use crate::Result;
+use serde::de::DeserializeOwned;
+use tracing::trace;
+
+/// A client for extracting data piped in through stdin, e.g. `cat data.json | my-etl`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdinClient;
+
+impl StdinClient {
+    /// Read all of stdin as JSON; see [`read_stdin_json`].
+    pub async fn read_json<T: DeserializeOwned>(&self) -> Result<T> {
+        read_stdin_json().await
+    }
+}
+
+/// Read all of stdin, until EOF, and deserialize it as JSON.
+pub async fn read_stdin_json<T: DeserializeOwned>() -> Result<T> {
+    trace!("reading stdin");
+    crate::extract::fs::read_json_from(tokio::io::stdin()).await
+}
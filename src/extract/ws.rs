@@ -0,0 +1,97 @@
+use crate::{Error, Result};
+use async_trait::async_trait;
+use futures::SinkExt;
+use futures::stream::{BoxStream, StreamExt};
+use serde::de::DeserializeOwned;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, error, trace};
+
+/// A client for extracting data pushed from WebSocket feeds, e.g. market data.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WsClient;
+
+/// Extension of WebSocket data-streaming methods for clients.
+#[async_trait]
+pub trait WsExtractExt {
+    /// Connect to `url`, send the subscription message `sub_msg` (unless it's empty), and
+    /// stream each text (or binary) frame that follows, deserialized as JSON.
+    ///
+    /// Pings are answered, and control frames skipped; the stream ends when the server closes
+    /// the connection.
+    ///
+    /// ```rust
+    /// use futures::{SinkExt, StreamExt};
+    /// use skopje::extract::ws::{WsClient, WsExtractExt};
+    /// use tokio_tungstenite::tungstenite::Message;
+    ///
+    /// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    /// rt.block_on(async {
+    ///     // A mock feed, which echoes the subscription, then pushes two ticks & closes.
+    ///     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    ///     let url = format!("ws://{}", listener.local_addr().unwrap());
+    ///     tokio::spawn(async move {
+    ///         let (stream, _) = listener.accept().await.unwrap();
+    ///         let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+    ///         let sub = ws.next().await.unwrap().unwrap();
+    ///         ws.send(sub).await.unwrap();
+    ///         ws.send(Message::text(r#"{"channel":"ticks","price":1.5}"#)).await.unwrap();
+    ///         ws.close(None).await.unwrap();
+    ///     });
+    ///
+    ///     let frames: Vec<serde_json::Value> = WsClient
+    ///         .subscribe(&url, r#"{"channel":"ticks"}"#)
+    ///         .await
+    ///         .unwrap()
+    ///         .map(Result::unwrap)
+    ///         .collect()
+    ///         .await;
+    ///     assert_eq!(frames[0]["channel"], "ticks");
+    ///     assert_eq!(frames[1]["price"], 1.5);
+    ///     assert_eq!(frames.len(), 2);
+    /// });
+    /// ```
+    async fn subscribe<T>(&self, url: &str, sub_msg: &str) -> Result<BoxStream<'static, Result<T>>>
+    where
+        T: DeserializeOwned + Send + 'static;
+}
+
+#[async_trait]
+impl WsExtractExt for WsClient {
+    async fn subscribe<T>(&self, url: &str, sub_msg: &str) -> Result<BoxStream<'static, Result<T>>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        trace!(url = %url, "connecting to websocket");
+        let (mut socket, _response) = tokio_tungstenite::connect_async(url).await.map_err(|e| {
+            error!(url = %url, "failed to connect to websocket: {e}");
+            e
+        })?;
+
+        if !sub_msg.is_empty() {
+            debug!(url = %url, "sending subscription message");
+            socket.send(Message::text(sub_msg)).await?;
+        }
+
+        let url = url.to_string();
+        let frames = socket.filter_map(move |frame| {
+            let item = match frame {
+                Ok(Message::Text(text)) => Some(serde_json::from_str(&text).map_err(Error::from)),
+                Ok(Message::Binary(bytes)) => {
+                    Some(serde_json::from_slice(&bytes).map_err(Error::from))
+                }
+                Ok(Message::Close(_)) => {
+                    debug!(url = %url, "websocket closed by server");
+                    None
+                }
+                Ok(_) => None,
+                Err(e) => {
+                    error!(url = %url, "websocket error: {e}");
+                    Some(Err(e.into()))
+                }
+            };
+            futures::future::ready(item)
+        });
+
+        Ok(frames.boxed())
+    }
+}
@@ -1,7 +1,10 @@
-use anyhow::Result;
+use crate::load::pg::{SqlMap, SqlTypes, copy_rows_in, resolve_types};
+use crate::pool::timed;
+use crate::{Error, Result};
 use bimap::BiMap;
 use deadpool_postgres::Pool;
 use num::PrimInt;
+use std::collections::{BTreeMap, HashMap};
 use std::hash::Hash;
 use std::ops::AddAssign;
 use tokio_postgres::types::{FromSql, ToSql};
@@ -15,6 +18,14 @@ use tokio_postgres::types::{FromSql, ToSql};
 ///
 /// In tangent, there is the `next_key` variable; the constantly-recalculated, lowest value, of type `PK`.
 ///
+/// Each `Obj` is stored only once: the [BiMap] holds both sides of the bijection behind shared
+/// (reference-counted) pointers, so a `KeyMap<_, String>` doesn't duplicate its strings, and needs
+/// no separate interning.
+///
+/// A `KeyMap` also tracks the keys allocated since they were last persisted (see
+/// [`KeyMap::pending_keys`]), in a private field; so it can no longer be built as a struct literal,
+/// only with [`KeyMap::from`] & co.
+///
 /// [BiMap]: bimap::BiMap,
 /// [Postgres Types]: tokio_postgres::types::FromSql,
 ///
@@ -41,6 +52,13 @@ where
 {
     pub bimap: BiMap<PK, Obj>,
     pub next_key: PK,
+
+    // Keys allocated since the last `flush_with` (or `clear_pending`), in allocation order; an
+    // evicted key is dropped, as it's handed to `on_evict` to persist instead.
+    pending: Vec<PK>,
+
+    // The number of times keys have been persisted, to tell stale snapshots apart.
+    persisted: u64,
 }
 
 impl<PK, Obj> KeyMap<PK, Obj>
@@ -49,29 +67,133 @@ where
     Obj: Eq + PartialEq + Hash + for<'a> FromSql<'a> + ToSql + Sync,
 {
     /// Retrieve a KeyMap from a PostgreSQL query.
-    pub async fn pg_fetch(pg_pool: &Pool, stmt: &str) -> Result<Self> {
+    ///
+    /// If the rows don't form a bijection, e.g. the same value under two keys, an
+    /// [`Error::BijectionViolation`] is returned; see [`KeyMap::try_from_pairs`].
+    pub async fn pg_fetch(pg_pool: &Pool, stmt: &str) -> Result<Self>
+    where
+        PK: std::fmt::Debug,
+        Obj: std::fmt::Debug,
+    {
+        Self::pg_fetch_where(pg_pool, stmt, &[]).await
+    }
+
+    /// Retrieve a KeyMap from a parameterised PostgreSQL query, e.g. to load only the subset of
+    /// keys a run touches:
+    ///
+    /// ```rust,no_run
+    /// use skopje::{KeyMap, PgPool};
+    ///
+    /// async fn nyse_stocks(pool: &PgPool) -> skopje::Result<KeyMap<i32, String>> {
+    ///     KeyMap::pg_fetch_where(
+    ///         pool,
+    ///         "SELECT id, symbol FROM stocks WHERE exchange = $1",
+    ///         &[&"NYSE"],
+    ///     )
+    ///     .await
+    /// }
+    /// ```
+    ///
+    /// As with [`KeyMap::pg_fetch`], the key is read from the first column, and the value from
+    /// the second.
+    pub async fn pg_fetch_where(
+        pg_pool: &Pool,
+        stmt: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Self>
+    where
+        PK: std::fmt::Debug,
+        Obj: std::fmt::Debug,
+    {
         let pg_client = pg_pool.get().await?;
 
         //retrieve a BiMap from a pg query
-        let bimap: BiMap<PK, Obj> = pg_client
-            .query(stmt, &[])
+        let pairs = timed(stmt, pg_client.query(stmt, params))
             .await
-            .expect("Failed to fetch key map")
+            .map_err(|e| {
+                tracing::error!("Failed to fetch key map with {stmt:?}: {e}");
+                e
+            })?
             .into_iter()
             .map(|row| {
                 let key: PK = row.get(0);
                 let value: Obj = row.get(1);
                 (key, value)
-            })
-            .collect();
+            });
 
-        // calculate the first, lowest, available key
-        let starting_key = Self::calc_lowest_key(&bimap);
+        // rows that break the bijection would otherwise be silently dropped from the map
+        let (keymap, conflicts) = Self::from_pairs_with_conflicts(pairs);
+        if !conflicts.is_empty() {
+            tracing::error!("{stmt:?} returned {} conflicting rows", conflicts.len());
+            return Err(bijection_violation(
+                &format!("rows of {stmt:?}"),
+                &conflicts,
+            ));
+        }
+        Ok(keymap)
+    }
 
-        Ok(Self {
-            bimap,
-            next_key: starting_key,
-        })
+    /// Build a `KeyMap` from `(key, value)` pairs, checking that they form a bijection; if any
+    /// pair reuses a key, or a value, already seen, an [`Error::BijectionViolation`] is returned
+    /// (exact duplicates are fine).
+    ///
+    /// A safe alternative to [`KeyMap::from`], for a [BiMap] which may have silently dropped some
+    /// entries as it was built.
+    ///
+    /// ```rust
+    /// use skopje::{Error, KeyMap};
+    ///
+    /// let pairs = vec![(0, "zero".to_string()), (1, "one".to_string()), (1, "one".to_string())];
+    /// let keymap: KeyMap<i8, String> = KeyMap::try_from_pairs(pairs).unwrap();
+    /// assert_eq!(keymap.see_next_key(), &2);
+    ///
+    /// // a duplicated key, with two values
+    /// let pairs = vec![(0, "zero".to_string()), (0, "nought".to_string())];
+    /// let result = KeyMap::<i8, String>::try_from_pairs(pairs);
+    /// assert!(matches!(result, Err(Error::BijectionViolation(_))));
+    /// ```
+    ///
+    /// [BiMap]: bimap::BiMap
+    pub fn try_from_pairs<I>(pairs: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (PK, Obj)>,
+        PK: std::fmt::Debug,
+        Obj: std::fmt::Debug,
+    {
+        let (keymap, conflicts) = Self::from_pairs_with_conflicts(pairs);
+        if !conflicts.is_empty() {
+            return Err(bijection_violation("pairs", &conflicts));
+        }
+        Ok(keymap)
+    }
+
+    /// Build a `KeyMap` from the `(key, value)` pairs that form a bijection, collecting (rather
+    /// than overwriting with) every pair whose key, or value, was already seen.
+    ///
+    /// ```rust
+    /// use skopje::KeyMap;
+    ///
+    /// // a duplicated value, under two keys
+    /// let pairs = vec![(0, "zero".to_string()), (1, "one".to_string()), (2, "one".to_string())];
+    /// let (keymap, conflicts) = KeyMap::<i8, String>::from_pairs_with_conflicts(pairs);
+    /// assert_eq!(keymap.bimap.get_by_right("one"), Some(&1)); // the first pair is kept
+    /// assert_eq!(conflicts, vec![(2, "one".to_string())]);
+    /// ```
+    pub fn from_pairs_with_conflicts<I>(pairs: I) -> (Self, Vec<(PK, Obj)>)
+    where
+        I: IntoIterator<Item = (PK, Obj)>,
+    {
+        let mut bimap = BiMap::new();
+        let mut conflicts = Vec::new();
+        for (key, value) in pairs {
+            if let Err((key, value)) = bimap.insert_no_overwrite(key, value)
+                && bimap.get_by_left(&key) != Some(&value)
+            {
+                conflicts.push((key, value));
+            }
+        }
+
+        (Self::from(bimap), conflicts)
     }
 
     /// Insert a KeyMap into a PostgreSQL table, synchronously.
@@ -89,10 +211,12 @@ where
         })?;
 
         for (key, value) in self.bimap.iter() {
-            tx.execute(&query, &[&key, &value]).await.map_err(|e| {
-                tracing::error!("Failed to execute {query:?}: {e}");
-                e
-            })?;
+            timed(stmt, tx.execute(&query, &[&key, &value]))
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to execute {query:?}: {e}");
+                    e
+                })?;
         }
 
         tx.commit().await.map_err(|e| {
@@ -103,7 +227,8 @@ where
         Ok(())
     }
 
-    /// Turn a BiMap into a `KeyMap`.
+    /// Turn a BiMap into a `KeyMap`; the BiMap is trusted as given, so see
+    /// [`KeyMap::try_from_pairs`] to build one from pairs that may conflict.
     ///
     /// ```rust
     /// use bimap::BiMap;
@@ -122,25 +247,43 @@ where
         Self {
             bimap,
             next_key: starting_key,
+            pending: Vec::new(),
+            persisted: 0,
         }
     }
 
     /// Finds the lowest available key, for some generic N, starting from 0.
+    ///
+    /// If the keyspace is exhausted, the maximum key (which is already taken) is returned.
     pub fn calc_lowest_key(map: &BiMap<PK, Obj>) -> PK {
         let mut next_key = PK::zero();
         while map.contains_left(&next_key) {
-            next_key += PK::one();
+            match next_key.checked_add(&PK::one()) {
+                Some(key) => next_key = key,
+                None => break,
+            }
         }
         next_key
     }
 
     /// Set the next available key.
+    ///
+    /// The key never overflows; if the keyspace is exhausted, `next_key` stays at the maximum key
+    /// (which is already taken), and [`KeyMap::is_exhausted`] returns `true`.
     pub fn calc_next_key(&mut self) {
         while self.bimap.contains_left(&self.next_key) {
-            self.next_key += PK::one();
+            match self.next_key.checked_add(&PK::one()) {
+                Some(key) => self.next_key = key,
+                None => break,
+            }
         }
     }
 
+    /// Check if there are no keys left to allocate.
+    pub fn is_exhausted(&self) -> bool {
+        self.bimap.contains_left(&self.next_key)
+    }
+
     /// Return a clone of the current `next_key`.
     pub fn see_next_key(&self) -> &PK {
         &self.next_key
@@ -168,17 +311,704 @@ where
     /// assert_eq!(pk, 2);
     /// assert_eq!(keymap.see_next_key(), &4); // next key recalculates
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if a new value is transacted when the keyspace is exhausted; see
+    /// [`KeyMap::try_transact`].
     pub fn transact(&mut self, value: Obj) -> PK {
+        self.try_transact(value)
+            .expect("KeyMap keyspace exhausted: no keys left to allocate")
+    }
+
+    /// See [`KeyMap::transact`]; but, if the value is new and there are no keys left to allocate,
+    /// return an error rather than panic.
+    ///
+    /// ```rust
+    /// use bimap::BiMap;
+    /// use skopje::KeyMap;
+    ///
+    /// let bimap: BiMap<i8, i16> = (0..=i8::MAX).map(|key| (key, key as i16)).collect();
+    /// let mut keymap = KeyMap::from(bimap);
+    /// assert!(keymap.is_exhausted());
+    ///
+    /// assert_eq!(keymap.try_transact(127).unwrap(), 127); // existing values are still found
+    /// assert!(keymap.try_transact(128).is_err());
+    /// ```
+    pub fn try_transact(&mut self, value: Obj) -> Result<PK> {
         // if the value already exists, return a clone of the associated key
         if let Some(key) = self.bimap.get_by_right(&value) {
-            return key.clone();
+            Ok(*key)
         // if the value does not exist, insert it with the next available key, and
         // copy a clone of that key
+        } else if self.is_exhausted() {
+            Err(Error::KeyspaceExhausted)
         } else {
             let key = self.next_key;
-            self.bimap.insert(key.clone(), value);
+            self.bimap.insert(key, value);
+            self.pending.push(key);
             self.calc_next_key();
-            return key;
+            Ok(key)
+        }
+    }
+
+    /// See [`KeyMap::try_transact`]; but, when a new key is allocated, also insert the new
+    /// `(key, value)` row to Postgres with `insert_stmt`, so that the map and the table stay in
+    /// sync without a separate [`KeyMap::pg_insert`] pass.
+    ///
+    /// Existing values are returned without touching the database. If the insert fails, the
+    /// allocation is undone, and the error returned.
+    pub async fn transact_persist(
+        &mut self,
+        pg_pool: &Pool,
+        insert_stmt: &str,
+        value: Obj,
+    ) -> Result<PK> {
+        if let Some(key) = self.bimap.get_by_right(&value) {
+            return Ok(*key);
+        }
+
+        let previous_next_key = self.next_key;
+        let key = self.try_transact(value)?;
+
+        let inserted = async {
+            let pg_client = pg_pool.get().await?;
+            let value = self
+                .bimap
+                .get_by_left(&key)
+                .expect("value was just inserted");
+            timed(insert_stmt, pg_client.execute(insert_stmt, &[&key, value]))
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to execute {insert_stmt:?}: {e}");
+                    e
+                })?;
+            Ok::<_, Error>(())
+        }
+        .await;
+
+        // Undo the allocation if the row couldn't be persisted.
+        if let Err(e) = inserted {
+            self.bimap.remove_by_left(&key);
+            self.pending.pop();
+            self.next_key = previous_next_key;
+            return Err(e);
+        }
+
+        // Already persisted, so there's nothing to flush.
+        self.pending.pop();
+        self.persisted += 1;
+        Ok(key)
+    }
+
+    /// The keys allocated (by [`KeyMap::transact`] & co.) since the last
+    /// [`KeyMap::flush_with`], in allocation order.
+    ///
+    /// ```rust
+    /// use bimap::BiMap;
+    /// use skopje::KeyMap;
+    ///
+    /// let mut bimap: BiMap<i8, String> = BiMap::new();
+    /// bimap.insert(0, "zero".to_string());
+    ///
+    /// let mut keymap = KeyMap::from(bimap);
+    /// keymap.transact("zero".to_string()); // existing
+    /// keymap.transact("one".to_string());
+    /// keymap.transact("two".to_string());
+    /// assert_eq!(keymap.pending_keys(), &[1, 2]);
+    /// ```
+    pub fn pending_keys(&self) -> &[PK] {
+        &self.pending
+    }
+
+    /// Forget the pending keys, once they've been persisted other than by
+    /// [`KeyMap::flush_with`]; e.g. with [`KeyMap::pg_insert`], which writes the whole map.
+    ///
+    /// ```rust
+    /// use bimap::BiMap;
+    /// use skopje::KeyMap;
+    ///
+    /// let mut keymap: KeyMap<i8, String> = KeyMap::from(BiMap::new());
+    /// keymap.transact("zero".to_string());
+    /// keymap.clear_pending();
+    /// assert!(keymap.pending_keys().is_empty());
+    /// ```
+    pub fn clear_pending(&mut self) {
+        self.pending.clear();
+        self.persisted += 1;
+    }
+
+    /// Persist the newly allocated keys, and the data rows referencing them, in a single
+    /// transaction: each pending `(key, value)` is inserted with `key_insert_stmt`, then the
+    /// `rows` are copied in with `data_copy_stmt`; so the database never holds rows whose
+    /// keys weren't persisted, even if the run fails between the two.
+    ///
+    /// If anything fails, both are rolled back, and the keys are left pending, to be flushed
+    /// again.
+    ///
+    /// ```rust,no_run
+    /// use bimap::BiMap;
+    /// use skopje::{KeyMap, PgPool};
+    ///
+    /// #[derive(skopje::SqlMap, skopje::SqlTypes)]
+    /// struct Price {
+    ///     stock_id: i32,
+    ///     close: f64,
+    /// }
+    ///
+    /// async fn load(pool: &PgPool, ticks: Vec<(String, f64)>) -> skopje::Result<()> {
+    ///     let mut stocks: KeyMap<i32, String> =
+    ///         KeyMap::pg_fetch(pool, "SELECT id, symbol FROM stocks").await?;
+    ///     let prices: Vec<Price> = ticks
+    ///         .into_iter()
+    ///         .map(|(symbol, close)| Price { stock_id: stocks.transact(symbol), close })
+    ///         .collect();
+    ///
+    ///     stocks
+    ///         .flush_with(
+    ///             pool,
+    ///             "INSERT INTO stocks (id, symbol) VALUES ($1, $2)",
+    ///             "COPY prices (stock_id, close) FROM STDIN BINARY",
+    ///             prices.iter(),
+    ///         )
+    ///         .await
+    /// }
+    /// ```
+    pub async fn flush_with<I, T>(
+        &mut self,
+        pg_pool: &Pool,
+        key_insert_stmt: &str,
+        data_copy_stmt: &str,
+        rows: I,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T>,
+        T: SqlTypes + SqlMap,
+    {
+        let types = resolve_types(pg_pool, T::sql_types()).await?;
+        let mut pg_client = pg_pool.get().await?;
+
+        let query = pg_client.prepare(key_insert_stmt).await.map_err(|e| {
+            tracing::error!("Failed to prepare {key_insert_stmt:?}: {e}");
+            e
+        })?;
+
+        let tx = pg_client.transaction().await.map_err(|e| {
+            tracing::error!("Failed to open a transaction: {e}");
+            e
+        })?;
+
+        // a pending key is always still in the map; evicted keys are no longer pending
+        for key in &self.pending {
+            let Some(value) = self.bimap.get_by_left(key) else {
+                continue;
+            };
+            timed(key_insert_stmt, tx.execute(&query, &[key, value]))
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to execute {key_insert_stmt:?}: {e}");
+                    e
+                })?;
+        }
+
+        copy_rows_in(&tx, data_copy_stmt, &types, rows, 0, |_| {}).await?;
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Failed to commit transaction: {e}");
+            e
+        })?;
+        tracing::trace!(keys = self.pending.len(), "KeyMap flushed");
+        self.pending.clear();
+        self.persisted += 1;
+
+        Ok(())
+    }
+
+    /// Take a snapshot of the map, `next_key` and the pending keys, to roll back to with
+    /// [`KeyMap::restore`]; e.g. if a downstream step fails partway through a transact loop,
+    /// without re-fetching the map from Postgres.
+    ///
+    /// The snapshot is a copy of the map, so it costs as much as a clone; values that are
+    /// expensive to copy can be shared, e.g. as an `Arc<str>`.
+    ///
+    /// ```rust
+    /// use bimap::BiMap;
+    /// use skopje::KeyMap;
+    ///
+    /// let mut keymap: KeyMap<i8, String> = KeyMap::from(BiMap::new());
+    /// keymap.transact("zero".to_string());
+    /// let snapshot = keymap.snapshot();
+    ///
+    /// keymap.transact("one".to_string());
+    /// keymap.transact("two".to_string());
+    /// assert_eq!(keymap.see_next_key(), &3);
+    ///
+    /// keymap.restore(snapshot).unwrap();
+    /// assert_eq!(keymap.bimap.len(), 1);
+    /// assert_eq!(keymap.bimap.get_by_right("one"), None);
+    /// assert_eq!(keymap.see_next_key(), &1);
+    /// assert_eq!(keymap.pending_keys(), &[0]);
+    /// ```
+    pub fn snapshot(&self) -> KeyMapSnapshot<PK, Obj>
+    where
+        Obj: Clone,
+    {
+        KeyMapSnapshot {
+            bimap: self.bimap.clone(),
+            next_key: self.next_key,
+            pending: self.pending.clone(),
+            persisted: self.persisted,
+        }
+    }
+
+    /// Roll the map back to a [`KeyMap::snapshot`]; any keys allocated since are forgotten.
+    ///
+    /// Only the in-memory state is restored, so a snapshot taken before keys were persisted (by
+    /// [`KeyMap::flush_with`], [`KeyMap::transact_persist`] or [`KeyMap::clear_pending`]) is
+    /// refused with [`Error::StaleSnapshot`]; restoring it would hand the persisted keys out
+    /// again, and flush them twice. Snapshots are best taken just after a flush.
+    ///
+    /// ```rust
+    /// use bimap::BiMap;
+    /// use skopje::{Error, KeyMap};
+    ///
+    /// let mut keymap: KeyMap<i8, String> = KeyMap::from(BiMap::new());
+    /// let snapshot = keymap.snapshot();
+    ///
+    /// keymap.transact("zero".to_string());
+    /// keymap.clear_pending(); // e.g. persisted by `pg_insert`
+    ///
+    /// assert!(matches!(keymap.restore(snapshot), Err(Error::StaleSnapshot)));
+    /// assert_eq!(keymap.see_next_key(), &1);
+    /// ```
+    pub fn restore(&mut self, snapshot: KeyMapSnapshot<PK, Obj>) -> Result<()> {
+        if snapshot.persisted != self.persisted {
+            return Err(Error::StaleSnapshot);
+        }
+        self.bimap = snapshot.bimap;
+        self.next_key = snapshot.next_key;
+        self.pending = snapshot.pending;
+        Ok(())
+    }
+
+    /// Fold another `KeyMap` into this one, with [`KeyMap::try_transact`] semantics; values
+    /// already present reuse their existing keys, and new values are allocated the next available
+    /// keys.
+    ///
+    /// New values are allocated in ascending order of their keys in `other`. Returns a remap table,
+    /// from each of `other`'s keys to its key in the merged map, so that any references to
+    /// `other`'s keys can be fixed up.
+    ///
+    /// If the keyspace runs out, [`Error::KeyspaceExhausted`] is returned; the values merged
+    /// before then are kept (and pending), as by any other `try_transact`.
+    ///
+    /// ```rust
+    /// use bimap::BiMap;
+    /// use skopje::KeyMap;
+    ///
+    /// let mut left: BiMap<i8, String> = BiMap::new();
+    /// left.insert(0, "zero".to_string());
+    /// left.insert(1, "one".to_string());
+    ///
+    /// let mut right: BiMap<i8, String> = BiMap::new();
+    /// right.insert(0, "one".to_string());
+    /// right.insert(1, "two".to_string());
+    ///
+    /// let mut keymap = KeyMap::from(left);
+    /// let remap = keymap.merge(KeyMap::from(right)).unwrap();
+    ///
+    /// assert_eq!(remap.get(&0), Some(&1)); // "one" reuses its existing key
+    /// assert_eq!(remap.get(&1), Some(&2)); // "two" is allocated a new key
+    /// assert_eq!(keymap.bimap.get_by_right("two"), Some(&2));
+    /// assert_eq!(keymap.see_next_key(), &3);
+    ///
+    /// // a map with no keys left for the new values
+    /// let full: BiMap<i8, String> = (0..=i8::MAX).map(|key| (key, key.to_string())).collect();
+    /// let mut full = KeyMap::from(full);
+    /// assert!(matches!(full.merge(keymap), Err(skopje::Error::KeyspaceExhausted)));
+    /// ```
+    pub fn merge(&mut self, other: KeyMap<PK, Obj>) -> Result<HashMap<PK, PK>> {
+        let mut entries: Vec<(PK, Obj)> = other.bimap.into_iter().collect();
+        entries.sort_by_key(|(key, _)| *key);
+
+        entries
+            .into_iter()
+            .map(|(other_key, value)| Ok((other_key, self.try_transact(value)?)))
+            .collect()
+    }
+}
+
+/// The state of a [`KeyMap`] at some point, to roll back to; see [`KeyMap::snapshot`].
+#[derive(Debug, Clone)]
+pub struct KeyMapSnapshot<PK, Obj>
+where
+    PK: Eq + Hash,
+    Obj: Eq + Hash,
+{
+    bimap: BiMap<PK, Obj>,
+    next_key: PK,
+    pending: Vec<PK>,
+    persisted: u64,
+}
+
+/// Describe the `conflicts` found among some `source` of `(key, value)` pairs, with a few
+/// examples.
+fn bijection_violation<PK, Obj>(source: &str, conflicts: &[(PK, Obj)]) -> Error
+where
+    PK: std::fmt::Debug,
+    Obj: std::fmt::Debug,
+{
+    let examples: Vec<String> = conflicts
+        .iter()
+        .take(5)
+        .map(|(key, value)| format!("({key:?}, {value:?})"))
+        .collect();
+    Error::BijectionViolation(format!(
+        "{} {source} conflict with an earlier key or value, e.g. {}",
+        conflicts.len(),
+        examples.join(", ")
+    ))
+}
+
+/// A [`KeyMap`] bounded to some `capacity`, evicting the least-recently-transacted entries
+/// whenever the capacity is exceeded.
+///
+/// Each evicted `(PK, Obj)` pair that is still pending (see [`KeyMap::pending_keys`]) is passed
+/// to the `on_evict` callback, e.g. so it can be persisted; entries already persisted, such as
+/// those of the initial map, are evicted silently. Evicted keys are never reallocated (new keys
+/// are only allocated above the highest evicted key).
+///
+/// The bijection only holds in memory, by itself: an evicted value that is transacted again is
+/// allocated a new key. To keep it across the persisted entries too, give a loader with
+/// [`BoundedKeyMap::with_loader`], which finds the key of a value missing from memory (e.g. in
+/// whatever `on_evict` persisted to) before a new key is allocated.
+///
+/// ```rust
+/// use bimap::BiMap;
+/// use skopje::keymap::{BoundedKeyMap, KeyMap};
+///
+/// let mut evicted = Vec::new();
+/// let keymap: KeyMap<i8, String> = KeyMap::from(BiMap::new());
+/// let mut bounded = BoundedKeyMap::new(keymap, 2, |key, value| evicted.push((key, value)));
+///
+/// bounded.transact("zero".to_string());
+/// bounded.transact("one".to_string());
+/// bounded.transact("zero".to_string()); // "one" is now the least-recently transacted
+/// bounded.transact("two".to_string());
+///
+/// assert_eq!(bounded.keymap.bimap.len(), 2);
+/// assert_eq!(bounded.keymap.bimap.get_by_right("zero"), Some(&0));
+/// assert_eq!(bounded.keymap.bimap.get_by_right("two"), Some(&2));
+/// assert_eq!(bounded.keymap.pending_keys(), &[0, 2]); // "one" is handed to `on_evict` instead
+///
+/// drop(bounded);
+/// assert_eq!(evicted, vec![(1, "one".to_string())]);
+/// ```
+///
+/// With a loader, over the evicted entries:
+///
+/// ```rust
+/// use bimap::BiMap;
+/// use skopje::keymap::{BoundedKeyMap, KeyMap};
+/// use std::cell::RefCell;
+/// use std::collections::HashMap;
+///
+/// let persisted: RefCell<HashMap<String, i8>> = RefCell::default();
+/// let keymap: KeyMap<i8, String> = KeyMap::from(BiMap::new());
+/// let mut bounded = BoundedKeyMap::new(keymap, 1, |key, value| {
+///     persisted.borrow_mut().insert(value, key);
+/// })
+/// .with_loader(|value: &String| persisted.borrow().get(value).copied());
+///
+/// assert_eq!(bounded.transact("zero".to_string()), 0);
+/// assert_eq!(bounded.transact("one".to_string()), 1); // evicting "zero"
+/// assert_eq!(bounded.transact("zero".to_string()), 0); // loaded, not allocated again
+/// assert_eq!(bounded.transact("two".to_string()), 2);
+/// ```
+pub struct BoundedKeyMap<PK, Obj, F, L = fn(&Obj) -> Option<PK>>
+where
+    PK: Eq + PartialEq + Hash + PrimInt + for<'a> FromSql<'a> + AddAssign,
+    Obj: Eq + PartialEq + Hash + for<'a> FromSql<'a>,
+    F: FnMut(PK, Obj),
+    L: FnMut(&Obj) -> Option<PK>,
+{
+    pub keymap: KeyMap<PK, Obj>,
+    capacity: usize,
+    on_evict: F,
+    load: L,
+
+    // Recency tracking; a monotonic tick per transaction, ordered from least to most recent.
+    tick: u64,
+    recency: BTreeMap<u64, PK>,
+    last_used: HashMap<PK, u64>,
+
+    // The highest key evicted so far; new keys are only allocated above it, so an evicted key is
+    // never handed out again. Once it's the maximum key, the keyspace is exhausted.
+    high_water: Option<PK>,
+    exhausted: bool,
+}
+
+impl<PK, Obj, F> BoundedKeyMap<PK, Obj, F>
+where
+    PK: Eq + PartialEq + Hash + PrimInt + for<'a> FromSql<'a> + ToSql + AddAssign + Sync,
+    Obj: Eq + PartialEq + Hash + for<'a> FromSql<'a> + ToSql + Sync,
+    F: FnMut(PK, Obj),
+{
+    /// Bound an existing `KeyMap`; pre-existing entries are treated as used in ascending key
+    /// order, and are evicted immediately if they already exceed `capacity` (silently, unless
+    /// they're pending).
+    pub fn new(keymap: KeyMap<PK, Obj>, capacity: usize, on_evict: F) -> Self {
+        assert!(
+            capacity > 0,
+            "BoundedKeyMap capacity must be greater than 0"
+        );
+
+        let mut keys: Vec<PK> = keymap.bimap.left_values().copied().collect();
+        keys.sort();
+
+        let mut bounded = Self {
+            keymap,
+            capacity,
+            on_evict,
+            load: |_| None,
+            tick: 0,
+            recency: BTreeMap::new(),
+            last_used: HashMap::new(),
+            high_water: None,
+            exhausted: false,
+        };
+        for key in keys {
+            bounded.touch(key);
+        }
+        bounded.evict();
+        bounded.skip_evicted();
+        bounded
+    }
+
+    /// Look up each value missing from memory with `load`, e.g. among the entries persisted by
+    /// `on_evict`, before allocating it a new key; a value it finds is brought back into memory
+    /// with its existing key (as already persisted).
+    pub fn with_loader<L>(self, load: L) -> BoundedKeyMap<PK, Obj, F, L>
+    where
+        L: FnMut(&Obj) -> Option<PK>,
+    {
+        BoundedKeyMap {
+            keymap: self.keymap,
+            capacity: self.capacity,
+            on_evict: self.on_evict,
+            load,
+            tick: self.tick,
+            recency: self.recency,
+            last_used: self.last_used,
+            high_water: self.high_water,
+            exhausted: self.exhausted,
+        }
+    }
+}
+
+impl<PK, Obj, F, L> BoundedKeyMap<PK, Obj, F, L>
+where
+    PK: Eq + PartialEq + Hash + PrimInt + for<'a> FromSql<'a> + ToSql + AddAssign + Sync,
+    Obj: Eq + PartialEq + Hash + for<'a> FromSql<'a> + ToSql + Sync,
+    F: FnMut(PK, Obj),
+    L: FnMut(&Obj) -> Option<PK>,
+{
+    /// See [`KeyMap::transact`]; the transacted entry becomes the most-recently used, and the
+    /// least-recently used entries are evicted if the capacity is exceeded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a new value is transacted when the keyspace is exhausted, including by evicted
+    /// keys, which are never reallocated; see [`BoundedKeyMap::try_transact`].
+    pub fn transact(&mut self, value: Obj) -> PK {
+        self.try_transact(value)
+            .expect("BoundedKeyMap keyspace exhausted: no keys left to allocate")
+    }
+
+    /// See [`BoundedKeyMap::transact`]; but, if the value is new and there are no keys left to
+    /// allocate, return an error rather than panic.
+    ///
+    /// A loaded key that is already in memory, for another value, is an
+    /// [`Error::BijectionViolation`].
+    pub fn try_transact(&mut self, value: Obj) -> Result<PK> {
+        if !self.keymap.bimap.contains_right(&value) {
+            if let Some(key) = (self.load)(&value) {
+                if self.keymap.bimap.insert_no_overwrite(key, value).is_err() {
+                    return Err(Error::BijectionViolation(
+                        "the loaded key of a value is already mapped to another value".to_string(),
+                    ));
+                }
+                self.keymap.calc_next_key();
+                self.touch(key);
+                self.evict();
+                self.skip_evicted();
+                return Ok(key);
+            }
+            if self.exhausted {
+                return Err(Error::KeyspaceExhausted);
+            }
+        }
+        let key = self.keymap.try_transact(value)?;
+        self.touch(key);
+        self.evict();
+        self.skip_evicted();
+        Ok(key)
+    }
+
+    /// Mark `key` as the most-recently used.
+    fn touch(&mut self, key: PK) {
+        if let Some(previous) = self.last_used.insert(key, self.tick) {
+            self.recency.remove(&previous);
+        }
+        self.recency.insert(self.tick, key);
+        self.tick += 1;
+    }
+
+    /// Evict the least-recently used entries until the map is within capacity.
+    fn evict(&mut self) {
+        while self.keymap.bimap.len() > self.capacity {
+            let Some((_, key)) = self.recency.pop_first() else {
+                break;
+            };
+            self.last_used.remove(&key);
+            let pending = self.keymap.pending.iter().position(|k| *k == key);
+            if let Some(pending) = pending {
+                self.keymap.pending.remove(pending);
+            }
+            if let Some((key, value)) = self.keymap.bimap.remove_by_left(&key) {
+                self.high_water = self.high_water.max(Some(key));
+                // An entry already persisted needs no flushing.
+                if pending.is_some() {
+                    (self.on_evict)(key, value);
+                }
+            }
+        }
+    }
+
+    /// Move the next key above the highest evicted key, if it isn't already.
+    fn skip_evicted(&mut self) {
+        let Some(high_water) = self.high_water else {
+            return;
+        };
+        if self.keymap.next_key > high_water {
+            return;
+        }
+        match high_water.checked_add(&PK::one()) {
+            Some(key) => {
+                self.keymap.next_key = key;
+                self.keymap.calc_next_key();
+            }
+            None => self.exhausted = true,
+        }
+    }
+}
+
+/// A lightweight, append-only alternative to [`KeyMap`], for when reverse lookups are never
+/// needed and keys are strictly increasing.
+///
+/// A single `HashMap<Obj, PK>` is kept, rather than a [`BiMap`]'s double index, alongside a
+/// monotonic `next_key`; so keys freed from the middle of the range are never reused.
+///
+/// ```rust
+/// use bimap::BiMap;
+/// use skopje::KeyMap;
+/// use skopje::keymap::AppendKeyMap;
+///
+/// let mut keymap: KeyMap<i32, String> = KeyMap::from(BiMap::new());
+/// let mut append_keymap: AppendKeyMap<i32, String> = AppendKeyMap::new();
+///
+/// // from an empty start, keys are assigned identically
+/// for value in ["a", "b", "a", "c", "b"] {
+///     assert_eq!(
+///         keymap.transact(value.to_string()),
+///         append_keymap.transact(value.to_string())
+///     );
+/// }
+/// assert_eq!(append_keymap.see_next_key(), &3);
+/// ```
+#[derive(Debug)]
+pub struct AppendKeyMap<PK, Obj>
+where
+    PK: Eq + PartialEq + Hash + PrimInt + AddAssign,
+    Obj: Eq + PartialEq + Hash,
+{
+    pub map: HashMap<Obj, PK>,
+    pub next_key: PK,
+    exhausted: bool,
+}
+
+impl<PK, Obj> Default for AppendKeyMap<PK, Obj>
+where
+    PK: Eq + PartialEq + Hash + PrimInt + AddAssign,
+    Obj: Eq + PartialEq + Hash,
+{
+    fn default() -> Self {
+        Self {
+            map: HashMap::new(),
+            next_key: PK::zero(),
+            exhausted: false,
+        }
+    }
+}
+
+impl<PK, Obj> AppendKeyMap<PK, Obj>
+where
+    PK: Eq + PartialEq + Hash + PrimInt + AddAssign,
+    Obj: Eq + PartialEq + Hash,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Turn an existing map into an `AppendKeyMap`; the next key follows the highest existing key.
+    pub fn from(map: HashMap<Obj, PK>) -> Self {
+        let (next_key, exhausted) = match map.values().max() {
+            Some(max) => match max.checked_add(&PK::one()) {
+                Some(next_key) => (next_key, false),
+                None => (*max, true),
+            },
+            None => (PK::zero(), false),
+        };
+        Self {
+            map,
+            next_key,
+            exhausted,
+        }
+    }
+
+    /// Return a reference to the current `next_key`.
+    pub fn see_next_key(&self) -> &PK {
+        &self.next_key
+    }
+
+    /// See [`KeyMap::transact`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if a new value is transacted when the keyspace is exhausted; see
+    /// [`AppendKeyMap::try_transact`].
+    pub fn transact(&mut self, value: Obj) -> PK {
+        self.try_transact(value)
+            .expect("AppendKeyMap keyspace exhausted: no keys left to allocate")
+    }
+
+    /// See [`KeyMap::try_transact`].
+    pub fn try_transact(&mut self, value: Obj) -> Result<PK> {
+        if let Some(key) = self.map.get(&value) {
+            return Ok(*key);
+        }
+
+        if self.exhausted {
+            return Err(Error::KeyspaceExhausted);
+        }
+
+        // the key never overflows; once the maximum key is allocated, the keyspace is exhausted
+        let key = self.next_key;
+        self.map.insert(value, key);
+        match key.checked_add(&PK::one()) {
+            Some(next_key) => self.next_key = next_key,
+            None => self.exhausted = true,
         }
+        Ok(key)
     }
 }
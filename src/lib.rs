@@ -1,13 +1,92 @@
+//! Extract, transform & load pipelines, from HTTP, files & databases into Postgres.
+//!
+//! # Logging
+//!
+//! skopje logs through [`tracing`], and every event's target is the path of the module that
+//! emits it, e.g. `skopje::load::pg`; so skopje's verbosity can be set independently of the
+//! rest of an application, as a whole (`skopje=warn`), or per module
+//! (`skopje=warn,skopje::load=trace`), with an `EnvFilter` or any other target filter.
+//!
+//! ```rust
+//! use std::sync::{Arc, Mutex};
+//! use tracing::span::{Attributes, Id, Record};
+//! use tracing::{Event, Metadata, Subscriber};
+//!
+//! /// Records the target of every event.
+//! #[derive(Clone, Default)]
+//! struct Targets(Arc<Mutex<Vec<String>>>);
+//!
+//! impl Subscriber for Targets {
+//!     fn enabled(&self, _: &Metadata<'_>) -> bool { true }
+//!     fn new_span(&self, _: &Attributes<'_>) -> Id { Id::from_u64(1) }
+//!     fn record(&self, _: &Id, _: &Record<'_>) {}
+//!     fn record_follows_from(&self, _: &Id, _: &Id) {}
+//!     fn event(&self, event: &Event<'_>) {
+//!         self.0.lock().unwrap().push(event.metadata().target().to_string());
+//!     }
+//!     fn enter(&self, _: &Id) {}
+//!     fn exit(&self, _: &Id) {}
+//! }
+//!
+//! let targets = Targets::default();
+//! tracing::subscriber::with_default(targets.clone(), || {
+//!     let reader: &[u8] = b"[1, 2, 3]";
+//!     let _: Vec<u32> = futures::executor::block_on(skopje::extract::fs::read_json_from(reader))
+//!         .unwrap();
+//!     assert!(skopje::util::convert_date_type(&"not a date".to_string()).is_err());
+//! });
+//!
+//! let targets = targets.0.lock().unwrap();
+//! assert!(targets.contains(&"skopje::extract::fs".to_string()));
+//! assert!(targets.contains(&"skopje::util".to_string()));
+//! assert!(targets.iter().all(|target| target.starts_with("skopje::")));
+//! ```
+//!
+//! # Large numbers
+//!
+//! JSON integers are parsed exactly into the full range of `i64` & `u64`, including by the
+//! methods which go through a [`serde_json::Value`] (e.g. `fetch_path`, `fetch_one_or_many`,
+//! [`transform::Remap`]); so 64-bit IDs round-trip unchanged.
+//!
+//! Beyond 64 bits, a `Value` holds a number as an `f64`, losing precision; the
+//! `arbitrary-precision` feature enables `serde_json`'s `arbitrary_precision`, keeping each number
+//! as its original digits, e.g. for a `u128`, or a [`rust_decimal::Decimal`] without rounding.
+//! (It changes `serde_json` for the whole dependency graph, so is left off by default.)
+//!
+//! ```rust
+//! let value = serde_json::json!({"data": {"id": u64::MAX, "parent": i64::MIN}});
+//! let id: u64 = skopje::util::from_json_pointer(value.clone(), "/data/id").unwrap();
+//! let parent: i64 = skopje::util::from_json_pointer(value, "/data/parent").unwrap();
+//! assert_eq!((id, parent), (18446744073709551615, -9223372036854775808));
+//!
+//! let value: serde_json::Value = serde_json::from_str(r#"{"id": 1180591620717411303424}"#).unwrap();
+//! let id = skopje::util::from_json_pointer::<u128>(value, "/id");
+//! if cfg!(feature = "arbitrary-precision") {
+//!     assert_eq!(id.unwrap(), 1 << 70);
+//! } else {
+//!     assert!(id.is_err()); // held as an f64
+//! }
+//! ```
+
+pub mod error;
 pub mod etl;
 pub mod extract;
 pub mod keymap;
 pub mod load;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod pool;
+pub mod transform;
 pub mod util;
 
 pub use async_trait::async_trait;
 pub use deadpool_postgres::Pool as PgPool;
+#[cfg(feature = "jsonschema")]
+pub use jsonschema::Validator as JsonSchema;
 pub use postgres_types::{ToSql, Type};
 pub use reqwest::Client as HttpClient;
+pub use serde_json;
 
+pub use self::error::{Error, Result};
 pub use self::keymap::KeyMap;
-pub use skopje_macros::SqlMap;
+pub use skopje_macros::{FromRow, Remap, SqlMap, SqlTypes, extract};
@@ -0,0 +1,225 @@
+use crate::load::pg::{SqlMap, SqlTypes};
+use crate::{Error, Result};
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use postgres_types::{FromSql, IsNull};
+use serde_json::Value;
+use tokio_postgres::types::Type;
+use tracing::{error, trace};
+
+/// An extension for loading data to ClickHouse, as an alternative sink to Postgres.
+///
+/// The rows are mapped to the table's `columns` by the same [`SqlMap`] & [`SqlTypes`] traits as a
+/// Postgres load, so one struct can be loaded to both; each value is sent in ClickHouse's
+/// `JSONEachRow` format, as the JSON equivalent of its Postgres type: numbers & booleans as
+/// themselves, and text, `uuid`, `numeric`, dates & timestamps (`timestamptz` in UTC) as strings.
+///
+/// ```rust,no_run
+/// use skopje::load::clickhouse::ClickHouseLoadExt;
+///
+/// #[derive(skopje::SqlMap, skopje::SqlTypes)]
+/// struct Price {
+///     symbol: String,
+///     close: f64,
+/// }
+///
+/// async fn mirror(client: &clickhouse::Client, prices: &[Price]) -> skopje::Result<()> {
+///     client
+///         .insert_rows("prices", &["symbol", "close"], prices.iter())
+///         .await
+/// }
+/// ```
+#[async_trait]
+pub trait ClickHouseLoadExt {
+    /// INSERT the whole collection as a single statement.
+    ///
+    /// (Named apart from [`clickhouse::Client::insert`], which would otherwise shadow it.)
+    async fn insert_rows<'a, I, T>(
+        &self,
+        table: &'a str,
+        columns: &'a [&'a str],
+        collection: I,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send,
+        T: SqlMap + SqlTypes + Send;
+
+    /// INSERT the collection in statements of (at most) `batch_size` rows; the bulk-load
+    /// equivalent of `copy()`, for collections too large for a single INSERT.
+    ///
+    /// Each batch is committed on its own, so a failure leaves any previous batches loaded.
+    async fn insert_batched<'a, I, T>(
+        &self,
+        table: &'a str,
+        columns: &'a [&'a str],
+        collection: I,
+        batch_size: usize,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send,
+        T: SqlMap + SqlTypes + Send;
+}
+
+#[async_trait]
+impl ClickHouseLoadExt for clickhouse::Client {
+    async fn insert_rows<'a, I, T>(
+        &self,
+        table: &'a str,
+        columns: &'a [&'a str],
+        collection: I,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send,
+        T: SqlMap + SqlTypes + Send,
+    {
+        self.insert_batched(table, columns, collection, usize::MAX)
+            .await
+    }
+
+    async fn insert_batched<'a, I, T>(
+        &self,
+        table: &'a str,
+        columns: &'a [&'a str],
+        mut collection: I,
+        batch_size: usize,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send,
+        T: SqlMap + SqlTypes + Send,
+    {
+        let types = T::sql_types();
+        if columns.len() != types.len() {
+            error!(table = %table, "Failed to insert into ClickHouse: column count mismatch");
+            return Err(Error::ColumnCount {
+                expected: columns.len(),
+                found: types.len(),
+            });
+        }
+
+        let batch_size = batch_size.max(1);
+        let stmt = format!(
+            "INSERT INTO {table} ({}) FORMAT JSONEachRow",
+            columns.join(", ")
+        );
+        let mut rows = 0;
+
+        loop {
+            // Encode the next batch, one JSON object per line.
+            let mut batch = Vec::new();
+            let mut batch_rows = 0;
+            for row in collection.by_ref().take(batch_size) {
+                encode_row(&mut batch, columns, types, &row)?;
+                batch_rows += 1;
+            }
+            if batch_rows == 0 {
+                break;
+            }
+
+            let mut insert = self.insert_formatted_with(stmt.as_str());
+            insert.send(Bytes::from(batch)).await.map_err(|e| {
+                error!(table = %table, "Failed to write ClickHouse rows: {e}");
+                e
+            })?;
+            insert.end().await.map_err(|e| {
+                error!(table = %table, "Failed to end ClickHouse INSERT: {e}");
+                e
+            })?;
+            rows += batch_rows;
+            trace!(table = %table, rows = rows, "ClickHouse batch inserted");
+
+            if batch_rows < batch_size {
+                break;
+            }
+        }
+        trace!(table = %table, rows = rows, "ClickHouse INSERT executed successfully");
+
+        Ok(())
+    }
+}
+
+/// Append `row` to `buf`, as a `JSONEachRow` line.
+fn encode_row<T: SqlMap>(
+    buf: &mut Vec<u8>,
+    columns: &[&str],
+    types: &[Type],
+    row: &T,
+) -> Result<()> {
+    let values = row.sql_map();
+    if values.len() != types.len() {
+        return Err(Error::ColumnCount {
+            expected: types.len(),
+            found: values.len(),
+        });
+    }
+
+    // Written field by field, to keep the columns in order.
+    let mut raw = BytesMut::new();
+    buf.push(b'{');
+    for (i, ((column, value), ty)) in columns.iter().zip(values).zip(types).enumerate() {
+        raw.clear();
+        let json = match value
+            .to_sql_checked(ty, &mut raw)
+            .map_err(Error::serialize)?
+        {
+            IsNull::Yes => Value::Null,
+            IsNull::No => json_value(ty, &raw)?,
+        };
+        if i > 0 {
+            buf.push(b',');
+        }
+        serde_json::to_writer(&mut *buf, column)?;
+        buf.push(b':');
+        serde_json::to_writer(&mut *buf, &json)?;
+    }
+    buf.extend_from_slice(b"}\n");
+    Ok(())
+}
+
+/// The JSON equivalent of a (non-NULL) value, in the binary form of the Postgres type `ty`, as
+/// read by ClickHouse: integers, floats & booleans as themselves; text-like types (including
+/// `citext`), `uuid` & `numeric` as strings; dates and timestamps as `YYYY-MM-DD[ hh:mm:ss.ffffff]`
+/// strings, `timestamptz` in UTC. Any other type is an error.
+fn json_value(ty: &Type, raw: &[u8]) -> Result<Value> {
+    fn read<'a, T: FromSql<'a>>(ty: &Type, raw: &'a [u8]) -> Result<T> {
+        T::from_sql(ty, raw).map_err(Error::deserialize)
+    }
+
+    let value = match *ty {
+        Type::BOOL => Value::from(read::<bool>(ty, raw)?),
+        Type::INT2 => Value::from(read::<i16>(ty, raw)?),
+        Type::INT4 => Value::from(read::<i32>(ty, raw)?),
+        Type::INT8 => Value::from(read::<i64>(ty, raw)?),
+        Type::FLOAT4 => finite(read::<f32>(ty, raw)?.into())?,
+        Type::FLOAT8 => finite(read::<f64>(ty, raw)?)?,
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => {
+            Value::from(read::<String>(ty, raw)?)
+        }
+        Type::UUID => Value::from(read::<uuid::Uuid>(ty, raw)?.to_string()),
+        Type::NUMERIC => Value::from(read::<rust_decimal::Decimal>(ty, raw)?.to_string()),
+        Type::DATE => Value::from(read::<chrono::NaiveDate>(ty, raw)?.to_string()),
+        Type::TIMESTAMP => Value::from(
+            read::<chrono::NaiveDateTime>(ty, raw)?
+                .format("%Y-%m-%d %H:%M:%S%.6f")
+                .to_string(),
+        ),
+        Type::TIMESTAMPTZ => Value::from(
+            read::<chrono::DateTime<chrono::Utc>>(ty, raw)?
+                .format("%Y-%m-%d %H:%M:%S%.6f")
+                .to_string(),
+        ),
+        _ if ty.name() == "citext" => Value::from(read::<String>(ty, raw)?),
+        _ => {
+            return Err(Error::Unsupported(format!(
+                "no ClickHouse JSON value for Postgres type {ty}"
+            )));
+        }
+    };
+    Ok(value)
+}
+
+/// A float as a JSON number; NaN & infinity have none.
+fn finite(float: f64) -> Result<Value> {
+    serde_json::Number::from_f64(float)
+        .map(Value::Number)
+        .ok_or_else(|| Error::Unsupported(format!("no JSON number for {float}")))
+}
@@ -0,0 +1,137 @@
+use crate::Result;
+use async_trait::async_trait;
+use futures::stream::{FuturesUnordered, StreamExt};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+use serde::Serialize;
+use tracing::{error, trace};
+
+/// Maximum number of records awaiting their delivery ack at once, in [`KafkaLoadExt::produce`].
+pub const MAX_IN_FLIGHT: usize = 1000;
+
+/// An extension for loading data to Kafka topics, as a streaming sink alongside the databases.
+///
+/// Records are serialized as JSON, so the data needs to derive [`serde::Serialize`] (rather than
+/// the Postgres [`crate::load::pg::SqlMap`]).
+///
+/// ```rust
+/// use rdkafka::ClientConfig;
+/// use rdkafka::consumer::{BaseConsumer, Consumer};
+/// use rdkafka::message::Message;
+/// use rdkafka::mocking::MockCluster;
+/// use rdkafka::producer::FutureProducer;
+/// use skopje::load::kafka::KafkaLoadExt;
+/// use std::time::Duration;
+///
+/// #[derive(serde::Serialize)]
+/// struct Price {
+///     symbol: String,
+///     close: f64,
+/// }
+///
+/// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+/// rt.block_on(async {
+///     // An in-process mock broker.
+///     let cluster = MockCluster::new(1).unwrap();
+///     cluster.create_topic("prices", 1, 1).unwrap();
+///     let producer: FutureProducer = ClientConfig::new()
+///         .set("bootstrap.servers", cluster.bootstrap_servers())
+///         .create()
+///         .unwrap();
+///
+///     let prices = vec![
+///         Price { symbol: "AAPL".to_string(), close: 170.5 },
+///         Price { symbol: "MSFT".to_string(), close: 420.0 },
+///     ];
+///     let delivered = producer
+///         .produce("prices", |price| price.symbol.clone(), prices.iter())
+///         .await
+///         .unwrap();
+///     assert_eq!(delivered, 2);
+///
+///     // Read the topic back.
+///     let consumer: BaseConsumer = ClientConfig::new()
+///         .set("bootstrap.servers", cluster.bootstrap_servers())
+///         .set("group.id", "skopje-doctest")
+///         .set("auto.offset.reset", "earliest")
+///         .create()
+///         .unwrap();
+///     consumer.subscribe(&["prices"]).unwrap();
+///     let mut keys = Vec::new();
+///     while keys.len() < 2 {
+///         if let Some(message) = consumer.poll(Duration::from_millis(100)) {
+///             let message = message.unwrap();
+///             keys.push(String::from_utf8(message.key().unwrap().to_vec()).unwrap());
+///             if keys.len() == 1 {
+///                 assert_eq!(message.payload().unwrap(), br#"{"symbol":"AAPL","close":170.5}"#);
+///             }
+///         }
+///     }
+///     assert_eq!(keys, vec!["AAPL", "MSFT"]);
+/// });
+/// ```
+#[async_trait]
+pub trait KafkaLoadExt {
+    /// Produce each record to `topic`, as JSON, with the key given by `key_fn`, e.g. so that a
+    /// symbol's records share a partition; returns the number of records delivered, once every
+    /// delivery has been acknowledged.
+    ///
+    /// The first failed delivery is returned as an error; records already queued may still be
+    /// delivered.
+    async fn produce<'a, I, T, F>(&self, topic: &'a str, key_fn: F, records: I) -> Result<u64>
+    where
+        I: Iterator<Item = &'a T> + Send,
+        T: Serialize + Sync + 'a,
+        F: Fn(&T) -> String + Send + Sync;
+}
+
+#[async_trait]
+impl KafkaLoadExt for FutureProducer {
+    async fn produce<'a, I, T, F>(&self, topic: &'a str, key_fn: F, records: I) -> Result<u64>
+    where
+        I: Iterator<Item = &'a T> + Send,
+        T: Serialize + Sync + 'a,
+        F: Fn(&T) -> String + Send + Sync,
+    {
+        // Keep (at most) `MAX_IN_FLIGHT` deliveries awaiting their ack.
+        let mut in_flight = FuturesUnordered::new();
+        let mut delivered = 0;
+        for record in records {
+            let key = key_fn(record);
+            let payload = serde_json::to_vec(record)?;
+            in_flight.push(deliver(self, topic, key, payload));
+
+            if in_flight.len() >= MAX_IN_FLIGHT
+                && let Some(delivery) = in_flight.next().await
+            {
+                delivery?;
+                delivered += 1;
+            }
+        }
+        while let Some(delivery) = in_flight.next().await {
+            delivery?;
+            delivered += 1;
+        }
+        trace!(topic = %topic, records = delivered, "Kafka records delivered");
+
+        Ok(delivered)
+    }
+}
+
+/// Produce a serialized record to `topic`, awaiting its delivery ack.
+async fn deliver(
+    producer: &FutureProducer,
+    topic: &str,
+    key: String,
+    payload: Vec<u8>,
+) -> Result<()> {
+    let record = FutureRecord::to(topic).key(&key).payload(&payload);
+    producer
+        .send(record, Timeout::Never)
+        .await
+        .map_err(|(e, _)| {
+            error!(topic = %topic, key = %key, "Failed to deliver Kafka record: {e}");
+            e
+        })?;
+    Ok(())
+}
@@ -1 +1,7 @@
+#[cfg(feature = "clickhouse")]
+pub mod clickhouse;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "parquet")]
+pub mod parquet;
 pub mod pg;
@@ -0,0 +1,125 @@
+use crate::{Error, Result};
+use arrow_schema::{DataType, Field, FieldRef, TimeUnit};
+use async_trait::async_trait;
+use parquet::arrow::ArrowWriter;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio_postgres::types::{Kind, Type};
+use tracing::{error, trace};
+
+/// An extension for loading rows to a Parquet file, as a file-based sink for analytics stores.
+///
+/// Rows are converted to Arrow with `serde`, so the data needs to derive [`serde::Serialize`];
+/// each field maps to the column of the same name in the `schema`. The schema can be built from
+/// [`crate::load::pg::SqlTypes`] with [`schema_from_sql_types`], or traced from the rows
+/// themselves with [`serde_arrow::schema::SchemaLike::from_samples`].
+///
+/// ```rust
+/// use skopje::load::parquet::{ParquetLoadExt, schema_from_sql_types};
+/// use skopje::Type;
+///
+/// #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+/// struct Price {
+///     symbol: String,
+///     close: Option<f64>,
+/// }
+///
+/// let prices = vec![
+///     Price { symbol: "AAPL".to_string(), close: Some(170.5) },
+///     Price { symbol: "MSFT".to_string(), close: None },
+/// ];
+/// let schema = schema_from_sql_types(&["symbol", "close"], &[Type::TEXT, Type::FLOAT8]).unwrap();
+///
+/// let path = std::env::temp_dir().join("skopje-parquet-doctest/prices.parquet");
+/// let path = path.to_str().unwrap();
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// runtime.block_on(prices.write_parquet(path, &schema)).unwrap();
+///
+/// let read: Vec<Price> = runtime.block_on(skopje::extract::fs::read_parquet(path)).unwrap();
+/// assert_eq!(read, prices);
+/// ```
+#[async_trait]
+pub trait ParquetLoadExt {
+    /// Write the rows to a new Parquet file at `path`, replacing any existing file.
+    ///
+    /// Parent directories are created, as necessary.
+    async fn write_parquet(&self, path: &str, schema: &[FieldRef]) -> Result<()>;
+}
+
+#[async_trait]
+impl<T: Serialize + Sync> ParquetLoadExt for [T] {
+    async fn write_parquet(&self, path: &str, schema: &[FieldRef]) -> Result<()> {
+        trace!(filepath=%path, rows = self.len(), "converting rows to arrow");
+        let batch = serde_arrow::to_record_batch(schema, &self).map_err(|e| {
+            error!(filepath=%path, "failed to convert rows to arrow: {e}");
+            Error::deserialize(e)
+        })?;
+
+        // Encode the file in memory, to write it asynchronously.
+        let mut bytes = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut bytes, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+
+        trace!(filepath=%path, "writing parquet file");
+        tokio::fs::write(path, bytes).await?;
+        Ok(())
+    }
+}
+
+/// Build an Arrow schema for the `columns`, from their Postgres `types`, e.g. from
+/// [`crate::load::pg::SqlTypes::sql_types`]; every column is nullable.
+///
+/// Text-like types (including `citext` & `json`) map to `Utf8`, timestamps to microseconds, and
+/// arrays to lists of their element type; any other type is an error.
+pub fn schema_from_sql_types(columns: &[&str], types: &[Type]) -> Result<Vec<FieldRef>> {
+    if columns.len() != types.len() {
+        return Err(Error::ColumnCount {
+            expected: types.len(),
+            found: columns.len(),
+        });
+    }
+
+    columns
+        .iter()
+        .zip(types)
+        .map(|(column, ty)| Ok(Arc::new(Field::new(*column, arrow_type(ty)?, true))))
+        .collect()
+}
+
+/// The Arrow equivalent of a Postgres type.
+fn arrow_type(ty: &Type) -> Result<DataType> {
+    let data_type = match *ty {
+        Type::BOOL => DataType::Boolean,
+        Type::CHAR => DataType::Int8,
+        Type::INT2 => DataType::Int16,
+        Type::INT4 => DataType::Int32,
+        Type::INT8 => DataType::Int64,
+        Type::OID => DataType::UInt32,
+        Type::FLOAT4 => DataType::Float32,
+        Type::FLOAT8 => DataType::Float64,
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::JSON | Type::JSONB => {
+            DataType::Utf8
+        }
+        Type::BYTEA => DataType::Binary,
+        Type::DATE => DataType::Date32,
+        Type::TIME => DataType::Time64(TimeUnit::Microsecond),
+        Type::TIMESTAMP => DataType::Timestamp(TimeUnit::Microsecond, None),
+        Type::TIMESTAMPTZ => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        _ => match ty.kind() {
+            Kind::Array(element) => DataType::new_list(arrow_type(element)?, true),
+            _ if ty.name() == "citext" => DataType::Utf8,
+            _ => {
+                return Err(Error::Unsupported(format!(
+                    "no Arrow type for Postgres type {ty}"
+                )));
+            }
+        },
+    };
+    Ok(data_type)
+}
@@ -1,9 +1,14 @@
-use anyhow::Result;
+use crate::pool::timed;
+use crate::{Error, Result};
 use async_trait::async_trait;
-use futures::{StreamExt, stream};
+use futures::{Stream, StreamExt, stream};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
 use tokio_postgres::binary_copy::BinaryCopyInWriter;
-use tokio_postgres::types::{ToSql, Type};
-use tracing::{error, trace};
+use tokio_postgres::error::SqlState;
+use tokio_postgres::types::{Kind, ToSql, Type};
+use tracing::{debug, error, trace};
 
 /// An extension for asynchronous versions of INSERT & COPY for loading data to Postgres.
 ///
@@ -16,21 +21,799 @@ pub trait PgLoadExt {
         I: Iterator<Item = T> + Send + Sync,
         T: SqlMap + Send + Sync;
 
+    /// INSERT transaction, after sorting the collection by `key`; so concurrent loaders, with
+    /// overlapping keys, take their row locks in the same order, and can't deadlock each other
+    /// by each waiting on a row the other has already locked.
+    ///
+    /// ```rust,no_run
+    /// use skopje::PgPool;
+    /// use skopje::load::pg::PgLoadExt;
+    ///
+    /// #[derive(skopje::SqlMap)]
+    /// struct Price {
+    ///     symbol: String,
+    ///     close: f64,
+    /// }
+    ///
+    /// // two loaders, upserting the same symbols in opposite orders
+    /// async fn load_both(pool: &PgPool, a: &[Price], b: &[Price]) -> skopje::Result<()> {
+    ///     let stmt = "INSERT INTO prices (symbol, close) VALUES ($1, $2) \
+    ///                 ON CONFLICT (symbol) DO UPDATE SET close = EXCLUDED.close";
+    ///     let by_symbol = |price: &&Price| price.symbol.clone();
+    ///     futures::try_join!(
+    ///         pool.insert_ordered(stmt, a.iter(), by_symbol),
+    ///         pool.insert_ordered(stmt, b.iter().rev(), by_symbol),
+    ///     )?;
+    ///     Ok(())
+    /// }
+    /// ```
+    async fn insert_ordered<'a, I, T, K, F>(
+        &self,
+        stmt: &'a str,
+        collection: I,
+        key: F,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlMap + Send + Sync,
+        K: Ord,
+        F: FnMut(&T) -> K + Send;
+
+    /// INSERT transaction, checked by running `count_stmt` (e.g. `SELECT count(*) FROM table`)
+    /// before & after; if the count didn't grow by the size of the collection, e.g. rows were
+    /// silently dropped by a trigger or rule, an [`Error::LoadVerification`] is returned.
+    ///
+    /// The insert is committed either way. Concurrent writers to the same table skew the count,
+    /// so this suits loads with the table to themselves.
+    ///
+    /// ```rust,no_run
+    /// use skopje::PgPool;
+    /// use skopje::load::pg::PgLoadExt;
+    ///
+    /// #[derive(skopje::SqlMap)]
+    /// struct Price {
+    ///     symbol: String,
+    ///     close: f64,
+    /// }
+    ///
+    /// async fn load(pool: &PgPool, prices: &[Price]) -> skopje::Result<()> {
+    ///     match pool
+    ///         .insert_verified(
+    ///             "INSERT INTO prices (symbol, close) VALUES ($1, $2)",
+    ///             "SELECT count(*) FROM prices",
+    ///             prices.iter(),
+    ///         )
+    ///         .await
+    ///     {
+    ///         Err(skopje::Error::LoadVerification { expected, counted }) => {
+    ///             eprintln!("partial load: {counted} of {expected} rows arrived");
+    ///             Ok(())
+    ///         }
+    ///         result => result,
+    ///     }
+    /// }
+    /// ```
+    async fn insert_verified<'a, I, T>(
+        &self,
+        stmt: &'a str,
+        count_stmt: &'a str,
+        collection: I,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlMap + Send + Sync;
+
+    /// INSERT transaction, for a statement using `ON CONFLICT DO NOTHING`; returns how many rows
+    /// were actually inserted, and how many were skipped as conflicts.
+    async fn insert_ignore_conflicts<'a, I, T>(
+        &self,
+        stmt: &'a str,
+        collection: I,
+    ) -> Result<InsertStats>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlMap + Send + Sync;
+
+    /// Run a parameterised `INSERT ... SELECT` (or any other statement without client-side data),
+    /// in a transaction, for transforms done entirely in the database; returns the number of
+    /// rows inserted.
+    ///
+    /// ```rust,no_run
+    /// use skopje::PgPool;
+    /// use skopje::load::pg::PgLoadExt;
+    ///
+    /// async fn daily_highs(pool: &PgPool, date: chrono::NaiveDate) -> skopje::Result<u64> {
+    ///     pool.execute_insert_select(
+    ///         "INSERT INTO daily_highs (symbol, date, high) \
+    ///          SELECT symbol, $1, max(price) FROM ticks WHERE ts::date = $1 GROUP BY symbol",
+    ///         &[&date],
+    ///     )
+    ///     .await
+    /// }
+    /// ```
+    async fn execute_insert_select<'a>(
+        &self,
+        stmt: &'a str,
+        params: &'a [&'a (dyn ToSql + Sync)],
+    ) -> Result<u64>;
+
     /// COPY transactions cannot fail and still continue committing the rest of the data; any duplicate
     /// data (or any other failing circumstances) must be dealt with prior to the use of the `copy()` function.
     async fn copy<'a, I, T>(&self, stmt: &'a str, collection: I) -> Result<()>
     where
         I: Iterator<Item = T> + Send + Sync,
         T: SqlTypes + SqlMap + Send + Sync;
+
+    /// COPY, reporting progress by calling `progress` with the running row count every `every`
+    /// rows written (a final partial interval is not reported); an `every` of 0 disables it.
+    async fn copy_with_progress<'a, I, T, F>(
+        &self,
+        stmt: &'a str,
+        collection: I,
+        every: u64,
+        progress: F,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlTypes + SqlMap + Send + Sync,
+        F: FnMut(u64) + Send;
+
+    /// COPY, after dropping any rows whose `key` was already seen, keeping either the first or
+    /// the last of each; so a collection with duplicates can't fail the COPY with a unique
+    /// violation. See [`dedup_by_key`].
+    async fn copy_deduped<'a, I, T, K, F>(
+        &self,
+        stmt: &'a str,
+        collection: I,
+        key: F,
+        keep: Keep,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlTypes + SqlMap + Send + Sync,
+        K: Eq + std::hash::Hash + Send,
+        F: FnMut(&T) -> K + Send;
+
+    /// COPY the items of an extracted stream as they arrive, e.g. from
+    /// [`crate::extract::pg::PgExtractExt::fetch_try_stream`] on another database, without
+    /// collecting them first; returns the number of rows copied.
+    ///
+    /// Items are owned, so they're written by their [`SqlFields`] (which the [`SqlMap`] derive
+    /// implements). An error from the stream aborts the COPY, and nothing is committed.
+    ///
+    /// ```rust,no_run
+    /// use skopje::PgPool;
+    /// use skopje::extract::pg::PgExtractExt;
+    /// use skopje::load::pg::PgLoadExt;
+    ///
+    /// #[derive(skopje::SqlMap, skopje::SqlTypes)]
+    /// struct Price {
+    ///     symbol: String,
+    ///     close: f64,
+    /// }
+    ///
+    /// async fn replicate(source: &PgPool, target: &PgPool) -> skopje::Result<u64> {
+    ///     let prices = source
+    ///         .fetch_try_stream("SELECT symbol, close FROM prices", &[], |row| Price {
+    ///             symbol: row.get(0),
+    ///             close: row.get(1),
+    ///         })
+    ///         .await?;
+    ///     target
+    ///         .copy_from_stream("COPY prices (symbol, close) FROM STDIN BINARY", prices)
+    ///         .await
+    /// }
+    /// ```
+    async fn copy_from_stream<'a, S, T>(&self, stmt: &'a str, stream: S) -> Result<u64>
+    where
+        S: Stream<Item = Result<T>> + Send,
+        T: SqlFields + SqlTypes + Send;
+
+    /// COPY with the column `types` given at runtime, rather than by [`SqlTypes`]; e.g. for
+    /// extension types, such as `citext`, resolved with [`resolve_type`].
+    async fn copy_with_types<'a, I, T>(
+        &self,
+        stmt: &'a str,
+        types: &'a [Type],
+        collection: I,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlMap + Send + Sync;
+
+    /// COPY the collection into `table` through a staging table: the rows are copied into a
+    /// temporary copy of `table`, then moved across with an `INSERT ... SELECT`, suffixed by the
+    /// `merge` clause, e.g. `ON CONFLICT (symbol, date) DO NOTHING` (or an empty string).
+    ///
+    /// Each call stages into its own uniquely-named table (see [`staging_table_name`]), dropped
+    /// again when the transaction ends; so concurrent staged loads never clash. Returns the number
+    /// of rows moved into `table`.
+    async fn copy_staged<'a, I, T>(
+        &self,
+        table: &'a str,
+        merge: &'a str,
+        collection: I,
+    ) -> Result<u64>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlTypes + SqlMap + Send + Sync;
+
+    /// COPY the collection as a batch identified by `batch_key`, unless that batch has already
+    /// been loaded; returns whether it was loaded (`true`), or skipped (`false`).
+    ///
+    /// Loaded keys are recorded in the [`LOADED_BATCHES_TABLE`] (created if it doesn't exist),
+    /// in the same transaction as the COPY, so the marker & data are committed together and a
+    /// retried load can't double up.
+    ///
+    /// ```rust,no_run
+    /// use skopje::PgPool;
+    /// use skopje::load::pg::PgLoadExt;
+    ///
+    /// # #[derive(skopje::SqlMap, skopje::SqlTypes)]
+    /// # struct Price { symbol: String, close: f64 }
+    /// async fn load_day(pool: &PgPool, date: &str, prices: &[Price]) -> skopje::Result<()> {
+    ///     let stmt = "COPY prices (symbol, close) FROM STDIN BINARY";
+    ///     let key = format!("prices/{date}");
+    ///     if !pool.idempotent_load(&key, stmt, prices.iter()).await? {
+    ///         println!("{key} was already loaded");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    async fn idempotent_load<'a, I, T>(
+        &self,
+        batch_key: &'a str,
+        stmt: &'a str,
+        collection: I,
+    ) -> Result<bool>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlTypes + SqlMap + Send + Sync;
+
+    /// Run a bulk `load` into `table` with its indexes deferred: each of the `index_defs`, as
+    /// `(name, definition)`, is dropped (if it exists) before the load, then recreated from its
+    /// `CREATE INDEX` definition afterwards, in one transaction, and the table is analyzed.
+    ///
+    /// Building an index once over the loaded rows is far quicker than updating it row by row.
+    /// The load runs on clients of its own, so can't share a transaction with the drop & the
+    /// recreate; the indexes are recreated even if the load fails, which then returns its own
+    /// error (and a failure to recreate them is logged). If only the recreate fails, its error is
+    /// returned.
+    ///
+    /// ```rust,no_run
+    /// use skopje::PgPool;
+    /// use skopje::load::pg::PgLoadExt;
+    ///
+    /// # #[derive(skopje::SqlMap, skopje::SqlTypes)]
+    /// # struct Price { symbol: String, close: f64 }
+    /// async fn initial_load(pool: &PgPool, prices: &[Price]) -> skopje::Result<()> {
+    ///     let indexes = [(
+    ///         "prices_symbol_idx",
+    ///         "CREATE INDEX prices_symbol_idx ON prices (symbol)",
+    ///     )];
+    ///     pool.with_deferred_indexes("prices", &indexes, || {
+    ///         pool.copy("COPY prices (symbol, close) FROM STDIN BINARY", prices.iter())
+    ///     })
+    ///     .await
+    /// }
+    /// ```
+    async fn with_deferred_indexes<'a, F, Fut, R>(
+        &self,
+        table: &'a str,
+        index_defs: &'a [(&'a str, &'a str)],
+        load: F,
+    ) -> Result<R>
+    where
+        F: FnOnce() -> Fut + Send,
+        Fut: Future<Output = Result<R>> + Send,
+        R: Send;
+
+    /// COPY the collection into the `columns` of `table`, with the statement built by
+    /// [`copy_columns`]; unless `table` is a foreign table (e.g. over `postgres_fdw`), which may
+    /// not accept a binary COPY, in which case the rows are inserted instead, with
+    /// [`insert_stmt`]. See [`is_foreign_table`].
+    ///
+    /// ```rust,no_run
+    /// use skopje::PgPool;
+    /// use skopje::load::pg::PgLoadExt;
+    ///
+    /// # #[derive(skopje::SqlMap, skopje::SqlTypes)]
+    /// # struct Price { symbol: String, close: f64 }
+    /// async fn load(pool: &PgPool, prices: &[Price]) -> skopje::Result<()> {
+    ///     // a local table is COPYed into, and a foreign one inserted into
+    ///     pool.copy_into("remote.prices", &["symbol", "close"], prices.iter())
+    ///         .await
+    /// }
+    /// ```
+    async fn copy_into<'a, I, T>(
+        &self,
+        table: &'a str,
+        columns: &'a [&'a str],
+        collection: I,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlTypes + SqlMap + Send + Sync;
+
+    /// COPY with additional options, e.g. `FREEZE`; see [`copy_stmt_with_options`] for how the
+    /// statement is built.
+    async fn copy_with_options<'a, I, T>(
+        &self,
+        stmt: &'a str,
+        options: &'a str,
+        collection: I,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlTypes + SqlMap + Send + Sync;
+
+    /// Attempt a fast `copy()` first, and, if it fails on a unique violation, fall back to
+    /// `insert()` with the (upserting) `insert_stmt`.
+    ///
+    /// The failed COPY is rolled back entirely, so the collection must be cloneable to be
+    /// iterated a second time.
+    async fn load_auto<'a, I, T>(
+        &self,
+        insert_stmt: &'a str,
+        copy_stmt: &'a str,
+        collection: I,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Clone + Send + Sync,
+        T: SqlTypes + SqlMap + Send + Sync;
+
+    /// Upsert (INSERT ... ON CONFLICT) transaction, with the statement generated from column
+    /// lists; see [`upsert_stmt`].
+    async fn upsert_on<'a, I, T>(
+        &self,
+        table: &'a str,
+        columns: &'a [&'a str],
+        conflict_cols: &'a [&'a str],
+        update_cols: &'a [&'a str],
+        collection: I,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlMap + Send + Sync;
+}
+
+/// Counts of rows inserted, and skipped, by [`PgLoadExt::insert_ignore_conflicts`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InsertStats {
+    pub inserted: u64,
+    pub skipped: u64,
+}
+
+/// Which of the rows sharing a key [`dedup_by_key`] keeps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Keep {
+    #[default]
+    First,
+    Last,
+}
+
+/// Drop the items of `collection` whose `key` was already seen, keeping the first (or last) of
+/// each key, in the order each key first appears; e.g. before a `copy()`, which fails on
+/// duplicate keys.
+///
+/// ```rust
+/// use skopje::load::pg::{Keep, dedup_by_key};
+///
+/// let prices = vec![("AAPL", 1.0), ("MSFT", 2.0), ("AAPL", 3.0)];
+///
+/// let first = dedup_by_key(prices.iter(), |(symbol, _)| *symbol, Keep::First);
+/// assert_eq!(first, vec![&("AAPL", 1.0), &("MSFT", 2.0)]);
+///
+/// let last = dedup_by_key(prices.iter(), |(symbol, _)| *symbol, Keep::Last);
+/// assert_eq!(last, vec![&("AAPL", 3.0), &("MSFT", 2.0)]);
+/// ```
+pub fn dedup_by_key<I, T, K, F>(collection: I, mut key: F, keep: Keep) -> Vec<T>
+where
+    I: IntoIterator<Item = T>,
+    K: Eq + std::hash::Hash,
+    F: FnMut(&T) -> K,
+{
+    let mut positions: HashMap<K, usize> = HashMap::new();
+    let mut rows = Vec::new();
+    for item in collection {
+        match positions.entry(key(&item)) {
+            Entry::Occupied(position) => {
+                if keep == Keep::Last {
+                    rows[*position.get()] = item;
+                }
+            }
+            Entry::Vacant(position) => {
+                position.insert(rows.len());
+                rows.push(item);
+            }
+        }
+    }
+    rows
+}
+
+/// The table recording the batch keys loaded by [`PgLoadExt::idempotent_load`].
+pub const LOADED_BATCHES_TABLE: &str = "skopje_loaded_batches";
+
+/// A unique name for a staging table, for [`PgLoadExt::copy_staged`].
+///
+/// ```rust
+/// use skopje::load::pg::staging_table_name;
+///
+/// let name = staging_table_name();
+/// assert!(name.starts_with("skopje_staging_"));
+/// assert!(name.len() <= 63); // Postgres' identifier limit
+/// assert_ne!(name, staging_table_name());
+/// ```
+pub fn staging_table_name() -> String {
+    format!("skopje_staging_{}", uuid::Uuid::new_v4().simple())
+}
+
+/// Build an `INSERT` statement, binding values positionally in the order of `columns`; e.g. for
+/// the [`SqlTable`] of a struct.
+///
+/// ```rust
+/// use skopje::load::pg::{SqlTable, copy_columns, insert_stmt};
+///
+/// #[derive(skopje::SqlMap)]
+/// #[sql(table = "prices")]
+/// struct Price {
+///     #[sql(skip)]
+///     id: i64,
+///     symbol: String,
+///     #[sql(rename = "close_price")]
+///     close: f64,
+/// }
+///
+/// assert_eq!(Price::TABLE, "prices");
+/// assert_eq!(Price::COLUMNS, &["symbol", "close_price"]);
+/// assert_eq!(
+///     insert_stmt(Price::TABLE, Price::COLUMNS),
+///     "INSERT INTO prices (symbol, close_price) VALUES ($1, $2)"
+/// );
+/// assert_eq!(
+///     copy_columns(Price::TABLE, Price::COLUMNS),
+///     "COPY prices (symbol, close_price) FROM STDIN BINARY"
+/// );
+/// ```
+pub fn insert_stmt(table: &str, columns: &[&str]) -> String {
+    let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("${i}")).collect();
+    format!(
+        "INSERT INTO {table} ({}) VALUES ({})",
+        columns.join(", "),
+        placeholders.join(", ")
+    )
+}
+
+/// Build an `INSERT ... ON CONFLICT (...) DO UPDATE SET ...` statement.
+///
+/// Values are bound positionally, in the order of `columns`. If `update_cols` is empty, then
+/// conflicting rows are left as they are, with `DO NOTHING`.
+///
+/// ```rust
+/// use skopje::load::pg::upsert_stmt;
+///
+/// let stmt = upsert_stmt("prices", &["symbol", "date", "close"], &["symbol", "date"], &["close"]);
+/// assert_eq!(
+///     stmt,
+///     "INSERT INTO prices (symbol, date, close) VALUES ($1, $2, $3) \
+///      ON CONFLICT (symbol, date) DO UPDATE SET close = EXCLUDED.close"
+/// );
+///
+/// let stmt = upsert_stmt("prices", &["symbol", "date"], &["symbol", "date"], &[]);
+/// assert_eq!(
+///     stmt,
+///     "INSERT INTO prices (symbol, date) VALUES ($1, $2) ON CONFLICT (symbol, date) DO NOTHING"
+/// );
+/// ```
+pub fn upsert_stmt(
+    table: &str,
+    columns: &[&str],
+    conflict_cols: &[&str],
+    update_cols: &[&str],
+) -> String {
+    let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("${i}")).collect();
+    let action = if update_cols.is_empty() {
+        "DO NOTHING".to_string()
+    } else {
+        let updates: Vec<String> = update_cols
+            .iter()
+            .map(|col| format!("{col} = EXCLUDED.{col}"))
+            .collect();
+        format!("DO UPDATE SET {}", updates.join(", "))
+    };
+
+    format!(
+        "INSERT INTO {table} ({}) VALUES ({}) ON CONFLICT ({}) {action}",
+        columns.join(", "),
+        placeholders.join(", "),
+        conflict_cols.join(", "),
+    )
+}
+
+/// Build a binary COPY statement into the `columns` of `table`.
+///
+/// Columns the database fills in itself, e.g. `GENERATED ALWAYS AS IDENTITY` ids, can't be
+/// copied into, so they're left out of `columns`, and the matching fields marked
+/// `#[sql(skip)]`, keeping the [`SqlMap`] & [`SqlTypes`] of each row aligned with the statement:
+///
+/// ```rust
+/// use skopje::load::pg::{SqlMap, SqlTypes, copy_columns};
+/// use skopje::Type;
+///
+/// #[derive(skopje::SqlMap, skopje::SqlTypes)]
+/// struct Price {
+///     #[sql(skip)]
+///     id: i64,
+///     symbol: String,
+///     close: f64,
+/// }
+///
+/// let stmt = copy_columns("prices", &["symbol", "close"]);
+/// assert_eq!(stmt, "COPY prices (symbol, close) FROM STDIN BINARY");
+///
+/// let price = Price { id: 0, symbol: "AAPL".to_string(), close: 1.5 };
+/// assert_eq!((&price).sql_map().len(), 2);
+/// assert_eq!(Price::sql_types(), &[Type::TEXT, Type::FLOAT8]);
+/// ```
+///
+/// A statement whose columns don't match the number of types (e.g. a skipped field, still in
+/// `columns`) fails the COPY before any row is written, with an [`Error::ColumnCount`] naming
+/// both counts.
+pub fn copy_columns(table: &str, columns: &[&str]) -> String {
+    format!("COPY {table} ({}) FROM STDIN BINARY", columns.join(", "))
+}
+
+/// Build a binary COPY statement with some additional `options`.
+///
+/// The `stmt` should be given without any format or options, e.g. `COPY table (a, b) FROM STDIN`;
+/// `FORMAT binary` is always included, as required by the [`BinaryCopyInWriter`].
+///
+/// ```rust
+/// use skopje::load::pg::copy_stmt_with_options;
+///
+/// let stmt = copy_stmt_with_options("COPY prices (id, price) FROM STDIN", "FREEZE");
+/// assert_eq!(stmt, "COPY prices (id, price) FROM STDIN WITH (FORMAT binary, FREEZE)");
+///
+/// let stmt = copy_stmt_with_options("COPY prices (id, price) FROM STDIN;", "");
+/// assert_eq!(stmt, "COPY prices (id, price) FROM STDIN WITH (FORMAT binary)");
+/// ```
+pub fn copy_stmt_with_options(stmt: &str, options: &str) -> String {
+    let stmt = stmt.trim().trim_end_matches(';').trim_end();
+    let options = options.trim();
+    if options.is_empty() {
+        format!("{stmt} WITH (FORMAT binary)")
+    } else {
+        format!("{stmt} WITH (FORMAT binary, {options})")
+    }
+}
+
+/// Whether `table` (optionally schema-qualified, and resolved through the `search_path`) is a
+/// foreign table, e.g. over `postgres_fdw`; `false` for any other relation, or none at all.
+pub async fn is_foreign_table(pool: &deadpool_postgres::Pool, table: &str) -> Result<bool> {
+    let stmt =
+        "SELECT COALESCE((SELECT relkind = 'f' FROM pg_class WHERE oid = to_regclass($1)), false)";
+    let client = pool.get().await?;
+    let row = timed(stmt, client.query_one(stmt, &[&table]))
+        .await
+        .map_err(|e| {
+            error!(table = %table, "Failed to look up the table kind: {e}");
+            e
+        })?;
+    Ok(row.get(0))
+}
+
+/// Resolve a Postgres type by name, e.g. `citext` or a custom enum, which has no constant in
+/// [`Type`]; e.g. for [`PgLoadExt::copy_with_types`].
+///
+/// The type's OID (and kind) is looked up from `pg_type` once per name, and cached for as long as
+/// the pool lives; an extension type's OID differs between databases, so each pool has its own
+/// cache.
+///
+/// ```rust,no_run
+/// use skopje::load::pg::{PgLoadExt, resolve_type};
+/// use skopje::{PgPool, Type};
+///
+/// #[derive(skopje::SqlMap)]
+/// struct User {
+///     id: i32,
+///     email: String,
+/// }
+///
+/// async fn load_users(pool: &PgPool, users: &[User]) -> skopje::Result<()> {
+///     let types = [Type::INT4, resolve_type(pool, "citext").await?];
+///     pool.copy_with_types("COPY users (id, email) FROM STDIN BINARY", &types, users.iter())
+///         .await
+/// }
+/// ```
+pub async fn resolve_type(pool: &deadpool_postgres::Pool, type_name: &str) -> Result<Type> {
+    if let Some(ty) = cached_type(pool, type_name) {
+        return Ok(ty);
+    }
+
+    let client = pool.get().await?;
+
+    // The name is bound, rather than spliced into the SQL; `regtype` checks it names a type, and
+    // gives it back in its canonical, quoted form.
+    let row = client
+        .query_one("SELECT $1::text::regtype::text", &[&type_name])
+        .await
+        .map_err(|e| {
+            error!("Failed to resolve Postgres type {type_name:?}: {e}");
+            e
+        })?;
+    let canonical: String = row.get(0);
+
+    // Preparing a typed NULL has tokio-postgres look up the full type information from `pg_type`.
+    let stmt = client
+        .prepare(&format!("SELECT NULL::{canonical}"))
+        .await
+        .map_err(|e| {
+            error!("Failed to resolve Postgres type {type_name:?}: {e}");
+            e
+        })?;
+    let ty = stmt.columns()[0].type_().clone();
+    debug!("resolved Postgres type {type_name:?} to OID {}", ty.oid());
+
+    drop(client); // guarantee the postgres client drops back to the pool
+
+    cache_type(pool, type_name, ty.clone());
+    Ok(ty)
+}
+
+/// A placeholder for the type named `type_name`, resolved (with [`resolve_type`]) only once the
+/// pool it's loaded into is known; see [`resolve_types`].
+///
+/// Fields marked `#[sql(type_name = "...")]` in the [`skopje::SqlTypes`](crate::SqlTypes)
+/// derive have this type.
+pub fn unresolved_type(type_name: &str) -> Type {
+    Type::new(type_name.to_string(), 0, Kind::Pseudo, String::new())
+}
+
+/// The column `types`, with any [`unresolved_type`] placeholders resolved against `pool`.
+///
+/// [`PgLoadExt`] resolves the [`SqlTypes`] of its rows with this, before the COPY.
+pub async fn resolve_types<'t>(
+    pool: &deadpool_postgres::Pool,
+    types: &'t [Type],
+) -> Result<Cow<'t, [Type]>> {
+    if types.iter().all(|ty| ty.oid() != 0) {
+        return Ok(Cow::Borrowed(types));
+    }
+
+    let mut resolved = Vec::with_capacity(types.len());
+    for ty in types {
+        resolved.push(match ty.oid() {
+            0 => resolve_type(pool, ty.name()).await?,
+            _ => ty.clone(),
+        });
+    }
+    Ok(Cow::Owned(resolved))
+}
+
+/// The types resolved for each live pool, by name; a dropped pool's entry is pruned when the next
+/// type is cached.
+type TypeCache = Vec<(deadpool_postgres::WeakPool, HashMap<String, Type>)>;
+
+fn type_cache() -> &'static std::sync::Mutex<TypeCache> {
+    static RESOLVED: std::sync::OnceLock<std::sync::Mutex<TypeCache>> = std::sync::OnceLock::new();
+    RESOLVED.get_or_init(Default::default)
+}
+
+/// Whether `weak` refers to `pool`; pools are told apart by their (shared) manager.
+fn is_pool(weak: &deadpool_postgres::WeakPool, pool: &deadpool_postgres::Pool) -> bool {
+    weak.upgrade()
+        .is_some_and(|live| std::ptr::eq(live.manager(), pool.manager()))
+}
+
+fn cached_type(pool: &deadpool_postgres::Pool, type_name: &str) -> Option<Type> {
+    let cache = type_cache().lock().expect("type cache lock");
+    cache
+        .iter()
+        .find(|(weak, _)| is_pool(weak, pool))
+        .and_then(|(_, types)| types.get(type_name).cloned())
+}
+
+fn cache_type(pool: &deadpool_postgres::Pool, type_name: &str, ty: Type) {
+    let mut cache = type_cache().lock().expect("type cache lock");
+    cache.retain(|(weak, _)| weak.upgrade().is_some());
+    match cache.iter_mut().find(|(weak, _)| is_pool(weak, pool)) {
+        Some((_, types)) => {
+            types.insert(type_name.to_string(), ty);
+        }
+        None => cache.push((pool.weak(), HashMap::from([(type_name.to_string(), ty)]))),
+    }
 }
 
 /// Provide a SQL mapping for the item struct.
 ///
 /// See [`postgres_types::types::ToSql`] for more detail.
+///
+/// `Option<T>` fields map `None` to NULL, through both `insert()` and the binary `copy()`:
+///
+/// ```rust
+/// use bytes::BytesMut;
+/// use postgres_types::IsNull;
+/// use skopje::load::pg::{SqlMap, SqlTypes};
+/// use skopje::{ToSql, Type};
+///
+/// #[derive(skopje::SqlMap)]
+/// struct Price {
+///     symbol: String,
+///     close: Option<f64>,
+/// }
+///
+/// impl SqlTypes for Price {
+///     fn sql_types() -> &'static [Type] {
+///         &[Type::TEXT, Type::FLOAT8]
+///     }
+/// }
+///
+/// let price = Price {
+///     symbol: "AAPL".to_string(),
+///     close: None,
+/// };
+/// let price = &price;
+/// let row = price.sql_map();
+/// let types = Price::sql_types();
+///
+/// // the `BinaryCopyInWriter` writes an `IsNull::Yes` value as NULL
+/// let mut buf = BytesMut::new();
+/// assert!(matches!(row[0].to_sql_checked(&types[0], &mut buf).unwrap(), IsNull::No));
+/// assert!(matches!(row[1].to_sql_checked(&types[1], &mut buf).unwrap(), IsNull::Yes));
+/// ```
 pub trait SqlMap {
     fn sql_map(&self) -> Vec<&(dyn ToSql + Sync)>;
 }
 
+/// The fields of a struct, as SQL values; implemented by the [`skopje::SqlMap`](crate::SqlMap)
+/// derive, alongside `SqlMap`, so that the struct can be `#[sql(flatten)]`-ed into another:
+///
+/// ```rust
+/// use skopje::load::pg::{SqlMap, SqlTypes};
+/// use skopje::Type;
+///
+/// #[derive(skopje::SqlMap, skopje::SqlTypes)]
+/// struct Address {
+///     street: String,
+///     city: String,
+/// }
+///
+/// #[derive(skopje::SqlMap, skopje::SqlTypes)]
+/// struct Customer {
+///     id: i32,
+///     #[sql(flatten)]
+///     address: Address,
+///     active: bool,
+/// }
+///
+/// let customer = Customer {
+///     id: 1,
+///     address: Address {
+///         street: "1 Main St".to_string(),
+///         city: "Skopje".to_string(),
+///     },
+///     active: true,
+/// };
+/// let customer = &customer;
+/// assert_eq!(customer.sql_map().len(), 4);
+/// assert_eq!(
+///     Customer::sql_types(),
+///     &[Type::INT4, Type::TEXT, Type::TEXT, Type::BOOL]
+/// );
+/// ```
+pub trait SqlFields {
+    fn sql_fields(&self) -> Vec<&(dyn ToSql + Sync)>;
+}
+
+/// The table a struct is loaded to, and the columns of its [`SqlMap`] values, in order; derived
+/// by [`skopje::SqlMap`](crate::SqlMap) for a struct marked `#[sql(table = "...")]`.
+///
+/// See [`insert_stmt`] & [`copy_columns`] for statements built from them.
+pub trait SqlTable {
+    const TABLE: &'static str;
+    const COLUMNS: &'static [&'static str];
+}
+
 /// Auto implementation for all values that impl ToSql + Sync.
 impl<T: ToSql + Sync> SqlMap for &T {
     fn sql_map(&self) -> Vec<&(dyn ToSql + Sync)> {
@@ -41,10 +824,104 @@ impl<T: ToSql + Sync> SqlMap for &T {
 /// Provide the SQL types required.
 ///
 /// See [`postgres_types::types::ToSql`] for more detail.
+///
+/// Usually derived, with [`skopje::SqlTypes`](crate::SqlTypes); `Vec<T>` fields map to the
+/// Postgres array of `T`, e.g. `text[]` & `int4[]`, and are written as arrays by both `insert()`
+/// and the binary `copy()`:
+///
+/// ```rust
+/// use bytes::BytesMut;
+/// use postgres_types::IsNull;
+/// use skopje::load::pg::{SqlMap, SqlTypes};
+/// use skopje::{ToSql, Type};
+///
+/// #[derive(skopje::SqlMap, skopje::SqlTypes)]
+/// struct Stock {
+///     symbol: String,
+///     tags: Vec<String>,
+///     volumes: Vec<i32>,
+/// }
+///
+/// assert_eq!(Stock::sql_types(), &[Type::TEXT, Type::TEXT_ARRAY, Type::INT4_ARRAY]);
+///
+/// let stock = Stock {
+///     symbol: "AAPL".to_string(),
+///     tags: vec!["tech".to_string()],
+///     volumes: vec![100, 200],
+/// };
+/// let stock = &stock;
+/// let row = stock.sql_map();
+///
+/// let mut buf = BytesMut::new();
+/// for (value, ty) in row.iter().zip(<&Stock>::sql_types()) {
+///     assert!(matches!(value.to_sql_checked(ty, &mut buf).unwrap(), IsNull::No));
+/// }
+/// ```
+///
+/// Rust enums map to Postgres `ENUM` columns by deriving `postgres_types::ToSql` (& `FromSql`);
+/// as an `ENUM` has no [`Type`] constant, it's named with `#[sql(type_name = "...")]`, and is
+/// resolved at runtime, against the pool it's loaded into (see [`resolve_types`]), before the COPY:
+///
+/// ```rust
+/// use skopje::load::pg::SqlTypes;
+///
+/// #[derive(Debug, postgres_types::ToSql, postgres_types::FromSql)]
+/// #[postgres(name = "mood", rename_all = "snake_case")]
+/// enum Mood {
+///     Happy,
+///     Sad,
+/// }
+///
+/// #[derive(skopje::SqlMap, skopje::SqlTypes)]
+/// struct Person {
+///     name: String,
+///     #[sql(type_name = "mood")]
+///     mood: Mood,
+/// }
+///
+/// // an unresolved placeholder, until the rows are copied into a database
+/// let mood = &Person::sql_types()[1];
+/// assert_eq!((mood.name(), mood.oid()), ("mood", 0));
+/// ```
+///
+/// `uuid::Uuid` fields map to `uuid` columns, and are written in the same binary form read back
+/// by a `fetch`:
+///
+/// ```rust
+/// use bytes::BytesMut;
+/// use postgres_types::FromSql;
+/// use skopje::load::pg::{SqlMap, SqlTypes};
+/// use skopje::{ToSql, Type};
+/// use uuid::Uuid;
+///
+/// #[derive(skopje::SqlMap, skopje::SqlTypes)]
+/// struct Account {
+///     id: Uuid,
+///     owner: Option<Uuid>,
+/// }
+///
+/// assert_eq!(Account::sql_types(), &[Type::UUID, Type::UUID]);
+///
+/// let account = Account {
+///     id: Uuid::new_v4(),
+///     owner: None,
+/// };
+/// let account_ref = &account;
+/// let mut buf = BytesMut::new();
+/// account_ref.sql_map()[0].to_sql_checked(&Type::UUID, &mut buf).unwrap();
+/// assert_eq!(Uuid::from_sql(&Type::UUID, &buf).unwrap(), account.id);
+/// ```
 pub trait SqlTypes {
     fn sql_types() -> &'static [Type];
 }
 
+/// References share the SQL types of what they refer to, e.g. when copying from `iter()`.
+impl<T: SqlTypes> SqlTypes for &T {
+    fn sql_types() -> &'static [Type] {
+        T::sql_types()
+    }
+}
+
 #[async_trait]
 impl PgLoadExt for &deadpool_postgres::Pool {
     async fn insert<'a, I, T>(&self, stmt: &'a str, collection: I) -> Result<()>
@@ -52,67 +929,682 @@ impl PgLoadExt for &deadpool_postgres::Pool {
         I: Iterator<Item = T> + Send + Sync,
         T: SqlMap + Send + Sync,
     {
-        // Get a client from the Pool.
-        let mut pg_client = self.get().await?;
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        let inserted = insert_rows(self, stmt, collection).await;
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_load("insert", started, &inserted, |rows| *rows);
+        inserted.map(|_| ())
+    }
 
-        // Start a transaction with a prepared statement.
-        let stmt = pg_client.prepare(stmt).await?;
-        let tx = pg_client.transaction().await?;
+    async fn insert_ordered<'a, I, T, K, F>(
+        &self,
+        stmt: &'a str,
+        collection: I,
+        key: F,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlMap + Send + Sync,
+        K: Ord,
+        F: FnMut(&T) -> K + Send,
+    {
+        let mut rows: Vec<T> = collection.collect();
+        rows.sort_by_cached_key(key);
+        debug!(rows = rows.len(), "inserting rows in key order");
+        self.insert(stmt, rows.into_iter()).await
+    }
 
-        // Stream the symbols & insert them to the database.
-        let mut stream = stream::iter(collection.into_iter());
-        while let Some(item) = stream.next().await {
-            let stmt = &stmt;
-            let tx = &tx;
-            tx.execute(stmt, &item.sql_map()).await?;
-            // async move {
-            //     match tx.execute(stmt, &item.sql_map()).await {
-            //         Ok(_) => {}
-            //         Err(e) => return Err(e),
-            //     };
-
-            //     Ok::<(), anyhow::Error>(());
-            // }
-            // .await;
-        }
-        trace!("{stmt:?} executed successfully");
+    async fn insert_verified<'a, I, T>(
+        &self,
+        stmt: &'a str,
+        count_stmt: &'a str,
+        collection: I,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlMap + Send + Sync,
+    {
+        let rows: Vec<T> = collection.collect();
+        let expected = rows.len() as u64;
 
-        // Commit the transaction.
-        tx.commit().await?;
+        let before = count_rows(self, count_stmt).await?;
+        self.insert(stmt, rows.into_iter()).await?;
+        let after = count_rows(self, count_stmt).await?;
 
-        drop(pg_client); // guarantee the postgres client drops back to the pool
+        let counted = after - before;
+        if counted != expected as i64 {
+            error!(
+                expected = expected,
+                counted = counted,
+                "{stmt:?} failed verification by {count_stmt:?}"
+            );
+            return Err(Error::LoadVerification { expected, counted });
+        }
+        trace!(rows = expected, "{stmt:?} verified by {count_stmt:?}");
 
         Ok(())
     }
 
+    async fn insert_ignore_conflicts<'a, I, T>(
+        &self,
+        stmt: &'a str,
+        collection: I,
+    ) -> Result<InsertStats>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlMap + Send + Sync,
+    {
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        let loaded = async {
+            // Get a client from the Pool.
+            let mut pg_client = self.get().await?;
+
+            // Start a transaction with a prepared statement.
+            let sql = stmt;
+            let stmt = pg_client.prepare(stmt).await?;
+            let tx = pg_client.transaction().await?;
+
+            // Each row inserted affects 1 row, and each conflicting row skipped affects 0.
+            let mut stats = InsertStats::default();
+            for item in collection {
+                match timed(sql, tx.execute(&stmt, &item.sql_map())).await? {
+                    0 => stats.skipped += 1,
+                    _ => stats.inserted += 1,
+                }
+            }
+            trace!(
+                inserted = stats.inserted,
+                skipped = stats.skipped,
+                "{stmt:?} executed successfully"
+            );
+
+            // Commit the transaction.
+            tx.commit().await?;
+
+            drop(pg_client); // guarantee the postgres client drops back to the pool
+
+            Ok::<_, Error>(stats)
+        }
+        .await;
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_load("insert_ignore_conflicts", started, &loaded, |stats| {
+            stats.inserted
+        });
+        loaded
+    }
+
+    async fn execute_insert_select<'a>(
+        &self,
+        stmt: &'a str,
+        params: &'a [&'a (dyn ToSql + Sync)],
+    ) -> Result<u64> {
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        let loaded = async {
+            // Get a client from the Pool.
+            let mut pg_client = self.get().await?;
+            let tx = pg_client.transaction().await?;
+
+            let rows = timed(stmt, tx.execute(stmt, params)).await.map_err(|e| {
+                error!("Failed to execute {stmt:?}: {e}");
+                e
+            })?;
+            trace!(rows = rows, "{stmt:?} executed successfully");
+
+            // Commit the transaction.
+            tx.commit().await?;
+
+            drop(pg_client); // guarantee the postgres client drops back to the pool
+
+            Ok::<_, Error>(rows)
+        }
+        .await;
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_load("execute_insert_select", started, &loaded, |rows| *rows);
+        loaded
+    }
+
     async fn copy<'a, I, T>(&self, stmt: &'a str, collection: I) -> Result<()>
     where
         I: Iterator<Item = T> + Send + Sync,
         T: SqlTypes + SqlMap + Send + Sync,
     {
-        // Get a client from the Pool.
+        self.copy_with_progress(stmt, collection, 0, |_| {}).await
+    }
+
+    async fn copy_with_progress<'a, I, T, F>(
+        &self,
+        stmt: &'a str,
+        collection: I,
+        every: u64,
+        progress: F,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlTypes + SqlMap + Send + Sync,
+        F: FnMut(u64) + Send,
+    {
+        copy_rows(self, stmt, T::sql_types(), collection, every, progress).await
+    }
+
+    async fn copy_deduped<'a, I, T, K, F>(
+        &self,
+        stmt: &'a str,
+        collection: I,
+        key: F,
+        keep: Keep,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlTypes + SqlMap + Send + Sync,
+        K: Eq + std::hash::Hash + Send,
+        F: FnMut(&T) -> K + Send,
+    {
+        let rows = dedup_by_key(collection, key, keep);
+        debug!(rows = rows.len(), "copying deduplicated rows");
+        self.copy(stmt, rows.into_iter()).await
+    }
+
+    async fn copy_from_stream<'a, S, T>(&self, stmt: &'a str, stream: S) -> Result<u64>
+    where
+        S: Stream<Item = Result<T>> + Send,
+        T: SqlFields + SqlTypes + Send,
+    {
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        let loaded = async {
+            let types = resolve_types(self, T::sql_types()).await?;
+
+            // Get a client from the Pool.
+            let mut pg_client = self.get().await?;
+            let tx = pg_client.transaction().await?;
+
+            let rows = copy_stream_in(&tx, stmt, &types, stream, T::sql_fields, 0, |_| {}).await?;
+            trace!(rows = rows, "{stmt:?} executed successfully");
+
+            // Commit the transaction.
+            tx.commit().await?;
+
+            drop(pg_client); // guarantee the postgres client drops back to the pool
+
+            Ok::<_, Error>(rows)
+        }
+        .await;
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_load("copy_from_stream", started, &loaded, |rows| *rows);
+        loaded
+    }
+
+    async fn copy_with_types<'a, I, T>(
+        &self,
+        stmt: &'a str,
+        types: &'a [Type],
+        collection: I,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlMap + Send + Sync,
+    {
+        copy_rows(self, stmt, types, collection, 0, |_| {}).await
+    }
+
+    async fn copy_staged<'a, I, T>(
+        &self,
+        table: &'a str,
+        merge: &'a str,
+        collection: I,
+    ) -> Result<u64>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlTypes + SqlMap + Send + Sync,
+    {
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        let loaded = async {
+            let types = resolve_types(self, T::sql_types()).await?;
+
+            // Get a client from the Pool.
+            let mut pg_client = self.get().await?;
+            let tx = pg_client.transaction().await?;
+
+            // Stage into a table of our own, which is dropped again as the transaction ends.
+            let staging = staging_table_name();
+            let create =
+                format!("CREATE TEMP TABLE {staging} (LIKE {table} INCLUDING DEFAULTS) ON COMMIT DROP");
+            timed(&create, tx.batch_execute(&create))
+                .await
+                .map_err(|e| {
+                    error!(table = %table, "Failed to create staging table: {e}");
+                    e
+                })?;
+
+            let copy = format!("COPY {staging} FROM STDIN BINARY");
+            copy_rows_in(&tx, &copy, &types, collection, 0, |_| {}).await?;
+
+            let insert = format!("INSERT INTO {table} SELECT * FROM {staging} {merge}");
+            let rows = timed(&insert, tx.execute(insert.trim_end(), &[]))
+                .await
+                .map_err(|e| {
+                    error!(table = %table, "Failed to move staged rows: {e}");
+                    e
+                })?;
+            trace!(table = %table, staging = %staging, rows = rows, "staged COPY executed successfully");
+
+            // Commit the transaction.
+            tx.commit().await?;
+
+            drop(pg_client); // guarantee the postgres client drops back to the pool
+
+            Ok::<_, Error>(rows)
+        }
+        .await;
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_load("copy_staged", started, &loaded, |rows| *rows);
+        loaded
+    }
+
+    async fn idempotent_load<'a, I, T>(
+        &self,
+        batch_key: &'a str,
+        stmt: &'a str,
+        collection: I,
+    ) -> Result<bool>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlTypes + SqlMap + Send + Sync,
+    {
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        // The rows copied, or `None` if the batch was already loaded.
+        let loaded = async {
+            let types = resolve_types(self, T::sql_types()).await?;
+
+            // Get a client from the Pool.
+            let mut pg_client = self.get().await?;
+            let tx = pg_client.transaction().await?;
+
+            let create = format!(
+                "CREATE TABLE IF NOT EXISTS {LOADED_BATCHES_TABLE} \
+                 (batch_key TEXT PRIMARY KEY, loaded_at TIMESTAMPTZ NOT NULL DEFAULT now())"
+            );
+            timed(&create, tx.batch_execute(&create)).await?;
+
+            // Claim the key; a concurrent load of the same batch waits here until this one commits
+            // (or rolls back).
+            let claim = format!(
+                "INSERT INTO {LOADED_BATCHES_TABLE} (batch_key) VALUES ($1) ON CONFLICT DO NOTHING"
+            );
+            if timed(&claim, tx.execute(&claim, &[&batch_key])).await? == 0 {
+                debug!(batch_key = %batch_key, "batch already loaded - skipping");
+                return Ok(None);
+            }
+
+            let rows = copy_rows_in(&tx, stmt, &types, collection, 0, |_| {}).await?;
+            trace!(batch_key = %batch_key, "idempotent load executed successfully");
+
+            // Commit the marker & the data together.
+            tx.commit().await?;
+
+            drop(pg_client); // guarantee the postgres client drops back to the pool
+
+            Ok::<_, Error>(Some(rows))
+        }
+        .await;
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_load("idempotent_load", started, &loaded, |rows| {
+            rows.unwrap_or(0)
+        });
+        loaded.map(|rows| rows.is_some())
+    }
+
+    async fn with_deferred_indexes<'a, F, Fut, R>(
+        &self,
+        table: &'a str,
+        index_defs: &'a [(&'a str, &'a str)],
+        load: F,
+    ) -> Result<R>
+    where
+        F: FnOnce() -> Fut + Send,
+        Fut: Future<Output = Result<R>> + Send,
+        R: Send,
+    {
+        // Drop the indexes; the client goes back to the pool before the load, which may need it.
         let mut pg_client = self.get().await?;
         let tx = pg_client.transaction().await?;
-        let sink = tx.copy_in(stmt).await?;
-        let writer = BinaryCopyInWriter::new(sink, T::sql_types());
-        futures::pin_mut!(writer); // writer must be pinned to use
+        for (name, _) in index_defs {
+            let drop_stmt = format!("DROP INDEX IF EXISTS {name}");
+            timed(&drop_stmt, tx.batch_execute(&drop_stmt))
+                .await
+                .map_err(|e| {
+                    error!(table = %table, "Failed to drop index {name}: {e}");
+                    e
+                })?;
+        }
+        tx.commit().await?;
+        drop(pg_client);
+        debug!(table = %table, indexes = index_defs.len(), "indexes dropped - loading");
+
+        let loaded = load().await;
 
-        // Loop the collection & write to the `BinaryCopyInWriter`.
-        // Possible async stream could go here, but copies are so quick this may be faster.
-        for item in collection {
-            match writer.as_mut().write(&item.sql_map()).await {
-                Ok(_) => {}
-                Err(e) => error!("Failed to copy {stmt:#?}: {e})"),
+        // Recreate the indexes, whether or not the load succeeded.
+        let recreated = async {
+            let mut pg_client = self.get().await?;
+            let tx = pg_client.transaction().await?;
+            for (name, def) in index_defs {
+                timed(def, tx.batch_execute(def)).await.map_err(|e| {
+                    error!(table = %table, "Failed to recreate index {name}: {e}");
+                    e
+                })?;
             }
+            let analyze = format!("ANALYZE {table}");
+            timed(&analyze, tx.batch_execute(&analyze)).await?;
+            tx.commit().await?;
+            trace!(table = %table, indexes = index_defs.len(), "indexes recreated");
+
+            drop(pg_client); // guarantee the postgres client drops back to the pool
+            Ok::<_, Error>(())
         }
-        trace!("{stmt:?} executed successfully");
+        .await;
+
+        // The load's own error is the one worth returning.
+        match (loaded, recreated) {
+            (Err(e), Err(recreate)) => {
+                error!(table = %table, "Failed to recreate indexes after a failed load: {recreate}");
+                Err(e)
+            }
+            (loaded, recreated) => recreated.and(loaded),
+        }
+    }
+
+    async fn copy_into<'a, I, T>(
+        &self,
+        table: &'a str,
+        columns: &'a [&'a str],
+        collection: I,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlTypes + SqlMap + Send + Sync,
+    {
+        if is_foreign_table(self, table).await? {
+            debug!(table = %table, "foreign table - inserting instead of COPY");
+            self.insert(&insert_stmt(table, columns), collection).await
+        } else {
+            self.copy(&copy_columns(table, columns), collection).await
+        }
+    }
+
+    async fn copy_with_options<'a, I, T>(
+        &self,
+        stmt: &'a str,
+        options: &'a str,
+        collection: I,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlTypes + SqlMap + Send + Sync,
+    {
+        let stmt = copy_stmt_with_options(stmt, options);
+        trace!("copying with options: {stmt:?}");
+        self.copy(&stmt, collection).await
+    }
+
+    async fn load_auto<'a, I, T>(
+        &self,
+        insert_stmt: &'a str,
+        copy_stmt: &'a str,
+        collection: I,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Clone + Send + Sync,
+        T: SqlTypes + SqlMap + Send + Sync,
+    {
+        match self.copy(copy_stmt, collection.clone()).await {
+            Ok(()) => Ok(()),
+            Err(e) if is_unique_violation(&e) => {
+                debug!(
+                    "{copy_stmt:?} hit a unique violation - falling back to {insert_stmt:?}: {e}"
+                );
+                self.insert(insert_stmt, collection).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn upsert_on<'a, I, T>(
+        &self,
+        table: &'a str,
+        columns: &'a [&'a str],
+        conflict_cols: &'a [&'a str],
+        update_cols: &'a [&'a str],
+        collection: I,
+    ) -> Result<()>
+    where
+        I: Iterator<Item = T> + Send + Sync,
+        T: SqlMap + Send + Sync,
+    {
+        let stmt = upsert_stmt(table, columns, conflict_cols, update_cols);
+        trace!("upserting with {stmt:?}");
+        self.insert(&stmt, collection).await
+    }
+}
+
+/// Run a `SELECT count(*) ...` statement, for [`PgLoadExt::insert_verified`].
+async fn count_rows(pool: &deadpool_postgres::Pool, count_stmt: &str) -> Result<i64> {
+    let pg_client = pool.get().await?;
+    let row = timed(count_stmt, pg_client.query_one(count_stmt, &[]))
+        .await
+        .map_err(|e| {
+            error!("Failed to count rows with {count_stmt:?}: {e}");
+            e
+        })?;
+    drop(pg_client); // guarantee the postgres client drops back to the pool
+
+    Ok(row.try_get(0)?)
+}
+
+/// INSERT the collection row by row with a prepared statement, in one transaction; returns the
+/// number of rows inserted.
+async fn insert_rows<I, T>(pool: &deadpool_postgres::Pool, stmt: &str, collection: I) -> Result<u64>
+where
+    I: Iterator<Item = T>,
+    T: SqlMap,
+{
+    // Get a client from the Pool.
+    let mut pg_client = pool.get().await?;
+
+    // Start a transaction with a prepared statement.
+    let sql = stmt;
+    let stmt = pg_client.prepare(stmt).await?;
+    let tx = pg_client.transaction().await?;
+
+    // Stream the symbols & insert them to the database.
+    let mut rows = 0;
+    let mut stream = stream::iter(collection);
+    while let Some(item) = stream.next().await {
+        let stmt = &stmt;
+        let tx = &tx;
+        rows += timed(sql, tx.execute(stmt, &item.sql_map())).await?;
+        // async move {
+        //     match tx.execute(stmt, &item.sql_map()).await {
+        //         Ok(_) => {}
+        //         Err(e) => return Err(e),
+        //     };
+
+        //     Ok::<(), anyhow::Error>(());
+        // }
+        // .await;
+    }
+    trace!("{stmt:?} executed successfully");
+
+    // Commit the transaction.
+    tx.commit().await?;
+
+    drop(pg_client); // guarantee the postgres client drops back to the pool
+
+    Ok(rows)
+}
+
+/// COPY the collection in with the (binary) `types` of its columns, reporting progress as
+/// [`PgLoadExt::copy_with_progress`].
+async fn copy_rows<I, T, F>(
+    pool: &deadpool_postgres::Pool,
+    stmt: &str,
+    types: &[Type],
+    collection: I,
+    every: u64,
+    progress: F,
+) -> Result<()>
+where
+    I: Iterator<Item = T>,
+    T: SqlMap,
+    F: FnMut(u64),
+{
+    #[cfg(feature = "metrics")]
+    let started = std::time::Instant::now();
+    let copied = async {
+        let types = resolve_types(pool, types).await?;
+
+        // Get a client from the Pool.
+        let mut pg_client = pool.get().await?;
+        let tx = pg_client.transaction().await?;
+        let rows = copy_rows_in(&tx, stmt, &types, collection, every, progress).await?;
 
         // Commit the transaction.
-        writer.finish().await?;
         tx.commit().await?;
 
         drop(pg_client); // guarantee the postgres client drops back to the pool
 
-        Ok(())
+        Ok::<_, Error>(rows)
+    }
+    .await;
+    #[cfg(feature = "metrics")]
+    crate::metrics::record_load("copy", started, &copied, |rows| *rows);
+    copied.map(|_| ())
+}
+
+/// COPY the collection in, as [`copy_rows`], within an open transaction; returns the number of rows
+/// copied.
+pub(crate) async fn copy_rows_in<I, T, F>(
+    tx: &deadpool_postgres::Transaction<'_>,
+    stmt: &str,
+    types: &[Type],
+    collection: I,
+    every: u64,
+    progress: F,
+) -> Result<u64>
+where
+    I: Iterator<Item = T>,
+    T: SqlMap,
+    F: FnMut(u64),
+{
+    let rows = stream::iter(collection.map(Ok));
+    copy_stream_in(tx, stmt, types, rows, T::sql_map, every, progress).await
+}
+
+/// COPY each row of the stream in, with its values given by `values` (e.g. [`SqlMap::sql_map`]),
+/// within an open transaction; calling `progress` every `every` rows (if not 0). Returns the
+/// number of rows copied.
+///
+/// A failed row, or stream, returns early, dropping the unfinished writer; which aborts the COPY.
+async fn copy_stream_in<S, T, V, F>(
+    tx: &deadpool_postgres::Transaction<'_>,
+    stmt: &str,
+    types: &[Type],
+    rows: S,
+    values: V,
+    every: u64,
+    mut progress: F,
+) -> Result<u64>
+where
+    S: Stream<Item = Result<T>>,
+    V: for<'r> Fn(&'r T) -> Vec<&'r (dyn ToSql + Sync)>,
+    F: FnMut(u64),
+{
+    // Check the rows have a value for each column the statement copies into.
+    if let Some(columns) = copy_target_columns(tx, stmt).await?
+        && columns != types.len()
+    {
+        error!("Failed to copy {stmt:#?}: column count mismatch");
+        return Err(Error::ColumnCount {
+            expected: columns,
+            found: types.len(),
+        });
+    }
+
+    let copied = timed(stmt, async {
+        let sink = tx.copy_in(stmt).await?;
+        let writer = BinaryCopyInWriter::new(sink, types);
+        futures::pin_mut!(writer); // writer must be pinned to use
+        futures::pin_mut!(rows);
+
+        // Loop the rows & write to the `BinaryCopyInWriter`.
+        let mut written: u64 = 0;
+        while let Some(item) = rows.next().await {
+            let item = item.map_err(|e| {
+                error!("Failed to copy {stmt:#?}: the stream failed: {e}");
+                e
+            })?;
+            let row = values(&item);
+            if row.len() != types.len() {
+                error!("Failed to copy {stmt:#?}: column count mismatch");
+                return Err(Error::ColumnCount {
+                    expected: types.len(),
+                    found: row.len(),
+                });
+            }
+            writer.as_mut().write(&row).await.map_err(|e| {
+                error!("Failed to copy {stmt:#?}: {e}");
+                e
+            })?;
+            written += 1;
+            if every > 0 && written.is_multiple_of(every) {
+                progress(written);
+            }
+        }
+        trace!("{stmt:?} executed successfully");
+
+        Ok::<_, Error>(writer.finish().await?)
+    })
+    .await?;
+
+    Ok(copied)
+}
+
+/// The number of columns a `COPY table [(columns)] FROM ...` statement copies into: those of its
+/// column list, else each (non-generated) column of the table; or `None`, if the statement (or
+/// table) isn't recognised, leaving Postgres to report it.
+async fn copy_target_columns(
+    tx: &deadpool_postgres::Transaction<'_>,
+    stmt: &str,
+) -> Result<Option<usize>> {
+    let lower = stmt.to_ascii_lowercase();
+    let (Some(start), Some(end)) = (lower.find("copy "), lower.find(" from ")) else {
+        return Ok(None);
+    };
+    let Some(target) = stmt.get(start + "copy ".len()..end).map(str::trim) else {
+        return Ok(None);
+    };
+
+    if let Some((_, columns)) = target.split_once('(') {
+        let columns = columns.trim_end().trim_end_matches(')');
+        return Ok(Some(columns.split(',').count()));
+    }
+
+    let count_stmt = "SELECT (SELECT count(*) FROM pg_attribute \
+                      WHERE attrelid = t.oid AND attnum > 0 AND NOT attisdropped \
+                      AND attgenerated = '') \
+                      FROM (SELECT to_regclass($1) AS oid) t WHERE t.oid IS NOT NULL";
+    let row = timed(count_stmt, tx.query_opt(count_stmt, &[&target])).await?;
+    Ok(row.map(|row| row.get::<_, i64>(0) as usize))
+}
+
+/// Check if an error was caused by a Postgres unique violation.
+fn is_unique_violation(err: &Error) -> bool {
+    match err {
+        Error::Postgres(e) => e.code() == Some(&SqlState::UNIQUE_VIOLATION),
+        _ => false,
     }
 }
@@ -0,0 +1,128 @@
+//! Counters & histograms of the extract and load operations, recorded through the [`metrics`]
+//! facade (with the `metrics` feature); so they're exported by whichever recorder is installed,
+//! e.g. `metrics-exporter-prometheus`.
+//!
+//! Every metric is labelled by its `operation`, e.g. `fetch` or `copy`:
+//!
+//! | metric | kind | operations |
+//! |---|---|---|
+//! | [`EXTRACTED_ROWS`] | counter | `fetch_collection` |
+//! | [`LOADED_ROWS`] | counter | `insert`, `insert_ignore_conflicts`, `execute_insert_select`, `copy`, `copy_from_stream`, `copy_staged`, `idempotent_load` |
+//! | [`DOWNLOADED_BYTES`] | counter | `download_file` |
+//! | [`ERRORS`] | counter | all |
+//! | [`DURATION`] | histogram, in seconds | all |
+//!
+//! ```rust
+//! use metrics::{Counter, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit};
+//! use skopje::extract::http::HttpExtractExt;
+//! use std::collections::HashMap;
+//! use std::sync::atomic::{AtomicU64, Ordering};
+//! use std::sync::{Arc, Mutex};
+//! use tokio::io::{AsyncReadExt, AsyncWriteExt};
+//!
+//! /// An in-memory recorder, of counters only.
+//! #[derive(Default)]
+//! struct Counters(Mutex<HashMap<String, Arc<AtomicU64>>>);
+//!
+//! impl Counters {
+//!     fn get(&self, name: &str, operation: &str) -> u64 {
+//!         let counters = self.0.lock().unwrap();
+//!         counters.get(&format!("{name}/{operation}")).map_or(0, |c| c.load(Ordering::SeqCst))
+//!     }
+//! }
+//!
+//! impl Recorder for Counters {
+//!     fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+//!     fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+//!     fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+//!     fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+//!         let operation = key.labels().find(|l| l.key() == "operation").unwrap();
+//!         let name = format!("{}/{}", key.name(), operation.value());
+//!         Counter::from_arc(self.0.lock().unwrap().entry(name).or_default().clone())
+//!     }
+//!     fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge { Gauge::noop() }
+//!     fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram { Histogram::noop() }
+//! }
+//!
+//! let recorder = Counters::default();
+//! let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+//! metrics::with_local_recorder(&recorder, || {
+//!     rt.block_on(async {
+//!         // A mock server, streaming a 7 byte file.
+//!         let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+//!         let addr = listener.local_addr().unwrap();
+//!         tokio::spawn(async move {
+//!             let (mut stream, _) = listener.accept().await.unwrap();
+//!             let mut request = Vec::new();
+//!             while !request.ends_with(b"\r\n\r\n") {
+//!                 let mut buf = [0; 1024];
+//!                 let n = stream.read(&mut buf).await.unwrap();
+//!                 request.extend_from_slice(&buf[..n]);
+//!             }
+//!             let response = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n7\r\na,b\n1,2\r\n0\r\n\r\n";
+//!             stream.write_all(response.as_bytes()).await.unwrap();
+//!         });
+//!
+//!         let dir = std::env::temp_dir().join(format!("skopje-metrics-{}", std::process::id()));
+//!         let path = dir.join("bulk.csv").to_string_lossy().to_string();
+//!         let client = skopje::HttpClient::new();
+//!         client.download_file(&format!("http://{addr}/bulk.csv"), &path).await.unwrap();
+//!         std::fs::remove_dir_all(dir).unwrap();
+//!
+//!         // the server has gone, so this fails
+//!         assert!(client.fetch::<u32>(&format!("http://{addr}/answer")).await.is_err());
+//!     })
+//! });
+//!
+//! assert_eq!(recorder.get(skopje::metrics::DOWNLOADED_BYTES, "download_file"), 7);
+//! assert_eq!(recorder.get(skopje::metrics::ERRORS, "download_file"), 0);
+//! assert_eq!(recorder.get(skopje::metrics::ERRORS, "fetch"), 1);
+//! ```
+use crate::Result;
+use std::time::Instant;
+
+/// Rows extracted from a database.
+pub const EXTRACTED_ROWS: &str = "skopje_extracted_rows_total";
+
+/// Rows loaded (and committed) to a database.
+pub const LOADED_ROWS: &str = "skopje_loaded_rows_total";
+
+/// Bytes of files downloaded.
+pub const DOWNLOADED_BYTES: &str = "skopje_downloaded_bytes_total";
+
+/// Failed operations.
+pub const ERRORS: &str = "skopje_errors_total";
+
+/// The duration of each operation, whether it succeeded or not.
+pub const DURATION: &str = "skopje_operation_duration_seconds";
+
+/// Record the duration of an operation started at `started`, and count it if it failed.
+pub(crate) fn record<T>(operation: &'static str, started: Instant, result: &Result<T>) {
+    ::metrics::histogram!(DURATION, "operation" => operation).record(started.elapsed());
+    if result.is_err() {
+        ::metrics::counter!(ERRORS, "operation" => operation).increment(1);
+    }
+}
+
+/// Record a load, as [`record`], and count the `rows` of its result.
+pub(crate) fn record_load<T>(
+    operation: &'static str,
+    started: Instant,
+    result: &Result<T>,
+    rows: impl FnOnce(&T) -> u64,
+) {
+    record(operation, started, result);
+    if let Ok(loaded) = result {
+        ::metrics::counter!(LOADED_ROWS, "operation" => operation).increment(rows(loaded));
+    }
+}
+
+/// Count rows extracted by an operation.
+pub(crate) fn extracted_rows(operation: &'static str, rows: u64) {
+    ::metrics::counter!(EXTRACTED_ROWS, "operation" => operation).increment(rows);
+}
+
+/// Count the bytes of a downloaded file.
+pub(crate) fn downloaded_bytes(bytes: u64) {
+    ::metrics::counter!(DOWNLOADED_BYTES, "operation" => "download_file").increment(bytes);
+}
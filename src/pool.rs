@@ -0,0 +1,385 @@
+use crate::{Error, Result};
+use deadpool_postgres::{
+    Hook, HookError, Manager, ManagerConfig, Pool, PoolBuilder, RecyclingMethod,
+};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tokio_postgres::Socket;
+use tokio_postgres::tls::{MakeTlsConnect, TlsConnect};
+use tracing::{debug, warn};
+
+/// The slow query threshold, in microseconds; `u64::MAX` when disabled.
+static SLOW_QUERY_THRESHOLD: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// A snapshot of a [`Pool`]'s saturation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStatus {
+    /// The maximum number of connections the pool will open.
+    pub max_size: usize,
+    /// The number of connections currently open.
+    pub size: usize,
+    /// The number of open connections idle in the pool.
+    pub available: usize,
+    /// The number of connections checked out of the pool.
+    pub in_use: usize,
+    /// The number of callers waiting for a connection.
+    pub waiting: usize,
+}
+
+/// Report the current status of a [`Pool`].
+///
+/// ```rust
+/// use deadpool_postgres::{Manager, Pool};
+///
+/// let manager = Manager::new(tokio_postgres::Config::new(), tokio_postgres::NoTls);
+/// let pool = Pool::builder(manager).max_size(4).build().unwrap();
+///
+/// let status = skopje::pool::pool_status(&pool);
+/// assert_eq!(status.max_size, 4);
+/// assert_eq!(status.size, 0); // connections are only opened on checkout
+/// assert_eq!(status.in_use, 0);
+/// ```
+pub fn pool_status(pool: &Pool) -> PoolStatus {
+    let status = pool.status();
+    PoolStatus {
+        max_size: status.max_size,
+        size: status.size,
+        available: status.available,
+        in_use: status.size.saturating_sub(status.available),
+        waiting: status.waiting,
+    }
+}
+
+/// Set a session-level `statement_timeout` on every connection the pool opens, so that any
+/// query exceeding it (from the extract or load methods alike) is cancelled by Postgres, and
+/// fails with [`tokio_postgres::error::SqlState::QUERY_CANCELED`].
+///
+/// Any existing connection `options` are kept.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// let mut config = deadpool_postgres::Config::new();
+/// config.options = Some("-c search_path=prices".to_string());
+/// skopje::pool::set_statement_timeout(&mut config, Duration::from_secs(30));
+///
+/// assert_eq!(
+///     config.options.as_deref(),
+///     Some("-c search_path=prices -c statement_timeout=30000")
+/// );
+/// ```
+pub fn set_statement_timeout(config: &mut deadpool_postgres::Config, timeout: Duration) {
+    let option = format!("-c statement_timeout={}", timeout.as_millis());
+    config.options = Some(match config.options.take() {
+        Some(options) if !options.trim().is_empty() => format!("{} {option}", options.trim()),
+        _ => option,
+    });
+}
+
+/// How a pool's connections are recycled, i.e. checked as they're handed out again; see
+/// [`pool_builder`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recycling {
+    /// How a connection is validated; [`RecyclingMethod::Fast`] (the default) only checks it's
+    /// open, while [`RecyclingMethod::Verified`] runs a test query, and
+    /// [`RecyclingMethod::Clean`] also resets any session state (e.g. `SET`s & temp tables).
+    pub method: RecyclingMethod,
+    /// The age beyond which a connection is closed, rather than handed out again, so that no
+    /// connection lives (and accumulates server-side state) for the whole of a long run.
+    pub max_lifetime: Option<Duration>,
+}
+
+impl Recycling {
+    /// The [`ManagerConfig`] of this recycling method.
+    pub fn manager_config(&self) -> ManagerConfig {
+        ManagerConfig {
+            recycling_method: self.method.clone(),
+        }
+    }
+}
+
+/// Start building a [`Pool`], with its connections recycled as configured by `recycling`;
+/// further options, e.g. `max_size`, can be set on the builder.
+///
+/// The extract & load methods check a connection out once per call, so recycling applies at
+/// the start of each call; a connection is never recycled mid-call (e.g. during a long
+/// `fetch_try_stream()`, which holds its connection until the stream is dropped).
+///
+/// ```rust
+/// use deadpool_postgres::RecyclingMethod;
+/// use skopje::pool::{Recycling, pool_builder};
+/// use std::time::Duration;
+///
+/// let recycling = Recycling {
+///     method: RecyclingMethod::Verified,
+///     max_lifetime: Some(Duration::from_secs(30 * 60)),
+/// };
+/// assert_eq!(recycling.manager_config().recycling_method, RecyclingMethod::Verified);
+///
+/// let pool = pool_builder(tokio_postgres::Config::new(), tokio_postgres::NoTls, &recycling)
+///     .max_size(4)
+///     .build()
+///     .unwrap();
+/// assert_eq!(skopje::pool::pool_status(&pool).max_size, 4);
+/// ```
+pub fn pool_builder<T>(
+    pg_config: tokio_postgres::Config,
+    tls: T,
+    recycling: &Recycling,
+) -> PoolBuilder
+where
+    T: MakeTlsConnect<Socket> + Clone + Sync + Send + 'static,
+    T::Stream: Sync + Send,
+    T::TlsConnect: Sync + Send,
+    <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+{
+    let manager = Manager::from_config(pg_config, tls, recycling.manager_config());
+    let builder = Pool::builder(manager);
+
+    match recycling.max_lifetime {
+        Some(max_lifetime) => builder.pre_recycle(Hook::sync_fn(move |_, metrics| {
+            if metrics.age() > max_lifetime {
+                debug!(age = ?metrics.age(), "closing expired postgres connection");
+                return Err(HookError::message("connection exceeded its max lifetime"));
+            }
+            Ok(())
+        })),
+        None => builder,
+    }
+}
+
+/// Periodically emit the [`PoolStatus`] of `pool` as a `tracing` event, every `interval`.
+///
+/// Events are emitted at DEBUG, or at WARN when callers are waiting for a connection. The
+/// task runs until the returned handle is aborted.
+pub fn spawn_pool_status_logging(pool: Pool, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let status = pool_status(&pool);
+            if status.waiting > 0 {
+                warn!(
+                    max_size = status.max_size,
+                    size = status.size,
+                    available = status.available,
+                    in_use = status.in_use,
+                    waiting = status.waiting,
+                    "postgres pool saturated"
+                );
+            } else {
+                debug!(
+                    max_size = status.max_size,
+                    size = status.size,
+                    available = status.available,
+                    in_use = status.in_use,
+                    waiting = status.waiting,
+                    "postgres pool status"
+                );
+            }
+        }
+    })
+}
+
+/// A circuit breaker, shared around the extract & load calls on a pool, for when the database
+/// is down: after `threshold` consecutive connection failures, it opens, and every call fails
+/// fast with [`Error::CircuitOpen`] for the `cooldown`; then the next call is let through, as a
+/// probe, which closes the breaker again if it connects. Only one probe runs at a time: the
+/// calls made while it's in flight fail fast too.
+///
+/// Only connection failures count: a pool error, or a closed connection. Any other outcome,
+/// including a query error, shows the database is reachable, and resets the count.
+///
+/// ```rust
+/// use skopje::Error;
+/// use skopje::pool::CircuitBreaker;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::time::Duration;
+///
+/// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+/// rt.block_on(async {
+///     let breaker = CircuitBreaker::new(3, Duration::from_millis(50));
+///     let attempts = AtomicUsize::new(0);
+///
+///     // a database that's down
+///     let copy = || async {
+///         attempts.fetch_add(1, Ordering::SeqCst);
+///         Err::<(), _>(Error::Pool(deadpool_postgres::PoolError::Closed))
+///     };
+///
+///     for _ in 0..3 {
+///         assert!(matches!(breaker.call(copy).await, Err(Error::Pool(_))));
+///     }
+///     // tripped: the next calls fail without being attempted
+///     assert!(matches!(breaker.call(copy).await, Err(Error::CircuitOpen(_))));
+///     assert!(matches!(breaker.call(copy).await, Err(Error::CircuitOpen(_))));
+///     assert_eq!(attempts.load(Ordering::SeqCst), 3);
+///
+///     // after the cooldown, a single probe is let through
+///     tokio::time::sleep(Duration::from_millis(60)).await;
+///     let (connected, connect) = tokio::sync::oneshot::channel::<()>();
+///     let probe = breaker.call(|| async {
+///         connect.await.unwrap();
+///         Ok(())
+///     });
+///     let other = async {
+///         tokio::task::yield_now().await;
+///         let other = breaker.call(copy).await;
+///         connected.send(()).unwrap();
+///         other
+///     };
+///     let (probe, other) = tokio::join!(probe, other);
+///     assert!(probe.is_ok());
+///     assert!(matches!(other, Err(Error::CircuitOpen(_))));
+///     assert_eq!(attempts.load(Ordering::SeqCst), 3);
+///     assert!(!breaker.is_open());
+/// });
+/// ```
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    failures: u32,
+    open_until: Option<Instant>,
+    /// Whether a call is in flight as the probe of an open breaker, past its cooldown.
+    probing: bool,
+}
+
+/// Ends the probe of a [`CircuitBreaker`] when dropped, even if the probe's future is.
+struct Probe<'a>(&'a Mutex<BreakerState>);
+
+impl Drop for Probe<'_> {
+    fn drop(&mut self) {
+        self.0
+            .lock()
+            .expect("circuit breaker lock poisoned")
+            .probing = false;
+    }
+}
+
+impl CircuitBreaker {
+    /// Open after `threshold` consecutive connection failures (at least 1), for `cooldown`.
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            cooldown,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    /// Whether calls are currently failing fast.
+    pub fn is_open(&self) -> bool {
+        let state = self.state.lock().expect("circuit breaker lock poisoned");
+        state.open_until.is_some_and(|until| Instant::now() < until)
+    }
+
+    /// Run `f`, e.g. `|| pool.copy(stmt, rows.iter())`, unless the breaker is open.
+    pub async fn call<F, Fut, R>(&self, f: F) -> Result<R>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<R>>,
+    {
+        let probe = {
+            let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+            match state.open_until {
+                Some(until) => {
+                    let now = Instant::now();
+                    if now < until {
+                        return Err(Error::CircuitOpen(until - now));
+                    }
+                    if state.probing {
+                        return Err(Error::CircuitOpen(Duration::ZERO));
+                    }
+                    state.probing = true;
+                    Some(Probe(&self.state))
+                }
+                None => None,
+            }
+        };
+
+        let result = f().await;
+
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        match &result {
+            Err(e) if is_connection_failure(e) => {
+                state.failures += 1;
+                if state.failures >= self.threshold {
+                    warn!(
+                        failures = state.failures,
+                        cooldown = ?self.cooldown,
+                        "postgres unreachable - circuit breaker open"
+                    );
+                    state.open_until = Some(Instant::now() + self.cooldown);
+                }
+            }
+            _ => {
+                if state.open_until.take().is_some() {
+                    debug!("postgres reachable - circuit breaker closed");
+                }
+                state.failures = 0;
+            }
+        }
+        drop(state);
+        drop(probe);
+        result
+    }
+}
+
+/// Whether an error means the database couldn't be reached, rather than it rejecting a query.
+fn is_connection_failure(e: &Error) -> bool {
+    match e {
+        Error::Pool(_) => true,
+        Error::Postgres(e) => e.is_closed(),
+        _ => false,
+    }
+}
+
+/// Warn when any query run by the extract or load methods (each `execute`, `query` & `copy`)
+/// takes longer than `threshold`, with a `tracing` WARN event carrying the statement and the
+/// elapsed time; `None` disables it, which is the default.
+///
+/// The threshold is global, across all pools.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// skopje::pool::set_slow_query_threshold(Some(Duration::from_millis(250)));
+/// assert_eq!(skopje::pool::slow_query_threshold(), Some(Duration::from_millis(250)));
+///
+/// skopje::pool::set_slow_query_threshold(None);
+/// assert_eq!(skopje::pool::slow_query_threshold(), None);
+/// ```
+pub fn set_slow_query_threshold(threshold: Option<Duration>) {
+    let micros = threshold.map_or(u64::MAX, |threshold| {
+        u64::try_from(threshold.as_micros()).unwrap_or(u64::MAX - 1)
+    });
+    SLOW_QUERY_THRESHOLD.store(micros, Ordering::Relaxed);
+}
+
+/// The current slow query threshold; see [`set_slow_query_threshold`].
+pub fn slow_query_threshold() -> Option<Duration> {
+    match SLOW_QUERY_THRESHOLD.load(Ordering::Relaxed) {
+        u64::MAX => None,
+        micros => Some(Duration::from_micros(micros)),
+    }
+}
+
+/// Run a query future, warning if it exceeds the slow query threshold.
+pub(crate) async fn timed<F: Future>(stmt: &str, query: F) -> F::Output {
+    let started = Instant::now();
+    let output = query.await;
+    let elapsed = started.elapsed();
+    if let Some(threshold) = slow_query_threshold()
+        && elapsed >= threshold
+    {
+        warn!(stmt = %stmt, elapsed = ?elapsed, "slow query");
+    }
+    output
+}
@@ -0,0 +1,256 @@
+use crate::KeyMap;
+use futures::future::{self, Future};
+use futures::stream::{Stream, StreamExt, TryStreamExt};
+use num::PrimInt;
+use serde::de::DeserializeOwned;
+use std::hash::Hash;
+use std::ops::AddAssign;
+use tokio::sync::mpsc;
+use tokio_postgres::types::{FromSql, ToSql};
+
+/// Transform each item of an extracted stream, e.g. from
+/// [`crate::extract::pg::PgExtractExt::fetch_try_stream`]; errors pass through untouched.
+///
+/// ```rust
+/// use futures::{StreamExt, executor::block_on, stream};
+/// use skopje::transform;
+///
+/// let rows = stream::iter(vec![Ok::<_, skopje::Error>(1), Ok(2), Ok(3), Ok(4)]);
+/// let rows = transform::filter(transform::map(rows, |x| x * 10), |x| *x > 10);
+///
+/// let output: Vec<i32> = block_on(rows.map(Result::unwrap).collect());
+/// assert_eq!(output, vec![20, 30, 40]);
+/// ```
+pub fn map<S, T, U, E, F>(stream: S, f: F) -> impl Stream<Item = Result<U, E>>
+where
+    S: Stream<Item = Result<T, E>>,
+    F: FnMut(T) -> U,
+{
+    stream.map_ok(f)
+}
+
+/// Keep only the items of an extracted stream which satisfy the predicate `f`; errors are always
+/// kept.
+pub fn filter<S, T, E, F>(stream: S, mut f: F) -> impl Stream<Item = Result<T, E>>
+where
+    S: Stream<Item = Result<T, E>>,
+    F: FnMut(&T) -> bool,
+{
+    stream.try_filter(move |item| future::ready(f(item)))
+}
+
+/// Group the items of an extracted stream into batches of (at most) `n`, e.g. so that each batch
+/// can be written with a single `copy()`.
+///
+/// A batch containing an error yields the first error in place of the batch.
+///
+/// ```rust
+/// use futures::{StreamExt, executor::block_on, stream};
+/// use skopje::transform;
+///
+/// let rows = stream::iter(vec![Ok::<_, skopje::Error>(1), Ok(2), Ok(3), Ok(4), Ok(5)]);
+/// let batches: Vec<Vec<i32>> = block_on(transform::batch(rows, 2).map(Result::unwrap).collect());
+/// assert_eq!(batches, vec![vec![1, 2], vec![3, 4], vec![5]]);
+/// ```
+pub fn batch<S, T, E>(stream: S, n: usize) -> impl Stream<Item = Result<Vec<T>, E>>
+where
+    S: Stream<Item = Result<T, E>>,
+{
+    stream
+        .chunks(n.max(1))
+        .map(|items| items.into_iter().collect::<Result<Vec<T>, E>>())
+}
+
+/// Replace the business key of each item of an extracted stream with its surrogate key, from a
+/// [`KeyMap`]: the key read by `extract_key` is transacted into the `keymap` (allocating the
+/// next key, if it's new), and the surrogate written back by `set_key`.
+///
+/// The keys allocated along the way are pending in the `keymap`, to be flushed to its table once
+/// the stream is done. If the keyspace runs out, the stream yields an
+/// [`Error::KeyspaceExhausted`](crate::Error::KeyspaceExhausted) in place of the item.
+///
+/// ```rust
+/// use futures::{StreamExt, executor::block_on, stream};
+/// use skopje::{KeyMap, transform};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Trade {
+///     symbol: String,
+///     symbol_id: i32,
+/// }
+///
+/// let trade = |symbol: &str| Ok::<_, skopje::Error>(Trade { symbol: symbol.to_string(), symbol_id: 0 });
+/// let trades = stream::iter(vec![trade("AAPL"), trade("MSFT"), trade("AAPL")]);
+///
+/// let mut keymap: KeyMap<i32, String> = KeyMap::from(bimap::BiMap::new());
+/// let trades = transform::with_keymap(
+///     trades,
+///     &mut keymap,
+///     |trade: &Trade| trade.symbol.clone(),
+///     |trade: &mut Trade, id| trade.symbol_id = id,
+/// );
+/// let ids: Vec<i32> = block_on(trades.map(|trade| trade.unwrap().symbol_id).collect());
+///
+/// assert_eq!(ids, vec![0, 1, 0]);
+/// assert_eq!(keymap.pending_keys(), &[0, 1]); // to be flushed
+/// ```
+pub fn with_keymap<S, T, E, PK, Obj, K, W>(
+    stream: S,
+    keymap: &mut KeyMap<PK, Obj>,
+    mut extract_key: K,
+    mut set_key: W,
+) -> impl Stream<Item = Result<T, E>>
+where
+    S: Stream<Item = Result<T, E>>,
+    E: From<crate::Error>,
+    PK: Eq + Hash + PrimInt + for<'a> FromSql<'a> + ToSql + AddAssign + Sync,
+    Obj: Eq + Hash + for<'a> FromSql<'a> + ToSql + Sync,
+    K: FnMut(&T) -> Obj,
+    W: FnMut(&mut T, PK),
+{
+    stream.map(move |item| {
+        let mut item = item?;
+        let key = keymap.try_transact(extract_key(&item))?;
+        set_key(&mut item, key);
+        Ok(item)
+    })
+}
+
+/// Pipe an extracted stream into `load`, one item at a time, through a channel of (at most)
+/// `buffer` items; so when the loader falls behind, the extractor is paused, rather than the
+/// whole stream buffered in memory.
+///
+/// The stream is driven on its own task. The first error, from either side, stops the pipe and
+/// is returned; a failed load aborts the extractor, even one stalled on its source.
+///
+/// ```rust
+/// use futures::{StreamExt, stream};
+/// use skopje::transform;
+/// use std::sync::Arc;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::time::Duration;
+///
+/// let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+/// let extracted = Arc::new(AtomicUsize::new(0));
+/// let (mut loaded, mut max_ahead) = (0, 0);
+///
+/// let counter = extracted.clone();
+/// let rows = stream::iter(0..20).map(move |x| {
+///     counter.fetch_add(1, Ordering::SeqCst);
+///     Ok::<_, skopje::Error>(x)
+/// });
+///
+/// // a slow loader, behind a buffer of 4 rows
+/// let slow_load = |_row| {
+///     loaded += 1;
+///     max_ahead = max_ahead.max(extracted.load(Ordering::SeqCst) - loaded);
+///     async {
+///         tokio::time::sleep(Duration::from_millis(5)).await;
+///         Ok(())
+///     }
+/// };
+/// rt.block_on(transform::pipe(rows, slow_load, 4)).unwrap();
+///
+/// assert_eq!(loaded, 20);
+/// assert!(max_ahead <= 4 + 1); // the buffer, plus the row waiting to be sent
+/// ```
+pub async fn pipe<S, T, E, F, Fut>(stream: S, mut load: F, buffer: usize) -> Result<(), E>
+where
+    S: Stream<Item = Result<T, E>> + Send + 'static,
+    T: Send + 'static,
+    E: Send + 'static,
+    F: FnMut(T) -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+{
+    let (tx, mut rx) = mpsc::channel(buffer.max(1));
+
+    // `send` waits for capacity, which is what throttles the extractor.
+    let producer = tokio::spawn(async move {
+        futures::pin_mut!(stream);
+        while let Some(item) = stream.next().await {
+            let failed = item.is_err();
+            if tx.send(item).await.is_err() || failed {
+                break; // the loader has stopped, or the error ends the pipe
+            }
+        }
+    });
+
+    let mut result = Ok(());
+    while let Some(item) = rx.recv().await {
+        if let Err(e) = match item {
+            Ok(item) => load(item).await,
+            Err(e) => Err(e),
+        } {
+            result = Err(e);
+            break;
+        }
+    }
+
+    // Stop the extractor, if it's still going; it may be stalled on the source, rather than
+    // waiting to send, so it's aborted, rather than left to notice the dropped receiver.
+    drop(rx);
+    producer.abort();
+    if let Err(e) = producer.await
+        && e.is_panic()
+    {
+        std::panic::resume_unwind(e.into_panic());
+    }
+
+    result
+}
+
+/// Build a struct from a loosely-typed JSON value, e.g. an extracted record whose keys differ
+/// from the struct's field names; without a second, mirror-image struct to deserialize into.
+///
+/// Usually derived, with [`skopje::Remap`](crate::Remap): each field is read from the key given
+/// by `#[remap(from = "...")]` (or a JSON Pointer, starting with `/`), else by any
+/// `#[serde(rename = "...")]`, else from its own name; see [`remap_field`].
+///
+/// ```rust
+/// use skopje::Remap;
+/// use skopje::transform::Remap as _;
+///
+/// #[derive(Remap, Debug, PartialEq)]
+/// struct Price {
+///     #[remap(from = "s")]
+///     symbol: String,
+///     #[serde(rename = "c")]
+///     close: f64,
+///     #[remap(from = "/meta/exchange")]
+///     exchange: String,
+///     volume: Option<u64>,
+/// }
+///
+/// let value = serde_json::json!({"s": "AAPL", "c": 170.5, "meta": {"exchange": "NASDAQ"}});
+/// let price = Price::remap(&value).unwrap();
+/// assert_eq!(
+///     price,
+///     Price {
+///         symbol: "AAPL".to_string(),
+///         close: 170.5,
+///         exchange: "NASDAQ".to_string(),
+///         volume: None, // a missing key is `null`
+///     }
+/// );
+///
+/// // a key of the wrong type is reported by name
+/// let err = Price::remap(&serde_json::json!({"s": "AAPL", "c": "n/a"})).unwrap_err();
+/// assert!(err.to_string().contains("\"c\""));
+/// ```
+pub trait Remap: Sized {
+    fn remap(value: &serde_json::Value) -> crate::Result<Self>;
+}
+
+/// Deserialize the field found at `from` in `value`: the key `from`, or, if it starts with `/`,
+/// the [JSON Pointer](serde_json::Value::pointer) `from`. A missing field is read as `null`, so
+/// an `Option` field is `None`.
+pub fn remap_field<T: DeserializeOwned>(value: &serde_json::Value, from: &str) -> crate::Result<T> {
+    let field = match from.starts_with('/') {
+        true => value.pointer(from),
+        false => value.get(from),
+    };
+    T::deserialize(field.unwrap_or(&serde_json::Value::Null)).map_err(|e| {
+        tracing::error!("failed to remap field {from:?}: {e}");
+        crate::Error::deserialize(format!("field {from:?}: {e}"))
+    })
+}
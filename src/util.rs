@@ -6,10 +6,265 @@ pub fn convert_timestamp(timestamp: u32) -> chrono::NaiveDate {
 }
 
 /// Convert a &String to a chrono::NaiveDate (so that it can inserted directly as DATE)
-pub fn convert_date_type(str_date: &String) -> anyhow::Result<chrono::NaiveDate> {
-    let date = chrono::NaiveDate::parse_from_str(&str_date, "%Y-%m-%d").map_err(|err| {
+pub fn convert_date_type(str_date: &String) -> crate::Result<chrono::NaiveDate> {
+    let date = chrono::NaiveDate::parse_from_str(str_date, "%Y-%m-%d").inspect_err(|err| {
         tracing::error!("failed to parse date string; expected form YYYYMMDD - received: {str_date}, error({err})");
-        err
-    })?;
+    })
+    .map_err(crate::Error::deserialize)?;
     Ok(date)
 }
+
+/// Deserialize the subtree of `value` found at the [JSON Pointer] `pointer`, e.g. `/result/data`.
+///
+/// [JSON Pointer]: serde_json::Value::pointer
+///
+/// ```rust
+/// let value = serde_json::json!({"result": {"data": [1, 2, 3]}});
+/// let data: Vec<i32> = skopje::util::from_json_pointer(value, "/result/data").unwrap();
+/// assert_eq!(data, vec![1, 2, 3]);
+/// ```
+pub fn from_json_pointer<T: serde::de::DeserializeOwned>(
+    mut value: serde_json::Value,
+    pointer: &str,
+) -> crate::Result<T> {
+    let subtree = value
+        .pointer_mut(pointer)
+        .ok_or_else(|| {
+            crate::Error::deserialize(format!("no value found at JSON pointer {pointer:?}"))
+        })?
+        .take();
+    let data: T = serde_json::from_value(subtree).inspect_err(|err| {
+        tracing::error!("failed to deserialize value at JSON pointer {pointer:?}: {err}");
+    })?;
+    Ok(data)
+}
+
+/// Deserialize a number sent either as a JSON number, or as a string, e.g. `"volume": 123` and
+/// `"volume": "123"`; for use with `#[serde(deserialize_with = "skopje::util::number_or_string")]`.
+///
+/// A string is trimmed, then parsed with [`FromStr`](std::str::FromStr).
+///
+/// ```rust
+/// #[derive(serde::Deserialize)]
+/// struct Trade {
+///     #[serde(deserialize_with = "skopje::util::number_or_string")]
+///     volume: u64,
+///     #[serde(deserialize_with = "skopje::util::number_or_string")]
+///     price: f64,
+/// }
+///
+/// let trade: Trade = serde_json::from_str(r#"{"volume": 123, "price": 1.5}"#).unwrap();
+/// assert_eq!((trade.volume, trade.price), (123, 1.5));
+///
+/// let trade: Trade = serde_json::from_str(r#"{"volume": "123", "price": " 1.5"}"#).unwrap();
+/// assert_eq!((trade.volume, trade.price), (123, 1.5));
+///
+/// assert!(serde_json::from_str::<Trade>(r#"{"volume": "12x", "price": 1.5}"#).is_err());
+/// ```
+pub fn number_or_string<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: std::str::FromStr + serde::de::DeserializeOwned,
+    T::Err: std::fmt::Display,
+{
+    use serde::de::Error;
+
+    match <serde_json::Value as serde::Deserialize>::deserialize(deserializer)? {
+        serde_json::Value::String(s) => s
+            .trim()
+            .parse()
+            .map_err(|e| D::Error::custom(format!("invalid number {s:?}: {e}"))),
+        value => serde_json::from_value(value).map_err(D::Error::custom),
+    }
+}
+
+/// Normalize an exchange/ticker symbol, so that the same instrument has a consistent key.
+///
+/// The rules are:
+/// - leading & trailing whitespace is trimmed;
+/// - letters are uppercased;
+/// - any run of separators (`.`, `-`, `/`, `_`, `:` or whitespace) becomes a single `.`;
+/// - separators at the start or end are dropped.
+///
+/// ```rust
+/// use skopje::util::normalize_symbol;
+///
+/// assert_eq!(normalize_symbol("BRK.B"), "BRK.B");
+/// assert_eq!(normalize_symbol("BRK-B"), "BRK.B");
+/// assert_eq!(normalize_symbol("brk/b"), "BRK.B");
+/// assert_eq!(normalize_symbol(" BRK_B "), "BRK.B");
+/// assert_eq!(normalize_symbol("BRK - B"), "BRK.B");
+/// assert_eq!(normalize_symbol("aapl"), "AAPL");
+/// ```
+pub fn normalize_symbol(s: &str) -> String {
+    let is_separator = |c: char| matches!(c, '.' | '-' | '/' | '_' | ':') || c.is_whitespace();
+
+    s.split(is_separator)
+        .filter(|part| !part.is_empty())
+        .map(|part| part.to_uppercase())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Truncate `dt` down to the start of its `period`, e.g. to bucket timestamps into
+/// minute/hour/day bars.
+///
+/// Periods are aligned to the Unix epoch (so days start at midnight UTC), except for whole
+/// weeks, which are aligned to start on Mondays.
+///
+/// ```rust
+/// use chrono::{Duration, TimeZone, Utc};
+/// use skopje::util::floor_to_period;
+///
+/// let dt = Utc.with_ymd_and_hms(2025, 1, 30, 14, 37, 52).unwrap(); // a Thursday
+///
+/// let minute = floor_to_period(dt, Duration::minutes(1));
+/// assert_eq!(minute, Utc.with_ymd_and_hms(2025, 1, 30, 14, 37, 0).unwrap());
+///
+/// let quarter_hour = floor_to_period(dt, Duration::minutes(15));
+/// assert_eq!(quarter_hour, Utc.with_ymd_and_hms(2025, 1, 30, 14, 30, 0).unwrap());
+///
+/// let hour = floor_to_period(dt, Duration::hours(1));
+/// assert_eq!(hour, Utc.with_ymd_and_hms(2025, 1, 30, 14, 0, 0).unwrap());
+///
+/// let day = floor_to_period(dt, Duration::days(1));
+/// assert_eq!(day, Utc.with_ymd_and_hms(2025, 1, 30, 0, 0, 0).unwrap());
+///
+/// let week = floor_to_period(dt, Duration::weeks(1));
+/// assert_eq!(week, Utc.with_ymd_and_hms(2025, 1, 27, 0, 0, 0).unwrap()); // the Monday
+/// ```
+///
+/// # Panics
+///
+/// Panics if `period` is not positive.
+pub fn floor_to_period(
+    dt: chrono::DateTime<chrono::Utc>,
+    period: chrono::Duration,
+) -> chrono::DateTime<chrono::Utc> {
+    const MICROS_PER_WEEK: i64 = 7 * 24 * 60 * 60 * 1_000_000;
+    // The Unix epoch was a Thursday; the following Monday is 4 days later.
+    const MONDAY_OFFSET_MICROS: i64 = 4 * 24 * 60 * 60 * 1_000_000;
+
+    let period = period
+        .num_microseconds()
+        .filter(|micros| *micros > 0)
+        .expect("period must be positive");
+    let offset = if period % MICROS_PER_WEEK == 0 {
+        MONDAY_OFFSET_MICROS
+    } else {
+        0
+    };
+
+    let micros = dt.timestamp_micros() - offset;
+    let floored = micros - micros.rem_euclid(period) + offset;
+    chrono::DateTime::from_timestamp_micros(floored).expect("floored timestamp is in range")
+}
+
+/// The separators of a locale's number formatting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberLocale {
+    /// `1,234.56`: comma thousands separators, and a point decimal separator.
+    Us,
+    /// `1.234,56`: point thousands separators, and a comma decimal separator.
+    European,
+}
+
+/// Parse a number encoded as a string with thousands separators, e.g. `"1,234.56"`, as a
+/// [`Decimal`](rust_decimal::Decimal), by the separators of `locale`.
+///
+/// Whitespace (including the non-breaking spaces some locales group digits with) is ignored.
+///
+/// ```rust
+/// use rust_decimal::Decimal;
+/// use skopje::util::{NumberLocale, parse_decimal};
+///
+/// let expected = Decimal::new(-123456789, 2);
+/// assert_eq!(parse_decimal("-1,234,567.89", NumberLocale::Us).unwrap(), expected);
+/// assert_eq!(parse_decimal("-1.234.567,89", NumberLocale::European).unwrap(), expected);
+/// assert_eq!(parse_decimal("1 234 567,89", NumberLocale::European).unwrap(), -expected);
+/// assert_eq!(parse_decimal("42", NumberLocale::Us).unwrap(), Decimal::from(42));
+///
+/// assert!(parse_decimal("1.234,56", NumberLocale::Us).is_err());
+/// assert!(parse_decimal("1234,567.8", NumberLocale::Us).is_err());
+/// assert!(parse_decimal(",123", NumberLocale::Us).is_err());
+/// assert!(parse_decimal("n/a", NumberLocale::Us).is_err());
+/// ```
+pub fn parse_decimal(s: &str, locale: NumberLocale) -> crate::Result<rust_decimal::Decimal> {
+    let (thousands, decimal) = match locale {
+        NumberLocale::Us => (',', '.'),
+        NumberLocale::European => ('.', ','),
+    };
+
+    let compact: String = s
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '\u{202F}')
+        .collect();
+
+    // Thousands separators may only group the whole part: a leading group of 1-3 digits, then
+    // groups of exactly 3, e.g. not `1,23.4`, `1234,567.8`, `,123` or `1.234,5` (for the US).
+    let whole = compact.split(decimal).next().unwrap_or_default();
+    let mut groups = whole.trim_start_matches(['-', '+']).split(thousands);
+    let leading = groups.next().unwrap_or_default();
+    let well_grouped = !compact
+        .split(decimal)
+        .skip(1)
+        .any(|part| part.contains(thousands))
+        && (!whole.contains(thousands) || (1..=3).contains(&leading.len()))
+        && groups.all(|group| group.len() == 3);
+
+    let normalized: String = compact
+        .chars()
+        .filter(|c| *c != thousands)
+        .map(|c| if c == decimal { '.' } else { c })
+        .collect();
+
+    let parsed = if well_grouped {
+        normalized.parse::<rust_decimal::Decimal>()
+    } else {
+        Err(rust_decimal::Error::ConversionTo(format!(
+            "misplaced thousands separator {thousands:?}"
+        )))
+    };
+    parsed
+        .inspect_err(|err| {
+            tracing::error!("failed to parse decimal string {s:?} as {locale:?}: {err}");
+        })
+        .map_err(crate::Error::deserialize)
+}
+
+/// Split `items` into (at most) `n` contiguous shards, as evenly as possible, e.g. for parallel
+/// loaders; shard sizes differ by at most one, with the larger shards first. Rather than the
+/// ragged last shard of `chunks(len / n)`.
+///
+/// There are never any empty shards, so fewer than `n` items give one shard each; and `n = 0`
+/// is treated as 1.
+///
+/// ```rust
+/// use skopje::util::chunk_balanced;
+///
+/// let shards = chunk_balanced((1..=10).collect(), 3);
+/// assert_eq!(shards, vec![vec![1, 2, 3, 4], vec![5, 6, 7], vec![8, 9, 10]]);
+///
+/// let shards = chunk_balanced((1..=11).collect(), 4);
+/// assert_eq!(shards.iter().map(Vec::len).collect::<Vec<_>>(), vec![3, 3, 3, 2]);
+///
+/// assert_eq!(chunk_balanced(vec![1, 2], 4), vec![vec![1], vec![2]]);
+/// assert_eq!(chunk_balanced(vec![1, 2, 3], 0), vec![vec![1, 2, 3]]);
+/// assert!(chunk_balanced(Vec::<u8>::new(), 4).is_empty());
+/// ```
+pub fn chunk_balanced<T>(items: Vec<T>, n: usize) -> Vec<Vec<T>> {
+    let shards = n.max(1).min(items.len());
+    if shards == 0 {
+        return Vec::new();
+    }
+    let (size, extra) = (items.len() / shards, items.len() % shards);
+
+    let mut items = items.into_iter();
+    (0..shards)
+        .map(|i| {
+            // The first `extra` shards take one item more.
+            let len = size + usize::from(i < extra);
+            items.by_ref().take(len).collect()
+        })
+        .collect()
+}
@@ -0,0 +1,130 @@
+//! Tests of the ClickHouse loader, against a mock ClickHouse HTTP interface.
+#![cfg(feature = "clickhouse")]
+mod common;
+
+use skopje::load::clickhouse::ClickHouseLoadExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+#[derive(skopje::SqlMap, skopje::SqlTypes)]
+struct Price {
+    symbol: String,
+    close: Option<f64>,
+    volume: i64,
+}
+
+/// Accept each INSERT, answering `200 OK`, and pass on its (unchunked) body.
+async fn mock_clickhouse() -> (
+    clickhouse::Client,
+    mpsc::UnboundedReceiver<(String, String)>,
+) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = common::read_request(&mut stream).await;
+            while !request.ends_with(b"0\r\n\r\n") {
+                let mut buf = [0; 1024];
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let request = String::from_utf8(request).unwrap();
+            let (head, chunked) = request.split_once("\r\n\r\n").unwrap();
+            tx.send((head.to_string(), unchunk(chunked))).unwrap();
+            stream.write_all(common::ok("").as_bytes()).await.unwrap();
+        }
+    });
+    let client = clickhouse::Client::default()
+        .with_url(url)
+        .with_compression(clickhouse::Compression::None);
+    (client, rx)
+}
+
+/// Decode a chunked HTTP body.
+fn unchunk(mut chunked: &str) -> String {
+    let mut body = String::new();
+    loop {
+        let (size, rest) = chunked.split_once("\r\n").unwrap();
+        let size = usize::from_str_radix(size, 16).unwrap();
+        if size == 0 {
+            return body;
+        }
+        body.push_str(&rest[..size]);
+        chunked = &rest[size + 2..];
+    }
+}
+
+fn prices() -> Vec<Price> {
+    vec![
+        Price {
+            symbol: "AAPL".to_string(),
+            close: Some(1.5),
+            volume: 10,
+        },
+        Price {
+            symbol: "MSFT".to_string(),
+            close: None,
+            volume: 20,
+        },
+        Price {
+            symbol: "IBM".to_string(),
+            close: Some(3.0),
+            volume: 30,
+        },
+    ]
+}
+
+#[tokio::test]
+async fn insert_rows_maps_columns_by_sql_map() {
+    let (client, mut inserts) = mock_clickhouse().await;
+
+    client
+        .insert_rows("prices", &["symbol", "close", "volume"], prices().iter())
+        .await
+        .unwrap();
+
+    let (head, body) = inserts.recv().await.unwrap();
+    assert!(head.contains("JSONEachRow"), "{head}");
+    assert_eq!(
+        body,
+        "{\"symbol\":\"AAPL\",\"close\":1.5,\"volume\":10}\n\
+         {\"symbol\":\"MSFT\",\"close\":null,\"volume\":20}\n\
+         {\"symbol\":\"IBM\",\"close\":3.0,\"volume\":30}\n"
+    );
+}
+
+#[tokio::test]
+async fn insert_batched_sends_a_statement_per_batch() {
+    let (client, mut inserts) = mock_clickhouse().await;
+
+    client
+        .insert_batched("prices", &["symbol", "close", "volume"], prices().iter(), 2)
+        .await
+        .unwrap();
+
+    let (_, first) = inserts.recv().await.unwrap();
+    let (_, second) = inserts.recv().await.unwrap();
+    assert_eq!(first.lines().count(), 2);
+    assert_eq!(second.lines().count(), 1);
+    assert!(second.contains("IBM"));
+}
+
+#[tokio::test]
+async fn insert_rows_checks_the_column_count() {
+    let client = clickhouse::Client::default().with_url("http://127.0.0.1:9");
+
+    let err = client
+        .insert_rows("prices", &["symbol", "close"], prices().iter())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        skopje::Error::ColumnCount {
+            expected: 2,
+            found: 3
+        }
+    ));
+}
@@ -0,0 +1,77 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Serve each of `responses`, a raw HTTP response, to one connection in turn; returns the base
+/// URL of the server.
+pub async fn serve(responses: Vec<String>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        for response in responses {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            read_request(&mut stream).await;
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    url
+}
+
+/// A raw `200 OK` response, with `body`.
+pub fn ok(body: &str) -> String {
+    format!(
+        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+/// A raw response of `status`, e.g. `500 Internal Server Error`, with `body`.
+pub fn status(status: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+/// Read a request's head, up to the blank line.
+pub async fn read_request(stream: &mut TcpStream) -> Vec<u8> {
+    let mut request = Vec::new();
+    while !request.ends_with(b"\r\n\r\n") {
+        let mut buf = [0; 1024];
+        let n = stream.read(&mut buf).await.unwrap();
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    request
+}
+
+/// A pool over the Postgres given by `SKOPJE_TEST_PG`, as a connection string, e.g.
+/// `host=127.0.0.1 user=postgres`; or `None` when it's unset, and the test is skipped.
+pub fn pg_pool() -> Option<skopje::PgPool> {
+    let Ok(config) = std::env::var("SKOPJE_TEST_PG") else {
+        eprintln!("SKOPJE_TEST_PG is unset - skipping");
+        return None;
+    };
+    let config: tokio_postgres::Config = config.parse().expect("invalid SKOPJE_TEST_PG");
+    let manager = deadpool_postgres::Manager::new(config, tokio_postgres::NoTls);
+    Some(
+        deadpool_postgres::Pool::builder(manager)
+            .max_size(8)
+            .build()
+            .unwrap(),
+    )
+}
+
+/// A table name unique to this run, so that tests (and runs) never share tables.
+pub fn table_name(prefix: &str) -> String {
+    format!("{prefix}_{}", uuid::Uuid::new_v4().simple())
+}
+
+/// Run a batch of SQL statements on the pool, e.g. to create the fixtures of a test.
+pub async fn execute(pool: &skopje::PgPool, sql: &str) {
+    pool.get().await.unwrap().batch_execute(sql).await.unwrap();
+}
@@ -0,0 +1,74 @@
+use skopje::extract::fs::{read_csv_from, read_file};
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Side {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+struct Trade {
+    account: String,
+    price: f64,
+    quantity: Option<u32>,
+    side: Side,
+    settled: bool,
+}
+
+const TRADES: &[u8] = b"account,price,quantity,side,settled\n\
+    007,1.5,10,buy,true\n\
+    1e3,2,,sell,false\n";
+
+#[tokio::test]
+async fn csv_cells_are_read_as_their_field_types() {
+    let trades: Vec<Trade> = read_csv_from(TRADES).await.unwrap();
+    assert_eq!(
+        trades,
+        vec![
+            Trade {
+                account: "007".to_string(), // not the number 7
+                price: 1.5,
+                quantity: Some(10),
+                side: Side::Buy,
+                settled: true,
+            },
+            Trade {
+                account: "1e3".to_string(), // not the number 1000
+                price: 2.0,
+                quantity: None,
+                side: Side::Sell,
+                settled: false,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn csv_files_keep_text_cells_as_text() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("trades.csv");
+    std::fs::write(&path, TRADES).unwrap();
+
+    let trades: Vec<Trade> = read_file(path.to_str().unwrap()).await.unwrap();
+    assert_eq!(trades[0].account, "007"); // not the number 7
+    assert_eq!(trades[1].side, Side::Sell);
+    assert_eq!(trades[1].quantity, None);
+
+    // Loosely-typed rows read every cell as text.
+    let rows: Vec<std::collections::HashMap<String, String>> =
+        read_file(path.to_str().unwrap()).await.unwrap();
+    assert_eq!(rows[0]["account"], "007");
+    assert_eq!(rows[1]["quantity"], "");
+}
+
+#[tokio::test]
+async fn csv_cells_that_dont_parse_are_a_deserialize_error() {
+    let err = read_csv_from::<Vec<Trade>, _>(
+        &b"account,price,quantity,side,settled\n007,abc,1,buy,true\n"[..],
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err, skopje::Error::Deserialize(_)), "{err}");
+    assert!(err.to_string().contains("\"abc\""), "{err}");
+}
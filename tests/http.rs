@@ -0,0 +1,195 @@
+mod common;
+
+use skopje::extract::http::DownloadRegistry;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[tokio::test]
+async fn shutdown_removes_part_file_and_keeps_previous_download() {
+    // A server which sends the start of a (chunked) body, then stalls.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/data.csv", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        common::read_request(&mut stream).await;
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n")
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_secs(60)).await;
+    });
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.csv");
+    let part = dir.path().join("data.csv.part");
+    std::fs::write(&path, "previous download").unwrap();
+
+    let downloads = DownloadRegistry::new();
+    downloads.spawn(&skopje::HttpClient::new(), &url, path.to_str().unwrap());
+    while !part.exists() {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    downloads.shutdown(Duration::from_millis(50)).await.unwrap();
+    assert!(!part.exists());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "previous download");
+}
+
+#[tokio::test]
+async fn invalid_json_is_a_deserialize_error() {
+    use skopje::extract::http::HttpExtractExt;
+
+    let url = common::serve(vec![
+        common::ok(r#"{"symbol": "AAPL""#),
+        common::ok("[1, 2]"),
+    ])
+    .await;
+    let client = skopje::HttpClient::new();
+
+    let err = client.fetch::<serde_json::Value>(&url).await.unwrap_err();
+    assert!(matches!(err, skopje::Error::Deserialize(_)), "{err:?}");
+
+    // valid JSON, of the wrong shape
+    let err = client.fetch_with_meta::<String>(&url).await.unwrap_err();
+    assert!(matches!(err, skopje::Error::Deserialize(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn cached_fetch_hits_the_network_once() {
+    use skopje::extract::http::CachedHttpClient;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // A server counting its requests, answering each with the count so far.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/symbols", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+    let served = requests.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            common::read_request(&mut stream).await;
+            let count = served.fetch_add(1, Ordering::SeqCst) + 1;
+            let response = common::ok(&count.to_string());
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    let client = CachedHttpClient::new(skopje::HttpClient::new());
+    let ttl = Duration::from_secs(60);
+    for _ in 0..3 {
+        assert_eq!(client.fetch_cached::<usize>(&url, ttl).await.unwrap(), 1);
+    }
+    // clones share the cache
+    let clone = client.clone();
+    assert_eq!(clone.fetch_cached::<usize>(&url, ttl).await.unwrap(), 1);
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    // an expired, or invalidated, response is fetched again
+    assert_eq!(
+        client
+            .fetch_cached::<usize>(&url, Duration::ZERO)
+            .await
+            .unwrap(),
+        2
+    );
+    client.invalidate(&url);
+    assert_eq!(clone.fetch_cached::<usize>(&url, ttl).await.unwrap(), 3);
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn error_statuses_are_http_errors() {
+    use skopje::extract::http::HttpExtractExt;
+
+    // An error page that would parse, were its status ignored.
+    let error_page = || common::status("500 Internal Server Error", "[]");
+    let client = skopje::HttpClient::new();
+
+    let url = common::serve(vec![error_page()]).await;
+    let err = client.fetch_relaxed::<Vec<u32>>(&url).await.unwrap_err();
+    assert!(matches!(err, skopje::Error::Http(_)), "{err:?}");
+
+    let url = common::serve(vec![common::status("404 Not Found", "<h1>Not Found</h1>")]).await;
+    let err = client.fetch_text(&url).await.unwrap_err();
+    assert!(matches!(err, skopje::Error::Http(_)), "{err:?}");
+
+    // A rejected form, answered once the whole request is read.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/token", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        while !request.ends_with(b"grant_type=client_credentials") {
+            let mut buf = [0; 1024];
+            let n = stream.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+        }
+        let response = common::status("401 Unauthorized", r#"{"error": "invalid_client"}"#);
+        stream.write_all(response.as_bytes()).await.unwrap();
+    });
+    let form = [("grant_type", "client_credentials")];
+    let err = client
+        .fetch_post_form::<serde_json::Value, _>(&url, &form)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, skopje::Error::Http(_)), "{err:?}");
+
+    // An empty body decodes as an (empty) message.
+    let url = common::serve(vec![common::status("503 Service Unavailable", "")]).await;
+    let err = client.fetch_protobuf::<String>(&url).await.unwrap_err();
+    assert!(matches!(err, skopje::Error::Http(_)), "{err:?}");
+
+    // Nor is an error page archived, to be replayed later.
+    let archive = tempfile::tempdir().unwrap();
+    let archive_dir = archive.path().to_str().unwrap();
+    let url = common::serve(vec![error_page()]).await;
+    let err = client
+        .fetch_archived::<Vec<u32>>(&url, archive_dir)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, skopje::Error::Http(_)), "{err:?}");
+    assert!(
+        skopje::extract::fs::replay_archived::<Vec<u32>>(archive_dir, &url)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn truncated_conditional_download_keeps_previous_file() {
+    use skopje::extract::http::HttpExtractExt;
+
+    // A body cut off short of its Content-Length, by a dropped connection.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/bulk.csv", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        common::read_request(&mut stream).await;
+        stream
+            .write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\nConnection: close\r\n\r\na,b\n1,2",
+            )
+            .await
+            .unwrap();
+    });
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("bulk.csv");
+    std::fs::write(&path, "previous download").unwrap();
+
+    let client = skopje::HttpClient::new();
+    let err = client
+        .download_file_if_modified(&url, path.to_str().unwrap())
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            skopje::Error::Http(_) | skopje::Error::IncompleteDownload { .. }
+        ),
+        "{err:?}"
+    );
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "previous download");
+}
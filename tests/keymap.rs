@@ -0,0 +1,65 @@
+use bimap::BiMap;
+use skopje::keymap::{BoundedKeyMap, KeyMap};
+
+#[test]
+fn bounded_keymap_never_reallocates_an_evicted_key() {
+    // Keys 1, 3 & 4 are free to allocate; 2 & 5 are evicted before the next key reaches them.
+    let bimap: BiMap<i8, String> = [(0, "a"), (2, "c"), (5, "f")]
+        .into_iter()
+        .map(|(key, value)| (key, value.to_string()))
+        .collect();
+    let mut evicted = Vec::new();
+    let mut bounded = BoundedKeyMap::new(KeyMap::from(bimap), 2, |key, _| evicted.push(key));
+
+    let keys: Vec<i8> = ["b", "d", "e", "g", "h"]
+        .into_iter()
+        .map(|value| bounded.transact(value.to_string()))
+        .collect();
+    drop(bounded);
+
+    // 0, 2 & 5 were evicted (silently, as already persisted), and 1, 3 & 6 after them; but none
+    // handed out again.
+    assert_eq!(evicted, vec![1, 3, 6]);
+    assert_eq!(keys, vec![1, 3, 6, 7, 8]);
+}
+
+#[test]
+fn bounded_keymap_loads_an_evicted_value_with_its_key() {
+    // The persisted table, whose entries are trimmed from memory straight away.
+    let table: BiMap<i8, String> = [(0, "a"), (1, "b"), (2, "c")]
+        .into_iter()
+        .map(|(key, value)| (key, value.to_string()))
+        .collect();
+    let mut flushed: Vec<(i8, String)> = Vec::new();
+    let mut bounded = BoundedKeyMap::new(KeyMap::from(table.clone()), 1, |key, value| {
+        flushed.push((key, value))
+    })
+    .with_loader(|value: &String| table.get_by_right(value).copied());
+    assert_eq!(bounded.keymap.bimap.len(), 1);
+
+    // Persisted values keep their keys, while new ones are allocated above them.
+    assert_eq!(bounded.transact("a".to_string()), 0);
+    assert_eq!(bounded.transact("d".to_string()), 3);
+    assert_eq!(bounded.transact("b".to_string()), 1);
+    assert_eq!(bounded.transact("e".to_string()), 4);
+    assert_eq!(bounded.keymap.pending_keys(), &[4]);
+    drop(bounded);
+
+    // Only the new entries are flushed, once evicted.
+    assert_eq!(flushed, vec![(3, "d".to_string())]);
+}
+
+#[test]
+#[should_panic(expected = "keyspace exhausted")]
+fn bounded_keymap_is_exhausted_once_the_maximum_key_is_evicted() {
+    let bimap: BiMap<i8, String> = BiMap::from_iter([(i8::MAX, "max".to_string())]);
+    let mut bounded = BoundedKeyMap::new(KeyMap::from(bimap), 1, |_, _| {});
+
+    bounded.transact("a".to_string()); // 0, evicting the maximum key
+    assert!(matches!(
+        bounded.try_transact("b".to_string()),
+        Err(skopje::Error::KeyspaceExhausted)
+    ));
+    assert_eq!(bounded.try_transact("a".to_string()).unwrap(), 0); // existing values are found
+    bounded.transact("b".to_string());
+}
@@ -0,0 +1,8 @@
+//! Compile tests of the derive & attribute macros: what expands, and the errors for what doesn't.
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/*-pass.rs");
+    t.compile_fail("tests/ui/*-fail.rs");
+}
@@ -0,0 +1,903 @@
+//! Tests against a real Postgres, given by `SKOPJE_TEST_PG`; skipped when it's unset.
+mod common;
+
+use bimap::BiMap;
+use skopje::extract::pg::PgExtractExt;
+use skopje::load::pg::{PgLoadExt, copy_columns, is_foreign_table, resolve_type};
+use skopje::{Error, KeyMap};
+
+#[derive(skopje::SqlMap, skopje::SqlTypes)]
+struct Price {
+    #[sql(skip)]
+    id: i64,
+    symbol: String,
+    close: f64,
+}
+
+fn prices(symbols: &[&str]) -> Vec<Price> {
+    symbols
+        .iter()
+        .enumerate()
+        .map(|(i, symbol)| Price {
+            id: 0,
+            symbol: symbol.to_string(),
+            close: i as f64,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn copy_skips_identity_column() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let table = common::table_name("prices");
+    common::execute(
+        &pool,
+        &format!(
+            "CREATE TABLE {table} (id BIGINT GENERATED ALWAYS AS IDENTITY, symbol TEXT, close FLOAT8)"
+        ),
+    )
+    .await;
+
+    let rows = prices(&["AAPL", "MSFT"]);
+    (&pool)
+        .copy(&copy_columns(&table, &["symbol", "close"]), rows.iter())
+        .await
+        .unwrap();
+
+    let ids: Vec<(i64, String)> = (&pool)
+        .fetch_collection_all(
+            &format!("SELECT id, symbol FROM {table} ORDER BY id"),
+            |row| (row.get(0), row.get(1)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![(1, "AAPL".to_string()), (2, "MSFT".to_string())]);
+    assert!(rows.iter().all(|price| price.id == 0));
+
+    // Without a column list, the COPY would need a value for the identity column too.
+    let err = (&pool)
+        .copy(&format!("COPY {table} FROM STDIN BINARY"), rows.iter())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::ColumnCount {
+            expected: 3,
+            found: 2
+        }
+    ));
+
+    // Nor may the list name more columns than each row has values.
+    let err = (&pool)
+        .copy(
+            &copy_columns(&table, &["id", "symbol", "close"]),
+            rows.iter(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::ColumnCount {
+            expected: 3,
+            found: 2
+        }
+    ));
+}
+
+#[tokio::test]
+async fn copy_fails_on_a_row_write_error() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let table = common::table_name("prices");
+    common::execute(
+        &pool,
+        &format!("CREATE TABLE {table} (symbol TEXT, close FLOAT8)"),
+    )
+    .await;
+
+    // The row can't be encoded into the (runtime) types.
+    let err = (&pool)
+        .copy_with_types(
+            &copy_columns(&table, &["symbol", "close"]),
+            &[skopje::Type::TEXT, skopje::Type::INT4],
+            prices(&["AAPL"]).iter(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Postgres(_)));
+
+    let count: i64 = (&pool)
+        .fetch_if_exists(&format!("SELECT count(*) FROM {table}"), &[])
+        .await
+        .unwrap();
+    assert_eq!(count, 0); // nothing is committed
+}
+
+#[derive(skopje::SqlMap, skopje::SqlTypes)]
+struct User {
+    id: i32,
+    #[sql(type_name = "citext")]
+    email: String,
+}
+
+#[tokio::test]
+async fn copy_resolves_citext_by_name() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let table = common::table_name("users");
+    common::execute(&pool, "CREATE EXTENSION IF NOT EXISTS citext").await;
+    common::execute(
+        &pool,
+        &format!("CREATE TABLE {table} (id INT4, email CITEXT)"),
+    )
+    .await;
+
+    // The derived type is resolved against the pool, as the rows are copied.
+    let users = [User {
+        id: 1,
+        email: "Ana@Example.com".to_string(),
+    }];
+    (&pool)
+        .copy(&copy_columns(&table, &["id", "email"]), users.iter())
+        .await
+        .unwrap();
+
+    // ... or resolved up-front, and given at runtime.
+    let citext = resolve_type(&pool, "citext").await.unwrap();
+    assert_eq!(citext.name(), "citext");
+    let users = [User {
+        id: 2,
+        email: "Bo@Example.com".to_string(),
+    }];
+    (&pool)
+        .copy_with_types(
+            &copy_columns(&table, &["id", "email"]),
+            &[skopje::Type::INT4, citext],
+            users.iter(),
+        )
+        .await
+        .unwrap();
+
+    let ids: Vec<i32> = (&pool)
+        .fetch_collection_all(
+            &format!("SELECT id FROM {table} WHERE email IN ('ana@example.com', 'BO@EXAMPLE.COM') ORDER BY id"),
+            |row| row.get(0),
+        )
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![1, 2]);
+}
+
+#[tokio::test]
+async fn resolve_type_binds_the_name() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+
+    // Not spliced into the SQL, so it can only fail to name a type.
+    let err = resolve_type(&pool, "int4; DROP TABLE pg_type")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Postgres(_)));
+}
+
+#[derive(Debug, PartialEq, postgres_types::ToSql, postgres_types::FromSql)]
+#[postgres(name = "skopje_test_mood", rename_all = "snake_case")]
+enum Mood {
+    Happy,
+    Sad,
+}
+
+#[derive(skopje::SqlMap, skopje::SqlTypes)]
+struct Person {
+    name: String,
+    #[sql(type_name = "skopje_test_mood")]
+    mood: Mood,
+}
+
+#[tokio::test]
+async fn copy_resolves_an_enum_by_name() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let table = common::table_name("people");
+    common::execute(
+        &pool,
+        "DO $$ BEGIN CREATE TYPE skopje_test_mood AS ENUM ('happy', 'sad'); \
+         EXCEPTION WHEN duplicate_object THEN NULL; END $$",
+    )
+    .await;
+    common::execute(
+        &pool,
+        &format!("CREATE TABLE {table} (name TEXT, mood skopje_test_mood)"),
+    )
+    .await;
+
+    let people = [
+        Person {
+            name: "Ana".to_string(),
+            mood: Mood::Happy,
+        },
+        Person {
+            name: "Bo".to_string(),
+            mood: Mood::Sad,
+        },
+    ];
+    (&pool)
+        .copy(&copy_columns(&table, &["name", "mood"]), people.iter())
+        .await
+        .unwrap();
+
+    let moods: Vec<Mood> = (&pool)
+        .fetch_collection_all(&format!("SELECT mood FROM {table} ORDER BY name"), |row| {
+            row.get(0)
+        })
+        .await
+        .unwrap();
+    assert_eq!(moods, vec![Mood::Happy, Mood::Sad]);
+}
+
+#[derive(skopje::SqlMap, skopje::SqlTypes)]
+struct Tick {
+    stock_id: i32,
+    close: f64,
+}
+
+#[tokio::test]
+async fn flush_with_rolls_back_keys_with_failed_rows() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let stocks = common::table_name("stocks");
+    let ticks = common::table_name("ticks");
+    common::execute(
+        &pool,
+        &format!(
+            "CREATE TABLE {stocks} (id INT4 PRIMARY KEY, symbol TEXT); \
+             CREATE TABLE {ticks} (stock_id INT4 REFERENCES {stocks}, close FLOAT8 CHECK (close >= 0))"
+        ),
+    )
+    .await;
+    let key_insert = format!("INSERT INTO {stocks} (id, symbol) VALUES ($1, $2)");
+    let data_copy = copy_columns(&ticks, &["stock_id", "close"]);
+
+    let mut keymap: KeyMap<i32, String> = KeyMap::from(BiMap::new());
+    let rows = [
+        Tick {
+            stock_id: keymap.transact("AAPL".to_string()),
+            close: 1.0,
+        },
+        Tick {
+            stock_id: keymap.transact("MSFT".to_string()),
+            close: -1.0,
+        },
+    ];
+
+    // The second row breaks the CHECK; so neither key, nor either row, is persisted.
+    let err = keymap
+        .flush_with(&pool, &key_insert, &data_copy, rows.iter())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Postgres(_)));
+    assert_eq!(keymap.pending_keys(), &[0, 1]);
+    let count = |table: String| {
+        let pool = pool.clone();
+        async move {
+            (&pool)
+                .fetch_if_exists::<i64>(&format!("SELECT count(*) FROM {table}"), &[])
+                .await
+                .unwrap()
+        }
+    };
+    assert_eq!(count(stocks.clone()).await, 0);
+    assert_eq!(count(ticks.clone()).await, 0);
+
+    // Flushed again, the still-pending keys go in with the fixed rows, and are drained.
+    let rows = [Tick {
+        stock_id: 1,
+        close: 1.0,
+    }];
+    keymap
+        .flush_with(&pool, &key_insert, &data_copy, rows.iter())
+        .await
+        .unwrap();
+    assert!(keymap.pending_keys().is_empty());
+    assert_eq!(count(stocks).await, 2);
+    assert_eq!(count(ticks).await, 1);
+}
+
+#[tokio::test]
+async fn restore_refuses_a_snapshot_from_before_a_flush() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let stocks = common::table_name("stocks");
+    let ticks = common::table_name("ticks");
+    common::execute(
+        &pool,
+        &format!(
+            "CREATE TABLE {stocks} (id INT4 PRIMARY KEY, symbol TEXT); \
+             CREATE TABLE {ticks} (stock_id INT4, close FLOAT8)"
+        ),
+    )
+    .await;
+
+    let mut keymap: KeyMap<i32, String> = KeyMap::from(BiMap::new());
+    let snapshot = keymap.snapshot();
+    let rows = [Tick {
+        stock_id: keymap.transact("AAPL".to_string()),
+        close: 1.0,
+    }];
+    keymap
+        .flush_with(
+            &pool,
+            &format!("INSERT INTO {stocks} (id, symbol) VALUES ($1, $2)"),
+            &copy_columns(&ticks, &["stock_id", "close"]),
+            rows.iter(),
+        )
+        .await
+        .unwrap();
+
+    // Key 0 is persisted now; restoring would allocate it again.
+    assert!(matches!(
+        keymap.restore(snapshot),
+        Err(Error::StaleSnapshot)
+    ));
+    assert_eq!(keymap.see_next_key(), &1);
+    assert!(keymap.pending_keys().is_empty());
+}
+
+#[tokio::test]
+async fn pg_fetch_where_returns_query_errors() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let stocks = common::table_name("stocks");
+    common::execute(
+        &pool,
+        &format!(
+            "CREATE TABLE {stocks} (id INT4, symbol TEXT, exchange TEXT); \
+             INSERT INTO {stocks} VALUES (0, 'AAPL', 'NASDAQ'), (1, 'IBM', 'NYSE')"
+        ),
+    )
+    .await;
+
+    let keymap: KeyMap<i32, String> = KeyMap::pg_fetch_where(
+        &pool,
+        &format!("SELECT id, symbol FROM {stocks} WHERE exchange = $1"),
+        &[&"NYSE"],
+    )
+    .await
+    .unwrap();
+    assert_eq!(keymap.bimap.get_by_right("IBM"), Some(&1));
+    assert_eq!(keymap.bimap.len(), 1);
+
+    // a failing query is an error, rather than a panic
+    let err = KeyMap::<i32, String>::pg_fetch_where(
+        &pool,
+        &format!("SELECT id, symbol FROM {stocks} WHERE exchange = $1"),
+        &[&1_i32],
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err, Error::Postgres(_)));
+}
+
+#[tokio::test]
+async fn pg_fetch_rejects_rows_breaking_the_bijection() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let stocks = common::table_name("stocks");
+    common::execute(
+        &pool,
+        &format!(
+            "CREATE TABLE {stocks} (id INT4, symbol TEXT); \
+             INSERT INTO {stocks} VALUES (0, 'AAPL'), (1, 'AAPL')"
+        ),
+    )
+    .await;
+
+    let err = KeyMap::<i32, String>::pg_fetch(&pool, &format!("SELECT id, symbol FROM {stocks}"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::BijectionViolation(_)));
+}
+
+#[derive(skopje::SqlMap, skopje::SqlTypes, skopje::FromRow)]
+struct Quote {
+    symbol: String,
+    #[sql(rename = "close_price")]
+    close: f64,
+    #[sql(skip)]
+    note: String,
+}
+
+#[tokio::test]
+async fn from_row_reads_renamed_and_skipped_fields() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let table = common::table_name("quotes");
+    common::execute(
+        &pool,
+        &format!("CREATE TABLE {table} (symbol TEXT, close_price FLOAT8)"),
+    )
+    .await;
+
+    let quotes = [Quote {
+        symbol: "AAPL".to_string(),
+        close: 1.5,
+        note: "not stored".to_string(),
+    }];
+    (&pool)
+        .copy(
+            &copy_columns(&table, &["symbol", "close_price"]),
+            quotes.iter(),
+        )
+        .await
+        .unwrap();
+
+    let quote: Quote = (&pool)
+        .fetch_row_as(&format!("SELECT symbol, close_price FROM {table}"), &[])
+        .await
+        .unwrap();
+    assert_eq!(quote.symbol, "AAPL");
+    assert_eq!(quote.close, 1.5);
+    assert_eq!(quote.note, "");
+}
+
+async fn index_exists(pool: &skopje::PgPool, index: &str) -> bool {
+    pool.fetch_if_exists::<bool>(
+        "SELECT EXISTS (SELECT 1 FROM pg_indexes WHERE indexname = $1)",
+        &[&index],
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn with_deferred_indexes_drops_and_recreates_indexes() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let table = common::table_name("prices");
+    let index = format!("{table}_symbol_idx");
+    let def = format!("CREATE INDEX {index} ON {table} (symbol)");
+    common::execute(
+        &pool,
+        &format!("CREATE TABLE {table} (symbol TEXT, close FLOAT8); {def}"),
+    )
+    .await;
+
+    let indexed_during_load = (&pool)
+        .with_deferred_indexes(&table, &[(&index, &def)], || async {
+            (&pool)
+                .copy(
+                    &copy_columns(&table, &["symbol", "close"]),
+                    prices(&["AAPL"]).iter(),
+                )
+                .await?;
+            Ok(index_exists(&pool, &index).await)
+        })
+        .await
+        .unwrap();
+    assert!(!indexed_during_load);
+    assert!(index_exists(&pool, &index).await);
+
+    // A failed load still has its indexes recreated, and returns its own error, even when the
+    // recreate fails too.
+    let err = (&pool)
+        .with_deferred_indexes(&table, &[(&index, &def)], || async {
+            Err::<(), _>(Error::Unsupported("load failed".to_string()))
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Unsupported(_)));
+    assert!(index_exists(&pool, &index).await);
+
+    let broken = format!("CREATE INDEX {index} ON {table} (no_such_column)");
+    let err = (&pool)
+        .with_deferred_indexes(&table, &[(&index, &broken)], || async {
+            Err::<(), _>(Error::Unsupported("load failed".to_string()))
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Unsupported(_)));
+
+    // ... while a successful load returns the recreate's error.
+    let err = (&pool)
+        .with_deferred_indexes(&table, &[(&index, &broken)], || async { Ok(()) })
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Postgres(_)));
+}
+
+#[tokio::test]
+async fn concurrent_copy_staged_loads_never_clash() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let table = common::table_name("prices");
+    common::execute(
+        &pool,
+        &format!("CREATE TABLE {table} (symbol TEXT PRIMARY KEY, close FLOAT8)"),
+    )
+    .await;
+
+    // Overlapping batches, staged at once, each in its own staging table.
+    let batches = [
+        prices(&["AAPL", "MSFT", "NVDA"]),
+        prices(&["MSFT", "NVDA", "AMZN"]),
+        prices(&["NVDA", "AMZN", "META"]),
+        prices(&["GOOG", "TSLA", "AAPL"]),
+    ];
+    let merge = "ON CONFLICT (symbol) DO NOTHING";
+    let target = &pool;
+    let moved = futures::future::try_join_all(
+        batches
+            .iter()
+            .map(|batch| target.copy_staged(&table, merge, batch.iter())),
+    )
+    .await
+    .unwrap();
+    assert_eq!(moved.iter().sum::<u64>(), 7); // each symbol moved by one of the loads
+
+    let symbols: Vec<String> = (&pool)
+        .fetch_collection_all(
+            &format!("SELECT symbol FROM {table} ORDER BY symbol"),
+            |row| row.get(0),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        symbols,
+        vec!["AAPL", "AMZN", "GOOG", "META", "MSFT", "NVDA", "TSLA"]
+    );
+
+    // The staging tables went with their transactions.
+    let staging: i64 = (&pool)
+        .fetch_if_exists(
+            "SELECT count(*) FROM pg_class WHERE relname LIKE 'skopje_staging_%'",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(staging, 0);
+}
+
+#[tokio::test]
+async fn idempotent_load_skips_a_batch_loaded_before() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let table = common::table_name("prices");
+    common::execute(
+        &pool,
+        &format!("CREATE TABLE {table} (symbol TEXT, close FLOAT8)"),
+    )
+    .await;
+    let stmt = copy_columns(&table, &["symbol", "close"]);
+    let key = format!("{table}/2025-01-31");
+    let rows = prices(&["AAPL", "MSFT"]);
+
+    assert!(
+        (&pool)
+            .idempotent_load(&key, &stmt, rows.iter())
+            .await
+            .unwrap()
+    );
+    // a retry of the same batch
+    assert!(
+        !(&pool)
+            .idempotent_load(&key, &stmt, rows.iter())
+            .await
+            .unwrap()
+    );
+
+    let count: i64 = (&pool)
+        .fetch_if_exists(&format!("SELECT count(*) FROM {table}"), &[])
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+
+    // A batch that fails to load leaves no marker, so it can be retried.
+    let failed = format!("{table}/2025-02-01");
+    let err = (&pool)
+        .idempotent_load(
+            &failed,
+            &format!("COPY {table} (symbol) FROM STDIN BINARY"),
+            rows.iter(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::ColumnCount { .. }), "{err:?}");
+    assert!(
+        (&pool)
+            .idempotent_load(&failed, &stmt, rows.iter())
+            .await
+            .unwrap()
+    );
+
+    // Of two concurrent loads of one batch, only one loads it.
+    let concurrent = format!("{table}/2025-02-02");
+    let target = &pool;
+    let (a, b) = futures::try_join!(
+        target.idempotent_load(&concurrent, &stmt, rows.iter()),
+        target.idempotent_load(&concurrent, &stmt, rows.iter()),
+    )
+    .unwrap();
+    assert!(a ^ b);
+
+    let count: i64 = (&pool)
+        .fetch_if_exists(&format!("SELECT count(*) FROM {table}"), &[])
+        .await
+        .unwrap();
+    assert_eq!(count, 6);
+}
+
+#[tokio::test]
+async fn fetch_keyset_reads_every_row_once_in_pages() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let table = common::table_name("prices");
+    // Ids with gaps, and a filter in the base statement.
+    common::execute(
+        &pool,
+        &format!(
+            "CREATE TABLE {table} (id BIGINT PRIMARY KEY, symbol TEXT); \
+             INSERT INTO {table} SELECT i * 3, CASE WHEN i % 5 = 0 THEN 'MSFT' ELSE 'AAPL' END \
+             FROM generate_series(1, 25) AS i"
+        ),
+    )
+    .await;
+
+    // Page sizes dividing the 20 matching rows, not dividing them, and larger than them.
+    for page_size in [3, 4, 20, 100] {
+        let ids: Vec<i64> = (&pool)
+            .fetch_keyset::<_, i64, _, _>(
+                &format!("SELECT id, symbol FROM {table} WHERE symbol = 'AAPL'"),
+                "id",
+                page_size,
+                |row| row.get(0),
+            )
+            .await
+            .unwrap();
+        let expected: Vec<i64> = (1..=25).filter(|i| i % 5 != 0).map(|i| i * 3).collect();
+        assert_eq!(ids, expected, "page size {page_size}");
+    }
+
+    // An empty result is no pages at all.
+    let none: Vec<i64> = (&pool)
+        .fetch_keyset::<_, i64, _, _>(
+            &format!("SELECT id FROM {table} WHERE symbol = 'NVDA'"),
+            "id",
+            4,
+            |row| row.get(0),
+        )
+        .await
+        .unwrap();
+    assert!(none.is_empty());
+}
+
+#[tokio::test]
+async fn copy_into_inserts_into_a_foreign_table() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let client = pool.get().await.unwrap();
+    if let Err(e) = client
+        .batch_execute("CREATE EXTENSION IF NOT EXISTS postgres_fdw")
+        .await
+    {
+        eprintln!("postgres_fdw is unavailable - skipping: {e}");
+        return;
+    }
+
+    // A foreign table over a local one, by a loopback server to this same database.
+    let row = client
+        .query_one(
+            "SELECT current_database(), current_user, \
+             COALESCE(host(inet_server_addr()), '127.0.0.1'), inet_server_port()",
+            &[],
+        )
+        .await
+        .unwrap();
+    let (database, user, host, port): (String, String, String, i32) =
+        (row.get(0), row.get(1), row.get(2), row.get(3));
+    drop(client);
+    let server = common::table_name("loopback");
+    let table = common::table_name("prices");
+    let remote = common::table_name("remote_prices");
+    common::execute(
+        &pool,
+        &format!(
+            "CREATE TABLE {table} (symbol TEXT, close FLOAT8); \
+             CREATE SERVER {server} FOREIGN DATA WRAPPER postgres_fdw \
+             OPTIONS (host '{host}', port '{port}', dbname '{database}'); \
+             CREATE USER MAPPING FOR CURRENT_USER SERVER {server} OPTIONS (user '{user}'); \
+             CREATE FOREIGN TABLE {remote} (symbol TEXT, close FLOAT8) \
+             SERVER {server} OPTIONS (table_name '{table}')"
+        ),
+    )
+    .await;
+
+    assert!(is_foreign_table(&pool, &remote).await.unwrap());
+    assert!(!is_foreign_table(&pool, &table).await.unwrap());
+
+    let columns = ["symbol", "close"];
+    (&pool)
+        .copy_into(&remote, &columns, prices(&["AAPL", "MSFT"]).iter())
+        .await
+        .unwrap();
+    (&pool)
+        .copy_into(&table, &columns, prices(&["NVDA"]).iter())
+        .await
+        .unwrap();
+
+    let symbols: Vec<String> = (&pool)
+        .fetch_collection_all(
+            &format!("SELECT symbol FROM {table} ORDER BY symbol"),
+            |row| row.get(0),
+        )
+        .await
+        .unwrap();
+    assert_eq!(symbols, vec!["AAPL", "MSFT", "NVDA"]);
+
+    common::execute(&pool, &format!("DROP SERVER {server} CASCADE")).await;
+}
+
+#[tokio::test]
+async fn concurrent_insert_ordered_upserts_dont_deadlock() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let table = common::table_name("prices");
+    common::execute(
+        &pool,
+        &format!("CREATE TABLE {table} (symbol TEXT PRIMARY KEY, close FLOAT8)"),
+    )
+    .await;
+    let stmt = format!(
+        "INSERT INTO {table} (symbol, close) VALUES ($1, $2) \
+         ON CONFLICT (symbol) DO UPDATE SET close = EXCLUDED.close"
+    );
+    let symbols: Vec<String> = (0..200).map(|i| format!("S{i:03}")).collect();
+    let symbols: Vec<&str> = symbols.iter().map(String::as_str).collect();
+    let rows = prices(&symbols);
+
+    // Loaders upserting the same rows in opposite orders, which would each lock rows the other
+    // then waits on, were they inserted as given.
+    let target = &pool;
+    let by_symbol = |price: &&Price| price.symbol.clone();
+    for _ in 0..5 {
+        futures::try_join!(
+            target.insert_ordered(&stmt, rows.iter(), by_symbol),
+            target.insert_ordered(&stmt, rows.iter().rev(), by_symbol),
+            target.insert_ordered(&stmt, rows.iter().step_by(2).chain(rows.iter()), by_symbol),
+        )
+        .unwrap();
+    }
+
+    let count: i64 = (&pool)
+        .fetch_if_exists(&format!("SELECT count(*) FROM {table}"), &[])
+        .await
+        .unwrap();
+    assert_eq!(count, 200);
+}
+
+#[tokio::test]
+async fn insert_verified_reports_rows_that_didnt_arrive() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let table = common::table_name("prices");
+    common::execute(
+        &pool,
+        &format!("CREATE TABLE {table} (symbol TEXT PRIMARY KEY, close FLOAT8)"),
+    )
+    .await;
+    let stmt =
+        format!("INSERT INTO {table} (symbol, close) VALUES ($1, $2) ON CONFLICT DO NOTHING");
+    let count_stmt = format!("SELECT count(*) FROM {table}");
+
+    (&pool)
+        .insert_verified(&stmt, &count_stmt, prices(&["AAPL", "MSFT"]).iter())
+        .await
+        .unwrap();
+
+    // Two of the three rows are silently dropped as conflicts.
+    let err = (&pool)
+        .insert_verified(&stmt, &count_stmt, prices(&["AAPL", "MSFT", "NVDA"]).iter())
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            Error::LoadVerification {
+                expected: 3,
+                counted: 1
+            }
+        ),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn fetch_collection_any_binds_the_ids_as_one_array() {
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let table = common::table_name("prices");
+    common::execute(
+        &pool,
+        &format!(
+            "CREATE TABLE {table} (id BIGINT PRIMARY KEY, symbol TEXT); \
+             INSERT INTO {table} VALUES (1, 'AAPL'), (2, 'MSFT'), (3, 'NVDA'), (4, 'AMZN')"
+        ),
+    )
+    .await;
+    let stmt = format!("SELECT id, symbol FROM {table} WHERE id = ANY($1) ORDER BY id");
+
+    // The same statement, for any number of ids; unknown ids match nothing.
+    for (ids, expected) in [
+        (vec![3_i64], vec![3]),
+        (vec![4, 1, 3, 99], vec![1, 3, 4]),
+        (vec![], vec![]),
+    ] {
+        let rows: Vec<(i64, String)> = (&pool)
+            .fetch_collection_any(&stmt, &ids, |row| (row.get(0), row.get(1)))
+            .await
+            .unwrap();
+        let found: Vec<i64> = rows.iter().map(|(id, _)| *id).collect();
+        assert_eq!(found, expected, "ids {ids:?}");
+    }
+
+    // Text ids bind as a text array, in the same way.
+    let symbols: Vec<String> = (&pool)
+        .fetch_collection_any(
+            &format!("SELECT symbol FROM {table} WHERE symbol = ANY($1) ORDER BY symbol"),
+            &["NVDA", "AAPL"],
+            |row| row.get(0),
+        )
+        .await
+        .unwrap();
+    assert_eq!(symbols, vec!["AAPL", "NVDA"]);
+}
+
+#[tokio::test]
+async fn copy_from_stream_copies_all_or_nothing() {
+    use futures::StreamExt;
+
+    let Some(pool) = common::pg_pool() else {
+        return;
+    };
+    let table = common::table_name("prices");
+    common::execute(
+        &pool,
+        &format!("CREATE TABLE {table} (symbol TEXT, close FLOAT8)"),
+    )
+    .await;
+    let stmt = copy_columns(&table, &["symbol", "close"]);
+
+    let rows = futures::stream::iter(prices(&["AAPL", "MSFT"]).into_iter().map(Ok));
+    assert_eq!((&pool).copy_from_stream(&stmt, rows).await.unwrap(), 2);
+
+    // A stream failing part-way aborts the whole COPY.
+    let rows = futures::stream::iter(prices(&["NVDA"]).into_iter().map(Ok)).chain(
+        futures::stream::once(async { Err(Error::Unsupported("extract failed".to_string())) }),
+    );
+    let err = (&pool).copy_from_stream(&stmt, rows).await.unwrap_err();
+    assert!(matches!(err, Error::Unsupported(_)), "{err:?}");
+
+    let count: i64 = (&pool)
+        .fetch_if_exists(&format!("SELECT count(*) FROM {table}"), &[])
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+}
@@ -0,0 +1,19 @@
+use futures::StreamExt;
+use futures::stream;
+use skopje::transform;
+use std::time::Duration;
+
+#[tokio::test]
+async fn pipe_returns_a_load_error_while_the_source_is_stalled() {
+    // One row, then a source which never yields again.
+    let rows = stream::iter(vec![Ok::<_, skopje::Error>(1)]).chain(stream::pending());
+    let failing_load = |_row: i32| async { Err(skopje::Error::KeyspaceExhausted) };
+
+    let result = tokio::time::timeout(
+        Duration::from_secs(5),
+        transform::pipe(rows, failing_load, 4),
+    )
+    .await
+    .expect("pipe hung on the stalled source");
+    assert!(matches!(result, Err(skopje::Error::KeyspaceExhausted)));
+}
@@ -0,0 +1,4 @@
+#[skopje::extract()]
+struct Symbols(Vec<String>);
+
+fn main() {}
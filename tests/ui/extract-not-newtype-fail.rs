@@ -0,0 +1,6 @@
+#[skopje::extract(url = "https://example.com/symbols")]
+struct Symbols {
+    symbols: Vec<String>,
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+// A URL, or path, can be an expression evaluated on each extract, not only a literal.
+use skopje::etl::Extract;
+
+fn base_url() -> String {
+    "https://example.com".to_string()
+}
+
+#[skopje::extract(urls = [
+    "https://example.com/symbols?page=1",
+    format!("{}/symbols?page=2", base_url()),
+])]
+struct Symbols(Vec<String>);
+
+#[skopje::extract(method = FS_READ, path = std::env::temp_dir().join("symbols.json").display().to_string())]
+struct CachedSymbols(Vec<String>);
+
+fn main() {
+    fn client_of<T: Extract<Client = C>, C>() {}
+    client_of::<Symbols, skopje::HttpClient>();
+    client_of::<CachedSymbols, ()>();
+}
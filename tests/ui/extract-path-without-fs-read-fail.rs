@@ -0,0 +1,4 @@
+#[skopje::extract(url = "https://example.com/symbols", path = "symbols.json")]
+struct Symbols(Vec<String>);
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[skopje::extract(method = HTTP_POST, url = "https://example.com/symbols")]
+struct Symbols(Vec<String>);
+
+fn main() {}
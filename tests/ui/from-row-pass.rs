@@ -0,0 +1,28 @@
+use skopje::extract::pg::FromRow;
+
+#[derive(skopje::FromRow)]
+struct Close {
+    #[sql(rename = "close_price")]
+    close: f64,
+}
+
+#[derive(skopje::FromRow)]
+struct Quote {
+    symbol: String,
+    #[sql(flatten)]
+    close: Close,
+    #[sql(skip)]
+    note: Option<String>,
+}
+
+// skipped fields take no column position
+#[derive(skopje::FromRow)]
+struct Pair(String, #[sql(skip)] u8, f64);
+
+fn read(row: &skopje::extract::pg::Row) -> skopje::Result<(Quote, Pair)> {
+    Ok((Quote::from_row(row)?, Pair::from_row(row)?))
+}
+
+fn main() {
+    let _ = read;
+}
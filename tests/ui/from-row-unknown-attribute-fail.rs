@@ -0,0 +1,7 @@
+#[derive(skopje::FromRow)]
+struct Quote {
+    #[sql(column = "close_price")]
+    close: f64,
+}
+
+fn main() {}
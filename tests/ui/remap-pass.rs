@@ -0,0 +1,17 @@
+// The expansion names everything through `skopje`, so needs no `serde_json` dependency of its own.
+use skopje::transform::Remap;
+
+#[derive(skopje::Remap)]
+struct Price {
+    #[remap(from = "t")]
+    symbol: String,
+    #[remap(from = "/quote/close")]
+    close: Option<f64>,
+}
+
+fn main() {
+    let value = skopje::serde_json::json!({"t": "AAPL", "quote": {"close": 1.5}});
+    let price = Price::remap(&value).unwrap();
+    assert_eq!(price.symbol, "AAPL");
+    assert_eq!(price.close, Some(1.5));
+}
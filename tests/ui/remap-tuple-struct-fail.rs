@@ -0,0 +1,4 @@
+#[derive(skopje::Remap)]
+struct Price(String, f64);
+
+fn main() {}
@@ -0,0 +1,7 @@
+#[derive(skopje::Remap)]
+struct Price {
+    #[remap(into = "t")]
+    symbol: String,
+}
+
+fn main() {}